@@ -0,0 +1,46 @@
+-- Chain and token registry read by the sweeper workers (src/chain_config/registry.rs).
+-- Rows are hot-reloaded every sweep cycle, so networks and tokens can be added without a redeploy.
+
+CREATE TABLE IF NOT EXISTS chains (
+    id            SERIAL PRIMARY KEY,
+    name          TEXT        NOT NULL UNIQUE,
+    chain_id      BIGINT      NOT NULL,
+    rpc_urls      JSONB       NOT NULL DEFAULT '[]'::jsonb,
+    confirmations INTEGER     NOT NULL DEFAULT 12,
+    native_symbol VARCHAR     NOT NULL,
+    enabled       BOOLEAN     NOT NULL DEFAULT TRUE,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at    TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS tokens (
+    id         SERIAL PRIMARY KEY,
+    chain      TEXT        NOT NULL REFERENCES chains (name) ON UPDATE CASCADE ON DELETE RESTRICT,
+    symbol     VARCHAR     NOT NULL,
+    address    VARCHAR     NOT NULL,
+    decimals   SMALLINT    NOT NULL,
+    enabled    BOOLEAN     NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (chain, address)
+);
+
+-- Networks previously hardcoded in CHAIN_RPC / TOKENS. Keyed RPC URLs belong in the
+-- database, not in source; add them with UPDATE chains SET rpc_urls = ... per environment.
+INSERT INTO chains (name, chain_id, rpc_urls, confirmations, native_symbol, enabled) VALUES
+    ('base_sepolia',      84532,   '["https://sepolia.base.org"]',              10, 'ETH', TRUE),
+    ('bitlayer_testnet',  200810,  '["https://testnet-rpc.bitlayer.org"]',      20, 'BTC', TRUE),
+    ('base_mainnet',      8453,    '["https://mainnet.base.org"]',              10, 'ETH', FALSE),
+    ('ethereum_mainnet',  1,       '["https://eth.llamarpc.com"]',              12, 'ETH', FALSE),
+    ('bnb_mainnet',       56,      '["https://bsc-dataseed.bnbchain.org"]',     15, 'BNB', FALSE)
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO tokens (chain, symbol, address, decimals, enabled) VALUES
+    ('base_sepolia',     'USDC', '0x6E5C7663971Be425B4726D7ba90456B935bb95ce', 6,  TRUE),
+    ('base_sepolia',     'USDT', '0xB72FDb9f8190D8e1141e6a8e9c0732b0f4d93c09', 6,  TRUE),
+    ('base_mainnet',     'USDC', '0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913', 6,  FALSE),
+    ('bnb_mainnet',      'USDC', '0x8AC76a51cc950d9822D68b83fE1Ad97B32Cd580d', 18, FALSE),
+    ('bnb_mainnet',      'USDT', '0x55d398326f99059fF775485246999027B3197955', 18, FALSE),
+    ('ethereum_mainnet', 'USDC', '0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48', 6,  FALSE),
+    ('ethereum_mainnet', 'USDT', '0xdAC17F958D2ee523a2206206994597C13D831ec7', 6,  FALSE)
+ON CONFLICT (chain, address) DO NOTHING;
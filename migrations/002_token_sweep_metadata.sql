@@ -0,0 +1,9 @@
+-- Per-token sweep behaviour used by the generic sweep routine (sweep_token in src/jobs/sweeper.rs).
+
+ALTER TABLE tokens
+    ADD COLUMN IF NOT EXISTS min_sweep_amount      NUMERIC(78, 18) NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS transfer_returns_bool BOOLEAN         NOT NULL DEFAULT TRUE;
+
+-- Tether on Ethereum predates the final ERC-20 spec and its transfer() returns nothing.
+UPDATE tokens SET transfer_returns_bool = FALSE
+WHERE chain = 'ethereum_mainnet' AND address = '0xdAC17F958D2ee523a2206206994597C13D831ec7';
@@ -0,0 +1,21 @@
+-- Sweeps waiting for enough confirmations before they are credited to user_balance.
+-- Lifecycle: PENDING -> CREDITED -> FINALIZED, or PENDING -> REORGED / CREDITED -> REVERSED
+-- when the sweep's block leaves the canonical chain (see src/jobs/confirmations.rs).
+
+CREATE TABLE IF NOT EXISTS pending_deposit (
+    id             UUID PRIMARY KEY,
+    user_id        UUID            NOT NULL REFERENCES app_user (id) ON UPDATE RESTRICT ON DELETE RESTRICT,
+    wallet_address VARCHAR         NOT NULL,
+    token          VARCHAR         NOT NULL,
+    chain          VARCHAR         NOT NULL,
+    amount         NUMERIC(78, 18) NOT NULL,
+    tx_hash        TEXT            NOT NULL UNIQUE,
+    block_number   BIGINT          NOT NULL,
+    block_hash     TEXT            NOT NULL,
+    status         TEXT            NOT NULL DEFAULT 'PENDING',
+    credited_at    TIMESTAMPTZ,
+    updated_at     TIMESTAMPTZ     NOT NULL DEFAULT now(),
+    created_at     TIMESTAMPTZ     NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS pending_deposit_chain_status_idx ON pending_deposit (chain, status);
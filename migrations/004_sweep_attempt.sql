@@ -0,0 +1,22 @@
+-- Outbox of sweep transactions. A row is written with the signed raw transaction
+-- before it is broadcast, so a crash between broadcast and commit can be reconciled
+-- against chain receipts on the next start (src/jobs/recovery.rs).
+-- States: SIGNED -> BROADCAST -> MINED, or FAILED (reverted) / DROPPED (nonce reused).
+
+CREATE TABLE IF NOT EXISTS sweep_attempt (
+    id             UUID PRIMARY KEY,
+    user_id        UUID            NOT NULL,
+    wallet_id      UUID            NOT NULL REFERENCES user_wallet (id) ON UPDATE RESTRICT ON DELETE RESTRICT,
+    wallet_address VARCHAR         NOT NULL,
+    chain          VARCHAR         NOT NULL,
+    token          VARCHAR         NOT NULL,
+    amount         NUMERIC(78, 18) NOT NULL,
+    nonce          BIGINT          NOT NULL,
+    raw_tx         TEXT            NOT NULL,
+    tx_hash        TEXT            NOT NULL UNIQUE,
+    state          TEXT            NOT NULL DEFAULT 'SIGNED',
+    updated_at     TIMESTAMPTZ     NOT NULL DEFAULT now(),
+    created_at     TIMESTAMPTZ     NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS sweep_attempt_state_idx ON sweep_attempt (state);
@@ -0,0 +1,21 @@
+-- Every user_wallet.status change goes through utils::wallet_lifecycle::transition_wallet,
+-- which rejects illegal moves and appends a row here.
+-- user_wallet.status stays TEXT; valid values are the WalletStatus string values:
+-- ASSIGNED, AWAITING_DEPOSIT, SWEEPABLE, GAS_PENDING, SWEEP_IN_PROGRESS, CONFIRMING, FREE, QUARANTINED, FAILED.
+
+CREATE TABLE IF NOT EXISTS wallet_status_audit (
+    id          UUID PRIMARY KEY,
+    wallet_id   UUID        NOT NULL REFERENCES user_wallet (id) ON UPDATE RESTRICT ON DELETE RESTRICT,
+    from_status TEXT        NOT NULL,
+    to_status   TEXT        NOT NULL,
+    reason      TEXT        NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS wallet_status_audit_wallet_idx ON wallet_status_audit (wallet_id, created_at);
+
+ALTER TABLE user_wallet DROP CONSTRAINT IF EXISTS user_wallet_status_check;
+ALTER TABLE user_wallet ADD CONSTRAINT user_wallet_status_check CHECK (status IN (
+    'ASSIGNED', 'AWAITING_DEPOSIT', 'SWEEPABLE', 'GAS_PENDING', 'SWEEP_IN_PROGRESS',
+    'CONFIRMING', 'FREE', 'QUARANTINED', 'FAILED'
+));
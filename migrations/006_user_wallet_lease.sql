@@ -0,0 +1,9 @@
+-- Lease on wallets claimed by a sweeper worker. A wallet in SWEEP_IN_PROGRESS whose
+-- lease has expired belongs to a dead or stuck worker and is picked up by the reaper
+-- (src/jobs/reaper.rs).
+
+ALTER TABLE user_wallet
+    ADD COLUMN IF NOT EXISTS claimed_by       TEXT,
+    ADD COLUMN IF NOT EXISTS lease_expires_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS user_wallet_lease_idx ON user_wallet (status, lease_expires_at);
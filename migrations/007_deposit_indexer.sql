@@ -0,0 +1,31 @@
+-- Deposit indexer state (src/jobs/indexer.rs).
+-- indexer_cursor holds the last block whose Transfer logs were scanned on each chain.
+-- detected_deposit holds every registered-token Transfer into a user wallet.
+-- Status: QUEUED (wallet was marked SWEEPABLE) or DEFERRED (wallet was busy, e.g.
+-- mid-sweep; retried every indexer cycle until it can be queued).
+
+CREATE TABLE IF NOT EXISTS indexer_cursor (
+    chain        VARCHAR     PRIMARY KEY,
+    last_block   BIGINT      NOT NULL,
+    updated_at   TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS detected_deposit (
+    id             UUID PRIMARY KEY,
+    user_id        UUID            NOT NULL,
+    wallet_id      UUID            NOT NULL REFERENCES user_wallet (id) ON UPDATE RESTRICT ON DELETE RESTRICT,
+    wallet_address VARCHAR         NOT NULL,
+    chain          VARCHAR         NOT NULL,
+    token          VARCHAR         NOT NULL,
+    from_address   VARCHAR         NOT NULL,
+    amount         NUMERIC(78, 18) NOT NULL,
+    tx_hash        TEXT            NOT NULL,
+    log_index      BIGINT          NOT NULL,
+    block_number   BIGINT          NOT NULL,
+    block_hash     TEXT            NOT NULL,
+    status         TEXT            NOT NULL DEFAULT 'QUEUED',
+    created_at     TIMESTAMPTZ     NOT NULL DEFAULT now(),
+    UNIQUE (chain, tx_hash, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS detected_deposit_status_idx ON detected_deposit (status);
@@ -0,0 +1,13 @@
+-- Per-chain settings of the Multicall3 balance scanner (src/utils/balance_scanner.rs).
+-- multicall3_address: Multicall3 deployment used to batch balance reads; NULL makes the
+-- sweeper read balances wallet by wallet instead.
+-- min_native_sweep: native balance (in whole coins) below which a wallet is not worth sweeping.
+
+ALTER TABLE chains
+    ADD COLUMN IF NOT EXISTS multicall3_address VARCHAR,
+    ADD COLUMN IF NOT EXISTS min_native_sweep   NUMERIC(78, 18) NOT NULL DEFAULT 0;
+
+-- Canonical deterministic deployment, present on all of these networks.
+UPDATE chains SET multicall3_address = '0xcA11bde05977b3631167028862bE2a173976CA11'
+WHERE multicall3_address IS NULL
+  AND name IN ('base_sepolia', 'base_mainnet', 'ethereum_mainnet', 'bnb_mainnet');
@@ -0,0 +1,12 @@
+-- On-chain token metadata verified once and reused by every sweep (src/utils/token_metadata.rs).
+-- decimals is what the contract itself returned; the sweeper refuses to sweep a token whose
+-- registry decimals (tokens.decimals) disagree with it.
+
+CREATE TABLE IF NOT EXISTS token_metadata (
+    chain       VARCHAR     NOT NULL,
+    address     VARCHAR     NOT NULL,
+    decimals    SMALLINT    NOT NULL,
+    code_hash   TEXT        NOT NULL,
+    verified_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (chain, address)
+);
@@ -0,0 +1,20 @@
+-- Per-chain fee policy (src/utils/fee_policy.rs).
+-- max_fee_per_gas_gwei: hard cap on max fee per gas; sweeps wait while the base fee is above it.
+-- max_fee_ratio:        refuse sweeps whose worst-case fee exceeds this fraction of the swept value.
+-- rbf_after_secs:       age after which an unmined sweep is replaced with bumped fees.
+-- rbf_bump_percent:     minimum fee increase of a replacement (nodes require at least 10%).
+
+ALTER TABLE chains
+    ADD COLUMN IF NOT EXISTS max_fee_per_gas_gwei NUMERIC(78, 18),
+    ADD COLUMN IF NOT EXISTS max_fee_ratio        NUMERIC(78, 18),
+    ADD COLUMN IF NOT EXISTS rbf_after_secs       INTEGER NOT NULL DEFAULT 600,
+    ADD COLUMN IF NOT EXISTS rbf_bump_percent     INTEGER NOT NULL DEFAULT 20;
+
+-- Approximate value of one token in the chain's native coin, only used for the
+-- fee/value ratio check. NULL skips the check for that token.
+ALTER TABLE tokens
+    ADD COLUMN IF NOT EXISTS native_price NUMERIC(78, 18);
+
+-- Wallets whose sweep was deferred (e.g. fees above policy) are not claimed again before this.
+ALTER TABLE user_wallet
+    ADD COLUMN IF NOT EXISTS next_attempt_at TIMESTAMPTZ;
@@ -0,0 +1,32 @@
+-- Dust handling (src/utils/dust_policy.rs).
+-- Token balances below tokens.min_sweep_amount are never swept on their own; dust_policy
+-- decides what the user sees in the meantime:
+--   ACCUMULATE: the dust stays on the wallet and is credited once later deposits push the
+--               balance past the threshold and it is swept.
+--   CREDIT:     the dust is credited to user_balance straight away and netted out of the
+--               sweep that eventually moves it.
+
+ALTER TABLE tokens
+    ADD COLUMN IF NOT EXISTS dust_policy TEXT NOT NULL DEFAULT 'ACCUMULATE'
+        CHECK (dust_policy IN ('ACCUMULATE', 'CREDIT'));
+
+-- One row per dust decision. balance is the dust seen on the wallet, credited what that
+-- decision added to user_balance. Rows with swept_tx_hash NULL are still on the wallet;
+-- the sweep that moves them fills it in (and a reorg of that sweep clears it again).
+-- Decision: ACCUMULATED or CREDITED.
+
+CREATE TABLE IF NOT EXISTS dust_ledger (
+    id             UUID PRIMARY KEY,
+    user_id        UUID            NOT NULL,
+    wallet_id      UUID            NOT NULL REFERENCES user_wallet (id) ON UPDATE RESTRICT ON DELETE RESTRICT,
+    wallet_address VARCHAR         NOT NULL,
+    chain          VARCHAR         NOT NULL,
+    token          VARCHAR         NOT NULL,
+    balance        NUMERIC(78, 18) NOT NULL,
+    credited       NUMERIC(78, 18) NOT NULL DEFAULT 0,
+    decision       TEXT            NOT NULL,
+    swept_tx_hash  TEXT,
+    created_at     TIMESTAMPTZ     NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS dust_ledger_open_idx ON dust_ledger (wallet_id, chain, token) WHERE swept_tx_hash IS NULL;
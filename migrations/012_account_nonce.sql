@@ -0,0 +1,12 @@
+-- Persistent nonce allocator (src/utils/nonce_allocator.rs).
+-- next_nonce is the next nonce handed out for an address on a chain. Allocation takes the
+-- larger of it and the node's pending transaction count under a row lock, so concurrent
+-- workers and restarted processes never hand out the same nonce twice.
+
+CREATE TABLE IF NOT EXISTS account_nonce (
+    chain       VARCHAR     NOT NULL,
+    address     VARCHAR     NOT NULL,
+    next_nonce  BIGINT      NOT NULL,
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (chain, address)
+);
@@ -0,0 +1,34 @@
+-- Versioned deposit-key derivation (src/chain_config/key_deriver.rs).
+-- key_version 1: HMAC-SHA256 of the user id under WALLET_GENERATION_SECRET; key_index is NULL.
+-- key_version 2: BIP-44 m/44'/60'/0'/0/key_index under WALLET_HD_SEED, indexes drawn from
+-- wallet_key_index_seq and never reused.
+-- The rotate_wallet_keys tool (src/jobs/key_rotation.rs) moves empty v1 wallets to v2 addresses
+-- and records every move in wallet_key_rotation.
+
+ALTER TABLE user_wallet ADD COLUMN IF NOT EXISTS key_version SMALLINT NOT NULL DEFAULT 1;
+ALTER TABLE user_wallet ADD COLUMN IF NOT EXISTS key_index   BIGINT;
+
+ALTER TABLE user_wallet DROP CONSTRAINT IF EXISTS user_wallet_key_index_check;
+ALTER TABLE user_wallet ADD CONSTRAINT user_wallet_key_index_check
+    CHECK (key_version = 1 OR key_index IS NOT NULL);
+
+CREATE UNIQUE INDEX IF NOT EXISTS user_wallet_key_index_idx
+    ON user_wallet (key_version, key_index) WHERE key_index IS NOT NULL;
+
+-- Non-hardened BIP-32 child indexes stop at 2^31 - 1
+CREATE SEQUENCE IF NOT EXISTS wallet_key_index_seq AS BIGINT MINVALUE 0 MAXVALUE 2147483647 START 0;
+
+CREATE TABLE IF NOT EXISTS wallet_key_rotation (
+    id              UUID PRIMARY KEY,
+    wallet_id       UUID        NOT NULL REFERENCES user_wallet (id) ON UPDATE RESTRICT ON DELETE RESTRICT,
+    old_address     TEXT        NOT NULL,
+    old_key_version SMALLINT    NOT NULL,
+    old_key_index   BIGINT,
+    new_address     TEXT        NOT NULL,
+    new_key_version SMALLINT    NOT NULL,
+    new_key_index   BIGINT,
+    rotated_at      TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS wallet_key_rotation_wallet_idx ON wallet_key_rotation (wallet_id, rotated_at);
+CREATE INDEX IF NOT EXISTS wallet_key_rotation_old_address_idx ON wallet_key_rotation (old_address);
@@ -0,0 +1,8 @@
+-- Per-chain treasury allow-list (src/utils/tx_policy.rs).
+-- treasury_addresses: JSON array of the only addresses sweeps on the chain may pay out to.
+-- Every sweep is checked against it before signing; a mismatch is refused and recorded in
+-- suspicious_activities. An empty list refuses every sweep on the chain, and startup fails
+-- while MASTER_WALLET_ADDRESS is missing from the list of an enabled chain.
+
+ALTER TABLE chains
+    ADD COLUMN IF NOT EXISTS treasury_addresses JSONB NOT NULL DEFAULT '[]'::jsonb;
@@ -0,0 +1,26 @@
+-- Hot/cold treasury split (src/utils/treasury_router.rs, src/jobs/treasury_monitor.rs).
+-- Sweeps go to MASTER_WALLET_ADDRESS (the hot wallet) until its balance of the swept asset
+-- reaches admin_limits.vault_limit, then to the chain's cold_wallet_address. Chains without a
+-- cold wallet, and assets without a limit, always sweep to the hot wallet. The cold wallet must
+-- be on the chain's treasury_addresses allow-list.
+--
+-- admin_limits rows are scoped by chain and token (token address, the zero address for the
+-- native coin); NULL matches any. The most specific row wins, the lowest vault_limit among
+-- equally specific ones. Amounts are in whole units of the asset, so unscoped rows only make
+-- sense when every swept asset is worth about the same (e.g. USD stablecoins).
+-- hot_floor: the treasury monitor reports when the hot wallet holds less than this.
+
+ALTER TABLE chains
+    ADD COLUMN IF NOT EXISTS cold_wallet_address TEXT;
+
+CREATE TABLE IF NOT EXISTS admin_limits (
+    id          SERIAL PRIMARY KEY,
+    vault_limit NUMERIC(78, 18) NOT NULL
+);
+
+ALTER TABLE admin_limits
+    ADD COLUMN IF NOT EXISTS chain     TEXT,
+    ADD COLUMN IF NOT EXISTS token     TEXT,
+    ADD COLUMN IF NOT EXISTS hot_floor NUMERIC(78, 18);
+
+CREATE INDEX IF NOT EXISTS admin_limits_scope_idx ON admin_limits (chain, token);
@@ -0,0 +1,20 @@
+-- Withdrawal worker (src/jobs/withdrawals.rs).
+-- The application inserts withdraw_request rows as PENDING; the worker of the request's chain
+-- takes them in order and moves them through:
+--   PENDING    -> REJECTED   invalid request or insufficient user_balance, nothing debited
+--   PENDING    -> PROCESSING user_balance debited, transfer not signed yet
+--   PROCESSING -> SIGNED     raw_tx, tx_hash and nonce stored before the transfer is broadcast
+--   SIGNED     -> BROADCAST  sent to the node
+--   BROADCAST  -> COMPLETED  mined and buried under the chain's confirmations; withdraw_receipt written
+--   any of the three in-flight states -> FAILED: reverted, dropped or unsignable; the debit is refunded
+-- failure_reason says why a request was REJECTED or FAILED. sender is the hot wallet that signed.
+
+ALTER TABLE withdraw_request
+    ADD COLUMN IF NOT EXISTS sender         VARCHAR,
+    ADD COLUMN IF NOT EXISTS nonce          BIGINT,
+    ADD COLUMN IF NOT EXISTS raw_tx         TEXT,
+    ADD COLUMN IF NOT EXISTS tx_hash        TEXT,
+    ADD COLUMN IF NOT EXISTS failure_reason TEXT;
+
+CREATE INDEX IF NOT EXISTS withdraw_request_queue_idx ON withdraw_request (chain, status, created_at);
+CREATE UNIQUE INDEX IF NOT EXISTS withdraw_request_tx_hash_idx ON withdraw_request (tx_hash) WHERE tx_hash IS NOT NULL;
@@ -0,0 +1,7 @@
+-- Withdrawal drop check (src/jobs/withdrawals.rs).
+-- dropped_at_block is the head block at which a SIGNED/BROADCAST withdrawal was first found
+-- unknown to the node while its nonce was already used. The request is only failed and
+-- refunded once it is still unknown enough blocks later, so a lagging RPC endpoint cannot
+-- get a paid-out withdrawal refunded. Cleared as soon as the transfer is seen again.
+
+ALTER TABLE withdraw_request ADD COLUMN IF NOT EXISTS dropped_at_block BIGINT;
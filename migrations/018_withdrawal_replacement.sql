@@ -0,0 +1,6 @@
+-- Withdrawal fee bumps (src/jobs/withdrawals.rs).
+-- A BROADCAST withdrawal unmined after its chain's rbf_after is re-signed with the same nonce
+-- and bumped fees. raw_tx/tx_hash then hold the replacement, and replaced_tx_hashes the
+-- comma-separated hashes it replaced, oldest first: any of them may still be the one mined.
+
+ALTER TABLE withdraw_request ADD COLUMN IF NOT EXISTS replaced_tx_hashes TEXT;
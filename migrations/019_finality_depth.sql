@@ -0,0 +1,7 @@
+-- Finality fallback (src/jobs/confirmations.rs).
+-- finality_depth: on chains whose RPC does not serve the `finalized` block tag, a credited
+-- sweep counts as finalized once it is this many blocks deep. Without it CREDITED sweeps
+-- there would be polled for reorgs forever.
+
+ALTER TABLE chains
+    ADD COLUMN IF NOT EXISTS finality_depth INTEGER NOT NULL DEFAULT 64;
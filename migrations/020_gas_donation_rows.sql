@@ -0,0 +1,8 @@
+-- One gas_donation row per top-up (src/utils/gas_station.rs).
+-- wallet_address was UNIQUE, so repeat top-ups of a wallet were accumulated onto one row
+-- and its created_at moved to the latest one, which mixed earlier days into today's cap.
+-- Each donation now has its own row and the daily cap sums the rows of the day.
+
+ALTER TABLE gas_donation DROP CONSTRAINT IF EXISTS gas_donation_wallet_address_key;
+
+CREATE INDEX IF NOT EXISTS gas_donation_chain_created_idx ON gas_donation (chain, created_at);
@@ -0,0 +1,15 @@
+-- Outbox of nonce gap fillers (src/utils/nonce_allocator.rs).
+-- A nonce allocated but never broadcast blocks every later transaction of the address. The
+-- allocator fills such gaps with zero-value transfers to the master wallet; each filler is
+-- written here before it is broadcast, so a gap found again re-sends the same transaction
+-- instead of signing a second one for the nonce.
+
+CREATE TABLE IF NOT EXISTS nonce_filler (
+    chain       VARCHAR     NOT NULL,
+    address     VARCHAR     NOT NULL,
+    nonce       BIGINT      NOT NULL,
+    raw_tx      TEXT        NOT NULL,
+    tx_hash     TEXT        NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (chain, address, nonce)
+);
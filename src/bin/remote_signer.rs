@@ -0,0 +1,48 @@
+//! Stand-in signer service: holds the deposit-wallet secrets so the sweeper does not have to.
+//!
+//! Reads `WALLET_GENERATION_SECRET` and/or `WALLET_HD_SEED`, `MASTER_WALLET_ADDRESS`
+//! and the optional comma-separated `COLD_WALLET_ADDRESSES` (the only recipients it
+//! signs sweeps to; list every chain's `cold_wallet_address`), the optional
+//! `SIGNER_AUTH_TOKEN`, and listens on `SIGNER_BIND` (defaults to `127.0.0.1:7070`). Point the sweeper's
+//! `SIGNER_URL` at it and leave the secrets out of the sweeper's environment.
+
+use std::{env, net::TcpListener};
+
+use avitus_casino_sweeper::{
+    chain_config::{key_deriver::KeyDerivers, remote_signer::{SignerService, run_signer_service}, signing_policy::SigningPolicy},
+    config::config::{parse_master_address, parse_treasury_addresses},
+    error::error::AppError,
+};
+use dotenv::dotenv;
+
+
+#[actix_web::main]
+async fn main() -> Result<(), AppError> {
+    dotenv().ok();
+
+    let derivers = KeyDerivers::from_secrets(
+        env::var("WALLET_GENERATION_SECRET").ok().as_deref(),
+        env::var("WALLET_HD_SEED").ok().as_deref(),
+    )?;
+
+    let master = parse_master_address(&env::var("MASTER_WALLET_ADDRESS")
+        .map_err(|e| AppError::ConfigError(format!("MASTER_WALLET_ADDRESS not set: {}", e)))?)?;
+
+    let cold_wallets = parse_treasury_addresses("COLD_WALLET_ADDRESSES", &env::var("COLD_WALLET_ADDRESSES").unwrap_or_default())?;
+    let policy = SigningPolicy::new(master, cold_wallets);
+
+    let bind = env::var("SIGNER_BIND").unwrap_or_else(|_| "127.0.0.1:7070".to_string());
+    let listener = TcpListener::bind(&bind)
+        .map_err(|e| AppError::ConfigError(format!("Cannot bind signer to {}: {}", bind, e)))?;
+
+    println!("Signer listening on {}, signing sweeps to {:?}", bind, policy.treasuries());
+
+    let service = SignerService {
+        derivers,
+        policy,
+        auth_token: env::var("SIGNER_AUTH_TOKEN").ok(),
+    };
+
+    run_signer_service(listener, service).await
+        .map_err(|e| AppError::InternalError(format!("Signer stopped: {}", e)))
+}
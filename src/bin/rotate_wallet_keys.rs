@@ -0,0 +1,73 @@
+//! Moves deposit wallets off older key versions onto the current one.
+//!
+//! ```text
+//! rotate_wallet_keys [--dry-run] [--limit N] [--idle-days N] [--report PATH]
+//! ```
+//!
+//! Only `FREE` wallets whose status has not changed for `--idle-days` (default
+//! 30) are rotated, so addresses users were recently given stay in place.
+//! Wallets still holding sweepable funds are queued for the sweeper and left on
+//! their old key; run the tool again once those sweeps are finalized. The report
+//! (default `wallet_key_rotation.json`) lists every wallet checked.
+
+use std::env;
+
+use chrono::Duration;
+
+use avitus_casino_sweeper::{
+    chain_config::registry::reload_registry, db::connection::init_db, error::error::AppError, jobs::key_rotation::rotate_wallet_keys,
+};
+
+const DEFAULT_IDLE_DAYS: i64 = 30;
+
+
+#[tokio::main]
+async fn main() -> Result<(), AppError> {
+
+    let mut dry_run = false;
+    let mut limit = None;
+    let mut idle_days = DEFAULT_IDLE_DAYS;
+    let mut report_path = "wallet_key_rotation.json".to_string();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--limit" => {
+                limit = Some(args.next().and_then(|raw| raw.parse().ok())
+                    .ok_or_else(|| AppError::ConfigError("--limit takes a number of wallets".into()))?);
+            }
+            "--idle-days" => {
+                idle_days = args.next().and_then(|raw| raw.parse().ok()).filter(|days| *days >= 0)
+                    .ok_or_else(|| AppError::ConfigError("--idle-days takes a number of days".into()))?;
+            }
+            "--report" => {
+                report_path = args.next().ok_or_else(|| AppError::ConfigError("--report takes a file path".into()))?;
+            }
+            other => return Err(AppError::ConfigError(format!("Unknown argument {}", other))),
+        }
+    }
+
+    let db = init_db().await
+        .map_err(|e| AppError::InternalError(format!("DB init error: {}", e)))?;
+
+    reload_registry(&db).await?;
+
+    let report = rotate_wallet_keys(&db, dry_run, limit, Duration::days(idle_days)).await?;
+
+    let rotated = report.wallets.iter().filter(|wallet| wallet.new_address.is_some()).count();
+    let queued = report.wallets.iter().filter(|wallet| wallet.queued_on.is_some()).count();
+    let failed = report.wallets.iter().filter(|wallet| wallet.error.is_some()).count();
+
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| AppError::InternalError(format!("Cannot serialize rotation report: {e}")))?;
+    tokio::fs::write(&report_path, json).await
+        .map_err(|e| AppError::InternalError(format!("Cannot write {}: {e}", report_path)))?;
+
+    println!(
+        "{} wallets checked{}: {} rotated, {} queued for sweeping, {} failed. Report written to {}",
+        report.wallets.len(), if dry_run { " (dry run)" } else { "" }, rotated, queued, failed, report_path
+    );
+
+    Ok(())
+}
@@ -1,90 +1,161 @@
 
-use std::collections::HashMap;
-
-use alloy::primitives::FixedBytes;
-use alloy::providers::{Provider, ProviderBuilder};
-use uuid::Uuid;
-use crate::config::config::AppConfig;
+use alloy::primitives::Address;
+use alloy::providers::{Provider, ProviderBuilder, RootProvider};
 use crate::state_models::models::ProviderConnection;
 use crate::error::error::AppError;
-use once_cell::sync::Lazy;
-use sha2::{ Sha256};
-use hmac::{Hmac, Mac};
-use alloy_signer_local::LocalSigner;
-type HmacSha256 = Hmac<Sha256>;
+use crate::chain_config::{key_deriver::{WalletKey, wallet_signer}, registry::registry, remote_signer::RemoteSigner, rpc_pool::{RpcPool, failover_client, rpc_pool}, signer_registry::SignerRegistry};
+use crate::config::config::AppConfig;
+use crate::state_models::models::SignerProvider;
+use once_cell::sync::{Lazy, OnceCell};
+use std::{collections::HashMap, sync::{Arc, RwLock}};
+use alloy_signer_local::PrivateKeySigner;
 
-fn uuid_to_seed(uuid: Uuid, secret: &[u8]) -> FixedBytes<32> {
-    let mut mac = HmacSha256::new_from_slice(secret)
-        .expect("HMAC can take key of any size");
 
-    mac.update(uuid.as_bytes());
+/// Signers of the wallets the process used recently, shared by all cached providers.
+static SIGNERS: Lazy<SignerRegistry> = Lazy::new(SignerRegistry::default);
 
-    let result = mac.finalize().into_bytes();
+/// Signer service of the process, `None` when deposit keys are derived locally.
+static REMOTE_SIGNER: OnceCell<Option<Arc<RemoteSigner>>> = OnceCell::new();
 
-    let bytes: [u8; 32] = result
-        .as_slice()
-        .try_into()
-        .expect("HMAC-SHA256 output is 32 bytes");
+/// Providers of each chain, rebuilt when the chain's RPC pool is replaced.
+static PROVIDERS: Lazy<RwLock<HashMap<String, ChainProviders>>> = Lazy::new(|| RwLock::new(HashMap::new()));
 
-    FixedBytes::from(bytes)
+
+/// The signing and read-only providers of a chain, sharing one failover RPC client.
+#[derive(Clone)]
+struct ChainProviders {
+    pool: Arc<RpcPool>,
+    signer: SignerProvider,
+    read: RootProvider,
 }
 
 
+fn remote_signer() -> Result<Option<Arc<RemoteSigner>>, AppError> {
+    REMOTE_SIGNER
+        .get_or_try_init(|| {
+            let config = AppConfig::from_env()?;
+            Ok(config.signer_url.map(|url| Arc::new(RemoteSigner::new(&url, config.signer_auth_token))))
+        })
+        .cloned()
+}
 
 
-pub static CHAIN_RPC : Lazy<HashMap<String , Vec< &'static str>>>= Lazy ::new(||{
-    let mut map = HashMap::new();
 
-    map.insert("bitlayer_testnet".to_string() , vec!["https://testnet-rpc.bitlayer.org"]);
-    map.insert("base_sepolia".to_string(), vec!["https://base-sepolia.g.alchemy.com/v2/XcjcviYCCB6UB3T5uwSk1dIIA-sbrA-p"]);
-    map.insert("base_mainnet".to_string(), vec!["https://base-mainnet.g.alchemy.com/v2/je8NBeGlxHuC1m6VCHB93"]);
-    map.insert("test_base_mainnet".to_string(), vec!["https://go.getblock.asia/399e2b8bd3fa44f3a9d05d3390ae43e2"]);
-    map.insert("umi_devnet".to_string(), vec!["https://devnet.uminetwork.com/evm"]);
-    map
-});
+/// Returns the provider of `chain` able to sign for the deposit wallet of `key`.
+///
+/// With a signer service configured the key stays in the service: only the
+/// wallet's address is fetched (once per process) and signing is delegated.
+pub async fn create_provider(chain: &str, key: &WalletKey) -> Result<ProviderConnection, AppError> {
 
+    let Some(remote) = remote_signer()? else {
+        return connect_with_signer(chain, wallet_signer(key)?).await;
+    };
 
-pub async fn create_provider(chain: &String , user_id: Uuid) -> Result<ProviderConnection, AppError> {
+    let providers = chain_providers(chain).await?;
 
-    let wallet_generation_secret = AppConfig::from_env().unwrap().wallet_generation_secret;
-        
-    let secret = hex::decode(wallet_generation_secret.trim())
-            .map_err(|_| AppError::InternalError
-                ("Invalid WALLET_GENERATION_SECRET".into()))?;
+    if SIGNERS.remote_address(key).is_none() {
+        let address = remote.address(key).await?;
+        SIGNERS.register_remote(address, remote, *key);
+    }
 
-    let seed = uuid_to_seed(user_id, &secret);
+    Ok(ProviderConnection(providers.signer))
+}
 
-    let signer = LocalSigner::from_bytes(&seed)
-            .map_err(|_| AppError::InternalError("Signer creation failed".into()))?;
 
-    let rpc_list = CHAIN_RPC
-        .get(chain)
-        .ok_or_else(|| AppError::InternalError(format!("No RPCs found for chain {}", chain)))?;
+/// Address of the deposit wallet of `key`, asking the signer service when one is configured.
+pub async fn wallet_address(key: &WalletKey) -> Result<Address, AppError> {
 
+    match remote_signer()? {
+        Some(remote) => remote.address(key).await,
+        None => Ok(wallet_signer(key)?.address()),
+    }
+}
+
+
+/// Returns the cached provider of `chain` with `signer` registered on it.
+///
+/// Used for deposit wallets via `create_provider` and for operator keys such as
+/// the gas funder. The provider signs for every registered key, so transactions
+/// must set `from` to the signer's address.
+pub async fn connect_with_signer(chain: &str, signer: PrivateKeySigner) -> Result<ProviderConnection, AppError> {
+
+    let providers = chain_providers(chain).await?;
+    SIGNERS.register(signer);
+
+    Ok(ProviderConnection(providers.signer))
+}
 
-    for rpc in rpc_list {
 
-        if let Ok(rpc_url) = rpc.parse() {
 
-            let provider = ProviderBuilder::new()
-                .with_cached_nonce_management()
-                .wallet(signer.clone())
-                .connect_http(rpc_url);
+/// Returns the cached read-only provider of `chain`, for jobs that only read chain state.
+pub async fn create_read_provider(chain: &str) -> Result<RootProvider, AppError> {
+    Ok(chain_providers(chain).await?.read)
+}
+
 
-            match provider.get_chain_id().await {
-                Ok(_) => {
-                    // println!("✅ Connected to RPC: {}", rpc);
-                    return Ok(ProviderConnection(provider));
-                }
-                Err(err) => {
-                    eprintln!("⚠️ Failed to connect to RPC {}: {}", rpc, err);
-                    continue;
-                }
-            }
-        }
+
+/// Looks up the providers of `chain`, building them on first use.
+///
+/// Building verifies the chain id once; after that, getting a provider costs
+/// no RPC call and no new connection, however many wallets are swept.
+async fn chain_providers(chain: &str) -> Result<ChainProviders, AppError> {
+
+    let registry = registry();
+    let chain = registry
+        .chain(chain)
+        .ok_or_else(|| AppError::InternalError(format!("No RPCs found for chain {}", chain)))?;
+
+    let pool = rpc_pool(chain);
+
+    if let Some(cached) = PROVIDERS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(&chain.name)
+        .filter(|cached| Arc::ptr_eq(&cached.pool, &pool))
+    {
+        return Ok(cached.clone());
     }
 
-    Err(AppError::InternalError("All RPC endpoints failed".to_string()))
+    let client = failover_client(pool.clone());
+
+    // Sweeps and gas top-ups take their nonces from the persistent allocator
+    // (utils::nonce_allocator); the filler only covers transactions sent without
+    // one, reading the pending count instead of caching nonces that may never be broadcast
+    let signer = ProviderBuilder::new()
+        .disable_recommended_fillers()
+        .with_gas_estimation()
+        .with_simple_nonce_management()
+        .with_chain_id(chain.chain_id)
+        .wallet(SIGNERS.clone())
+        .connect_client(client.clone());
+
+    verify_chain_id(&signer, chain.chain_id, &chain.name).await?;
+
+    let providers = ChainProviders { pool, signer, read: RootProvider::new(client) };
+
+    PROVIDERS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(chain.name.clone(), providers.clone());
+
+    Ok(providers)
 }
 
 
+
+/// Fails unless the RPC behind `provider` serves chain `expected`.
+async fn verify_chain_id<P: Provider>(provider: &P, expected: u64, chain: &str) -> Result<(), AppError> {
+
+    let chain_id = provider.get_chain_id().await.map_err(|e| {
+        eprintln!("⚠️ All RPC endpoints failed for {}: {}", chain, e);
+        AppError::InternalError("All RPC endpoints failed".to_string())
+    })?;
+
+    if chain_id != expected {
+        return Err(AppError::InternalError(format!(
+            "RPC for {} reports chain id {} instead of {}", chain, chain_id, expected
+        )));
+    }
+
+    Ok(())
+}
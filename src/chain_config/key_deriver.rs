@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use alloy::primitives::B256;
+use alloy_signer_local::PrivateKeySigner;
+use hmac::{Hmac, Mac};
+use k256::{
+    FieldBytes, NonZeroScalar, PublicKey, Scalar,
+    elliptic_curve::{PrimeField, sec1::ToEncodedPoint},
+};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use sea_orm::{ConnectionTrait, DbBackend, Statement};
+use sha2::{Sha256, Sha512};
+use uuid::Uuid;
+
+use crate::{config::config::AppConfig, entities::user_wallet, error::error::AppError};
+
+type HmacSha256 = Hmac<Sha256>;
+type HmacSha512 = Hmac<Sha512>;
+
+/// `user_wallet.key_version` of keys derived by [`HmacDeriver`].
+pub const HMAC_KEY_VERSION: i16 = 1;
+
+/// `user_wallet.key_version` of keys derived by [`Bip44Deriver`].
+pub const BIP44_KEY_VERSION: i16 = 2;
+
+/// Version new deposit wallets are issued under and rotation moves wallets to.
+pub const CURRENT_KEY_VERSION: i16 = BIP44_KEY_VERSION;
+
+/// Offset of hardened BIP-32 child indexes.
+pub const HARDENED: u32 = 1 << 31;
+
+
+/// What a deposit wallet's private key is derived from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WalletKey {
+    pub user_id: Uuid,
+    /// Derivation scheme, `user_wallet.key_version`.
+    pub version: i16,
+    /// Position of the key within its scheme, `user_wallet.key_index`.
+    /// Schemes keyed by user id alone (v1) leave it empty.
+    pub index: Option<i64>,
+}
+
+impl From<&user_wallet::Model> for WalletKey {
+    fn from(wallet: &user_wallet::Model) -> Self {
+        WalletKey { user_id: wallet.user_id, version: wallet.key_version, index: wallet.key_index }
+    }
+}
+
+
+/// A scheme deriving deposit-wallet keys from an operator secret.
+///
+/// Each scheme is stored under its own `key_version`, so a new scheme (or a new
+/// secret) only applies to wallets issued or rotated onto it; existing addresses
+/// stay derivable as long as their scheme's secret is configured.
+pub trait KeyDeriver: Send + Sync {
+    /// The `user_wallet.key_version` this scheme derives.
+    fn version(&self) -> i16;
+
+    fn derive(&self, key: &WalletKey) -> Result<PrivateKeySigner, AppError>;
+}
+
+
+/// The original scheme: `HMAC-SHA256(WALLET_GENERATION_SECRET, user id)`.
+///
+/// One key per user, so rotating the secret moves every address at once.
+pub struct HmacDeriver {
+    secret: Vec<u8>,
+}
+
+impl HmacDeriver {
+    pub fn new(secret: Vec<u8>) -> Self {
+        HmacDeriver { secret }
+    }
+}
+
+impl KeyDeriver for HmacDeriver {
+    fn version(&self) -> i16 {
+        HMAC_KEY_VERSION
+    }
+
+    fn derive(&self, key: &WalletKey) -> Result<PrivateKeySigner, AppError> {
+        let mut mac = HmacSha256::new_from_slice(&self.secret)
+            .expect("HMAC can take key of any size");
+
+        mac.update(key.user_id.as_bytes());
+
+        PrivateKeySigner::from_bytes(&B256::from_slice(&mac.finalize().into_bytes()))
+            .map_err(|_| AppError::InternalError("Signer creation failed".into()))
+    }
+}
+
+
+/// BIP-32 HD keys on the BIP-44 Ethereum path `m/44'/60'/0'/0/key_index`.
+///
+/// Keys are indexed from a database sequence instead of the user id, so one
+/// user may hold several addresses over time and any wallet can be moved to a
+/// fresh one, and the seed can be loaded into standard HD tooling for recovery.
+pub struct Bip44Deriver {
+    /// The `m/44'/60'/0'/0` node every deposit key is a child of.
+    external: ExtendedKey,
+}
+
+impl Bip44Deriver {
+    pub fn from_seed(seed: &[u8]) -> Result<Self, AppError> {
+        let external = ExtendedKey::master(seed)?
+            .derive_path(&[44 | HARDENED, 60 | HARDENED, HARDENED, 0])?;
+
+        Ok(Bip44Deriver { external })
+    }
+}
+
+impl KeyDeriver for Bip44Deriver {
+    fn version(&self) -> i16 {
+        BIP44_KEY_VERSION
+    }
+
+    fn derive(&self, key: &WalletKey) -> Result<PrivateKeySigner, AppError> {
+        let index = key
+            .index
+            .and_then(|index| u32::try_from(index).ok())
+            .filter(|index| *index < HARDENED)
+            .ok_or_else(|| AppError::InternalError(format!("Invalid key index {:?} for a BIP-44 wallet", key.index)))?;
+
+        let child = self.external.child(index)?;
+
+        PrivateKeySigner::from_bytes(&B256::from(child.secret_bytes()))
+            .map_err(|_| AppError::InternalError("Signer creation failed".into()))
+    }
+}
+
+
+/// A BIP-32 extended private key.
+#[derive(Clone)]
+pub struct ExtendedKey {
+    secret: NonZeroScalar,
+    chain_code: [u8; 32],
+}
+
+impl ExtendedKey {
+    /// The master node of `seed` (16 to 64 bytes).
+    pub fn master(seed: &[u8]) -> Result<Self, AppError> {
+        if !(16..=64).contains(&seed.len()) {
+            return Err(AppError::ConfigError(format!("HD seed must be 16 to 64 bytes, got {}", seed.len())));
+        }
+
+        let mut mac = HmacSha512::new_from_slice(b"Bitcoin seed").expect("HMAC can take key of any size");
+        mac.update(seed);
+
+        let (il, ir) = split_hmac(mac);
+        let secret = nonzero_scalar(il, Scalar::ZERO)
+            .ok_or_else(|| AppError::ConfigError("HD seed yields an invalid master key".into()))?;
+
+        Ok(ExtendedKey { secret, chain_code: ir })
+    }
+
+    /// The private child at `index`; indexes from [`HARDENED`] up are hardened.
+    pub fn child(&self, index: u32) -> Result<Self, AppError> {
+        let mut mac = HmacSha512::new_from_slice(&self.chain_code).expect("HMAC can take key of any size");
+
+        if index >= HARDENED {
+            mac.update(&[0]);
+            mac.update(&self.secret.to_repr());
+        } else {
+            let public = PublicKey::from_secret_scalar(&self.secret);
+            mac.update(public.to_encoded_point(true).as_bytes());
+        }
+        mac.update(&index.to_be_bytes());
+
+        // BIP-32 skips to the next index here; with odds below 2^-127 it is treated as an error
+        let (il, ir) = split_hmac(mac);
+        let secret = nonzero_scalar(il, *self.secret)
+            .ok_or_else(|| AppError::InternalError(format!("BIP-32 child {} is invalid", index)))?;
+
+        Ok(ExtendedKey { secret, chain_code: ir })
+    }
+
+    pub fn derive_path(&self, path: &[u32]) -> Result<Self, AppError> {
+        path.iter().try_fold(self.clone(), |node, &index| node.child(index))
+    }
+
+    pub fn secret_bytes(&self) -> [u8; 32] {
+        self.secret.to_repr().into()
+    }
+}
+
+
+fn split_hmac(mac: HmacSha512) -> ([u8; 32], [u8; 32]) {
+    let output = mac.finalize().into_bytes();
+    let (il, ir) = output.split_at(32);
+
+    (il.try_into().expect("32 bytes"), ir.try_into().expect("32 bytes"))
+}
+
+
+/// `il + parent` as a private key, `None` if `il` is not below the curve order or the sum is zero.
+fn nonzero_scalar(il: [u8; 32], parent: Scalar) -> Option<NonZeroScalar> {
+    let tweak = Option::<Scalar>::from(Scalar::from_repr(FieldBytes::from(il)))?;
+
+    Option::from(NonZeroScalar::new(tweak + parent))
+}
+
+
+/// The derivers of every scheme whose secret is configured.
+#[derive(Default)]
+pub struct KeyDerivers {
+    derivers: HashMap<i16, Box<dyn KeyDeriver>>,
+}
+
+impl KeyDerivers {
+    /// Builds the derivers from the hex `WALLET_GENERATION_SECRET` and `WALLET_HD_SEED` values.
+    pub fn from_secrets(wallet_generation_secret: Option<&str>, wallet_hd_seed: Option<&str>) -> Result<Self, AppError> {
+        let mut derivers = KeyDerivers::default();
+
+        if let Some(secret) = wallet_generation_secret {
+            let secret = hex::decode(secret.trim())
+                .map_err(|_| AppError::InternalError("Invalid WALLET_GENERATION_SECRET".into()))?;
+            derivers.insert(Box::new(HmacDeriver::new(secret)));
+        }
+
+        if let Some(seed) = wallet_hd_seed {
+            let seed = hex::decode(seed.trim().trim_start_matches("0x"))
+                .map_err(|_| AppError::ConfigError("Invalid WALLET_HD_SEED".into()))?;
+            derivers.insert(Box::new(Bip44Deriver::from_seed(&seed)?));
+        }
+
+        Ok(derivers)
+    }
+
+    pub fn insert(&mut self, deriver: Box<dyn KeyDeriver>) {
+        self.derivers.insert(deriver.version(), deriver);
+    }
+
+    /// The deriver of `version`, failing when its secret is not configured.
+    pub fn deriver(&self, version: i16) -> Result<&dyn KeyDeriver, AppError> {
+        self.derivers
+            .get(&version)
+            .map(Box::as_ref)
+            .ok_or_else(|| AppError::ConfigError(format!("No key deriver configured for key version {}", version)))
+    }
+
+    pub fn signer(&self, key: &WalletKey) -> Result<PrivateKeySigner, AppError> {
+        self.deriver(key.version)?.derive(key)
+    }
+}
+
+
+/// Derivers of this process, built once from its configuration.
+static DERIVERS: OnceCell<KeyDerivers> = OnceCell::new();
+
+
+fn derivers() -> Result<&'static KeyDerivers, AppError> {
+    DERIVERS.get_or_try_init(|| {
+        let config = AppConfig::from_env()?;
+        KeyDerivers::from_secrets(config.wallet_generation_secret.as_deref(), config.wallet_hd_seed.as_deref())
+    })
+}
+
+
+
+/// The deriver of `version` configured in this process.
+pub fn deriver(version: i16) -> Result<&'static dyn KeyDeriver, AppError> {
+    derivers()?.deriver(version)
+}
+
+
+
+/// Derives the signer of a deposit wallet in this process.
+pub fn wallet_signer(key: &WalletKey) -> Result<PrivateKeySigner, AppError> {
+    derivers()?.signer(key)
+}
+
+
+
+/// Reserves the key of a new deposit address for `user_id` under [`CURRENT_KEY_VERSION`].
+///
+/// Indexes come from `wallet_key_index_seq` and are never reused, even when the
+/// surrounding transaction rolls back.
+pub async fn next_wallet_key<C: ConnectionTrait>(conn: &C, user_id: Uuid) -> Result<WalletKey, AppError> {
+
+    let row = conn
+        .query_one(Statement::from_string(DbBackend::Postgres, "SELECT nextval('wallet_key_index_seq') AS key_index"))
+        .await
+        .map_err(AppError::DbError)?
+        .ok_or_else(|| AppError::InternalError("wallet_key_index_seq returned no row".into()))?;
+
+    let index: i64 = row.try_get("", "key_index").map_err(AppError::DbError)?;
+
+    Ok(WalletKey { user_id, version: CURRENT_KEY_VERSION, index: Some(index) })
+}
@@ -1,2 +1,7 @@
-
-pub mod chain_config;
\ No newline at end of file
+pub mod chain_config;
+pub mod key_deriver;
+pub mod registry;
+pub mod remote_signer;
+pub mod rpc_pool;
+pub mod signer_registry;
+pub mod signing_policy;
@@ -0,0 +1,223 @@
+use std::{collections::HashMap, str::FromStr, sync::{Arc, RwLock}, time::Duration};
+
+use alloy::primitives::Address;
+use once_cell::sync::Lazy;
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::{
+    entities::{chains, prelude::{Chains, Tokens}, tokens},
+    error::error::AppError,
+    state_models::models::DbConnection,
+    utils::dust_policy::DustPolicy,
+};
+
+
+/// A network the sweeper operates on, loaded from the `chains` table.
+#[derive(Debug, Clone)]
+pub struct ChainEntry {
+    /// Registry key used across the crate and in `user_balance.chain` (e.g. `base_sepolia`).
+    pub name: String,
+    /// EIP-155 chain id of the network.
+    pub chain_id: u64,
+    /// RPC endpoints in order of preference.
+    pub rpc_urls: Vec<String>,
+    /// Blocks a sweep must be buried under before it is credited.
+    pub confirmations: u64,
+    /// Depth at which a credited sweep counts as final where the RPC has no `finalized` tag.
+    pub finality_depth: u64,
+    /// Symbol of the native gas token (ETH, BTC, BNB...).
+    pub native_symbol: String,
+    /// Multicall3 deployment used to batch balance reads, if the chain has one.
+    pub multicall3: Option<Address>,
+    /// Native balances (in whole coins) below this amount are not worth sweeping.
+    pub min_native_sweep: Decimal,
+    /// Cap on the max fee per gas of any sweep, in wei.
+    pub max_fee_per_gas: Option<u128>,
+    /// Largest share of a sweep's value its worst-case fee may take.
+    pub max_fee_ratio: Option<Decimal>,
+    /// Age after which an unmined sweep is replaced with higher fees.
+    pub rbf_after: Duration,
+    /// Minimum fee increase of a replacement, in percent.
+    pub rbf_bump_percent: u32,
+    /// The only addresses sweeps on this chain may pay out to.
+    pub treasury_addresses: Vec<Address>,
+    /// Where sweeps go once the hot wallet holds its `vault_limit`; `None` keeps everything hot.
+    pub cold_wallet_address: Option<Address>,
+}
+
+impl ChainEntry {
+    pub fn is_treasury(&self, address: Address) -> bool {
+        self.treasury_addresses.contains(&address)
+    }
+}
+
+
+/// An ERC-20 the sweeper collects on a chain, loaded from the `tokens` table.
+#[derive(Debug, Clone)]
+pub struct TokenEntry {
+    pub chain: String,
+    pub symbol: String,
+    pub address: Address,
+    pub decimals: u8,
+    /// Balances below this amount (in whole tokens) are not worth the gas to sweep.
+    pub min_sweep_amount: Decimal,
+    /// `false` for tokens like USDT whose `transfer` returns no value instead of `bool`.
+    pub transfer_returns_bool: bool,
+    /// Approximate value of one token in the native coin, for the fee/value ratio check.
+    pub native_price: Option<Decimal>,
+    /// What happens to balances below `min_sweep_amount`.
+    pub dust_policy: DustPolicy,
+}
+
+
+/// Snapshot of every enabled chain and token.
+///
+/// Workers take a cheap `Arc` snapshot with [`registry`] at the start of a
+/// cycle so a reload never changes the view of a sweep that is already running.
+#[derive(Debug, Default)]
+pub struct Registry {
+    chains: HashMap<String, ChainEntry>,
+    tokens: HashMap<String, Vec<TokenEntry>>,
+}
+
+impl Registry {
+    pub fn chain(&self, name: &str) -> Option<&ChainEntry> {
+        self.chains.get(name)
+    }
+
+    pub fn chains(&self) -> impl Iterator<Item = &ChainEntry> {
+        self.chains.values()
+    }
+
+    /// Enabled tokens on `chain`; empty for native-only chains.
+    pub fn tokens(&self, chain: &str) -> &[TokenEntry] {
+        self.tokens.get(chain).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+
+static REGISTRY: Lazy<RwLock<Arc<Registry>>> = Lazy::new(|| RwLock::new(Arc::new(Registry::default())));
+
+
+/// Returns the most recently loaded registry.
+pub fn registry() -> Arc<Registry> {
+    REGISTRY
+        .read()
+        .map(|guard| guard.clone())
+        .unwrap_or_else(|poisoned| poisoned.into_inner().clone())
+}
+
+
+/// Reloads enabled chains and tokens from the database and swaps them in.
+///
+/// Rows that fail to parse are skipped with a log line instead of failing the
+/// whole reload, so one bad token address cannot stop every chain. On a database
+/// error the previous snapshot stays active.
+pub async fn reload_registry(db: &DbConnection) -> Result<(), AppError> {
+
+    let chain_rows = Chains::find()
+        .filter(chains::Column::Enabled.eq(true))
+        .order_by_asc(chains::Column::Id)
+        .all(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    let token_rows = Tokens::find()
+        .filter(tokens::Column::Enabled.eq(true))
+        .order_by_asc(tokens::Column::Id)
+        .all(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    let mut loaded = Registry::default();
+
+    for row in chain_rows {
+        let rpc_urls: Vec<String> = match serde_json::from_value(row.rpc_urls) {
+            Ok(urls) => urls,
+            Err(e) => {
+                eprintln!("Skipping chain {}: rpc_urls is not a list of strings: {}", row.name, e);
+                continue;
+            }
+        };
+
+        // A half-parsed allow-list could silently drop a treasury; skip the chain instead
+        let treasury_addresses: Vec<Address> = match serde_json::from_value::<Vec<String>>(row.treasury_addresses)
+            .map_err(|e| e.to_string())
+            .and_then(|addresses| addresses.iter().map(|address| Address::from_str(address.trim()).map_err(|e| e.to_string())).collect())
+        {
+            Ok(addresses) => addresses,
+            Err(e) => {
+                eprintln!("Skipping chain {}: invalid treasury_addresses: {}", row.name, e);
+                continue;
+            }
+        };
+
+        let Ok(cold_wallet_address) = row.cold_wallet_address.as_deref().map(|address| Address::from_str(address.trim())).transpose() else {
+            eprintln!("Skipping chain {}: invalid cold_wallet_address", row.name);
+            continue;
+        };
+
+        let multicall3 = match row.multicall3_address.as_deref().map(Address::from_str).transpose() {
+            Ok(address) => address,
+            Err(_) => {
+                eprintln!("Ignoring invalid multicall3_address for chain {}", row.name);
+                None
+            }
+        };
+
+        loaded.chains.insert(row.name.clone(), ChainEntry {
+            name: row.name,
+            chain_id: row.chain_id as u64,
+            rpc_urls,
+            confirmations: row.confirmations.max(0) as u64,
+            finality_depth: row.finality_depth.max(0) as u64,
+            native_symbol: row.native_symbol,
+            multicall3,
+            min_native_sweep: row.min_native_sweep,
+            max_fee_per_gas: row.max_fee_per_gas_gwei.and_then(|gwei| (gwei * Decimal::from(1_000_000_000u64)).trunc().to_u128()),
+            max_fee_ratio: row.max_fee_ratio,
+            rbf_after: Duration::from_secs(row.rbf_after_secs.max(0) as u64),
+            rbf_bump_percent: row.rbf_bump_percent.max(10) as u32,
+            treasury_addresses,
+            cold_wallet_address,
+        });
+    }
+
+    for row in token_rows {
+        if !loaded.chains.contains_key(&row.chain) {
+            continue;
+        }
+
+        let Ok(address) = Address::from_str(&row.address) else {
+            eprintln!("Skipping token {} on {}: invalid address {}", row.symbol, row.chain, row.address);
+            continue;
+        };
+
+        let Ok(decimals) = u8::try_from(row.decimals) else {
+            eprintln!("Skipping token {} on {}: invalid decimals {}", row.symbol, row.chain, row.decimals);
+            continue;
+        };
+
+        let Ok(dust_policy) = row.dust_policy.parse() else {
+            eprintln!("Skipping token {} on {}: invalid dust_policy {}", row.symbol, row.chain, row.dust_policy);
+            continue;
+        };
+
+        loaded.tokens.entry(row.chain.clone()).or_default().push(TokenEntry {
+            chain: row.chain,
+            symbol: row.symbol,
+            address,
+            decimals,
+            min_sweep_amount: row.min_sweep_amount,
+            transfer_returns_bool: row.transfer_returns_bool,
+            native_price: row.native_price,
+            dust_policy,
+        });
+    }
+
+    let mut guard = REGISTRY.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    *guard = Arc::new(loaded);
+
+    Ok(())
+}
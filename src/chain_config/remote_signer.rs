@@ -0,0 +1,204 @@
+use std::net::TcpListener;
+
+use actix_web::{App, HttpRequest, HttpResponse, HttpServer, http::header, web};
+use alloy::{
+    consensus::{TxEnvelope, TypedTransaction},
+    eips::eip2718::{Decodable2718, Encodable2718},
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::{Address, Bytes},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::{
+    chain_config::{key_deriver::{KeyDerivers, WalletKey}, signing_policy::SigningPolicy},
+    error::error::AppError,
+};
+
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddressRequest {
+    pub key: WalletKey,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddressResponse {
+    pub address: Address,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignRequest {
+    pub key: WalletKey,
+    pub tx: TypedTransaction,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignResponse {
+    /// EIP-2718 encoding of the signed transaction.
+    pub raw_tx: Bytes,
+}
+
+/// Body of a failed request, as written by `AppError`'s `ResponseError`.
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    message: String,
+}
+
+
+/// Client of a signer service holding the deposit-wallet secrets.
+///
+/// Set `SIGNER_URL` (e.g. `http://127.0.0.1:7070`) to sign through one; the
+/// sweeper then only ever sees wallet addresses and signed transactions. The
+/// optional `SIGNER_AUTH_TOKEN` is sent as a bearer token.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    url: String,
+    auth_token: Option<String>,
+    http: reqwest::Client,
+}
+
+impl RemoteSigner {
+    pub fn new(url: &str, auth_token: Option<String>) -> Self {
+        RemoteSigner { url: url.trim_end_matches('/').to_string(), auth_token, http: reqwest::Client::new() }
+    }
+
+    /// Address of the deposit wallet of `key`.
+    pub async fn address(&self, key: &WalletKey) -> Result<Address, AppError> {
+        let response: AddressResponse = self.post("/v1/address", &AddressRequest { key: *key }).await?;
+        Ok(response.address)
+    }
+
+    /// Has the signer sign `tx` for the wallet of `key`.
+    ///
+    /// The returned envelope is checked to carry exactly `tx`, so the signer
+    /// cannot swap in a different transaction.
+    pub async fn sign(&self, key: &WalletKey, tx: TypedTransaction) -> Result<TxEnvelope, AppError> {
+        let response: SignResponse = self.post("/v1/sign", &SignRequest { key: *key, tx: tx.clone() }).await?;
+
+        let envelope = TxEnvelope::decode_2718(&mut response.raw_tx.as_ref())
+            .map_err(|e| AppError::InternalError(format!("Signer returned an undecodable transaction: {e}")))?;
+
+        if TypedTransaction::from(envelope.clone()) != tx {
+            return Err(AppError::InternalError("Signer returned a different transaction than requested".into()));
+        }
+
+        Ok(envelope)
+    }
+
+    async fn post<Req: Serialize, Res: DeserializeOwned>(&self, path: &str, body: &Req) -> Result<Res, AppError> {
+        let body = serde_json::to_vec(body)
+            .map_err(|e| AppError::InternalError(format!("Cannot encode signer request: {e}")))?;
+
+        let mut request = self.http
+            .post(format!("{}{}", self.url, path))
+            .header(header::CONTENT_TYPE.as_str(), "application/json")
+            .body(body);
+
+        if let Some(token) = &self.auth_token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let bytes = response.bytes().await?;
+
+        if !status.is_success() {
+            let message = serde_json::from_slice::<ErrorBody>(&bytes)
+                .map(|body| body.message)
+                .unwrap_or_else(|_| String::from_utf8_lossy(&bytes).into_owned());
+
+            return Err(AppError::InternalError(format!("Signer refused {} ({}): {}", path, status, message)));
+        }
+
+        serde_json::from_slice(&bytes)
+            .map_err(|e| AppError::InternalError(format!("Cannot decode signer response: {e}")))
+    }
+}
+
+
+/// State of the signer service: the secrets, the policy and the expected token.
+pub struct SignerService {
+    pub derivers: KeyDerivers,
+    pub policy: SigningPolicy,
+    pub auth_token: Option<String>,
+}
+
+impl SignerService {
+    fn authorize(&self, request: &HttpRequest) -> Result<(), AppError> {
+        let Some(expected) = &self.auth_token else {
+            return Ok(());
+        };
+
+        let presented = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        if presented != Some(expected.as_str()) {
+            return Err(AppError::Unauthorized("missing or invalid signer token".into()));
+        }
+
+        Ok(())
+    }
+}
+
+
+/// Serves the signer on `listener` until the process stops.
+///
+/// Bind it to loopback or a private interface: the token only keeps other
+/// local processes out, and the policy is what limits what can be signed.
+pub async fn run_signer_service(listener: TcpListener, service: SignerService) -> std::io::Result<()> {
+
+    let service = web::Data::new(service);
+
+    HttpServer::new(move || {
+        App::new()
+            .app_data(service.clone())
+            .route("/v1/address", web::post().to(address_handler))
+            .route("/v1/sign", web::post().to(sign_handler))
+    })
+    .workers(1)
+    .listen(listener)?
+    .run()
+    .await
+}
+
+
+
+async fn address_handler(
+    request: HttpRequest,
+    service: web::Data<SignerService>,
+    body: web::Json<AddressRequest>,
+) -> Result<HttpResponse, AppError> {
+
+    service.authorize(&request)?;
+    let address = service.derivers.signer(&body.key)?.address();
+
+    Ok(HttpResponse::Ok().json(AddressResponse { address }))
+}
+
+
+
+async fn sign_handler(
+    request: HttpRequest,
+    service: web::Data<SignerService>,
+    body: web::Json<SignRequest>,
+) -> Result<HttpResponse, AppError> {
+
+    service.authorize(&request)?;
+    let SignRequest { key, tx } = body.into_inner();
+
+    if let Err(e) = service.policy.check(&tx) {
+        eprintln!("Signer refused a transaction for {:?}: {}", key, e);
+        return Err(e);
+    }
+
+    let signer = service.derivers.signer(&key)?;
+    let address = signer.address();
+
+    let envelope = NetworkWallet::<Ethereum>::sign_transaction_from(&EthereumWallet::new(signer), address, tx)
+        .await
+        .map_err(|e| AppError::InternalError(format!("Signing failed: {e}")))?;
+
+    Ok(HttpResponse::Ok().json(SignResponse { raw_tx: envelope.encoded_2718().into() }))
+}
@@ -0,0 +1,403 @@
+use std::{
+    collections::HashMap, sync::{Arc, Mutex, RwLock}, task::{Context, Poll}, time::{Duration, Instant}
+};
+
+use alloy::{
+    providers::{Provider, RootProvider},
+    rpc::{client::RpcClient, json_rpc::{RequestPacket, ResponsePacket}},
+    transports::{RpcError, TransportError, TransportErrorKind, TransportFut, http::{Client, Http}},
+};
+use once_cell::sync::Lazy;
+use tower::Service;
+use tracing::{error, warn};
+
+use crate::chain_config::registry::ChainEntry;
+
+/// A hung endpoint must fail fast enough for the next one to still be useful.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// Weight of the newest sample in the latency and error-rate moving averages.
+const EWMA_ALPHA: f64 = 0.2;
+/// Score of an endpoint failing every call, on top of its latency: as bad as timing out.
+const ERROR_PENALTY_MS: f64 = REQUEST_TIMEOUT.as_millis() as f64;
+/// Score added per block an endpoint trails the highest head seen on its chain.
+const LAG_PENALTY_MS: f64 = 500.0;
+
+/// Methods that must not be replayed on another endpoint after an ambiguous failure.
+const NON_IDEMPOTENT_METHODS: [&str; 2] = ["eth_sendRawTransaction", "eth_sendTransaction"];
+
+static HTTP_CLIENT: Lazy<Client> = Lazy::new(|| {
+    Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("Cannot build the RPC HTTP client")
+});
+
+static POOLS: Lazy<RwLock<HashMap<String, Arc<RpcPool>>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+
+#[derive(Debug, Default)]
+struct Health {
+    requests: u64,
+    errors: u64,
+    latency_ms: f64,
+    error_rate: f64,
+    block_height: Option<u64>,
+    /// Chain id the endpoint last reported; unknown until it answers `eth_chainId`.
+    chain_id: Option<u64>,
+    last_error: Option<String>,
+}
+
+
+struct Endpoint {
+    /// Scheme and host only, so API keys in the URL path never reach the logs.
+    label: String,
+    http: Http<Client>,
+    health: Mutex<Health>,
+}
+
+impl Endpoint {
+    fn provider(&self) -> RootProvider {
+        RootProvider::new(RpcClient::new(self.http.clone(), false))
+    }
+
+    fn health(&self) -> std::sync::MutexGuard<'_, Health> {
+        self.health.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn record_success(&self, elapsed: Duration) {
+        let mut health = self.health();
+        let latency_ms = elapsed.as_secs_f64() * 1000.0;
+
+        health.latency_ms = if health.requests == 0 {
+            latency_ms
+        } else {
+            EWMA_ALPHA * latency_ms + (1.0 - EWMA_ALPHA) * health.latency_ms
+        };
+        health.error_rate *= 1.0 - EWMA_ALPHA;
+        health.requests += 1;
+    }
+
+    fn record_failure(&self, error: &TransportError) {
+        let mut health = self.health();
+        health.error_rate = EWMA_ALPHA + (1.0 - EWMA_ALPHA) * health.error_rate;
+        health.requests += 1;
+        health.errors += 1;
+        health.last_error = Some(error.to_string());
+    }
+
+    fn score(&self, best_height: Option<u64>) -> f64 {
+        let health = self.health();
+        let lag = match (best_height, health.block_height) {
+            (Some(best), Some(height)) => best.saturating_sub(height),
+            _ => 0,
+        };
+
+        health.latency_ms + health.error_rate * ERROR_PENALTY_MS + lag as f64 * LAG_PENALTY_MS
+    }
+}
+
+
+/// Point-in-time health of one RPC endpoint.
+#[derive(Debug, Clone)]
+pub struct EndpointStats {
+    pub chain: String,
+    pub endpoint: String,
+    pub requests: u64,
+    pub errors: u64,
+    /// Moving average of the share of recent calls that failed, from 0 to 1.
+    pub error_rate: f64,
+    /// Moving average of successful call latency.
+    pub latency_ms: f64,
+    pub block_height: Option<u64>,
+    /// Chain id reported by the endpoint; traffic is refused unless it matches the registry.
+    pub chain_id: Option<u64>,
+    /// Blocks behind the highest head reported on the chain.
+    pub block_lag: u64,
+    /// Routing score; the lowest-scored endpoint receives traffic first.
+    pub score: f64,
+    pub last_error: Option<String>,
+}
+
+
+/// The RPC endpoints of one chain with their health, shared by every provider on that chain.
+pub struct RpcPool {
+    chain: String,
+    /// EIP-155 id every endpoint must report before it receives traffic.
+    chain_id: u64,
+    urls: Vec<String>,
+    endpoints: Vec<Arc<Endpoint>>,
+}
+
+impl RpcPool {
+    fn new(chain: &ChainEntry) -> Self {
+        let endpoints = chain
+            .rpc_urls
+            .iter()
+            .filter_map(|rpc| match rpc.parse::<reqwest::Url>() {
+                Ok(url) => Some(Arc::new(Endpoint {
+                    label: format!("{}://{}", url.scheme(), url.host_str().unwrap_or_default()),
+                    http: Http::with_client(HTTP_CLIENT.clone(), url),
+                    health: Mutex::default(),
+                })),
+                Err(e) => {
+                    eprintln!("⚠️ Skipping invalid RPC URL for {}: {}", chain.name, e);
+                    None
+                }
+            })
+            .collect();
+
+        RpcPool { chain: chain.name.clone(), chain_id: chain.chain_id, urls: chain.rpc_urls.clone(), endpoints }
+    }
+
+    fn matches(&self, chain: &ChainEntry) -> bool {
+        self.urls == chain.rpc_urls && self.chain_id == chain.chain_id
+    }
+
+    /// Whether `endpoint` serves this pool's chain, asking it once if it never said.
+    async fn verify(&self, endpoint: &Endpoint) -> bool {
+        let reported = endpoint.health().chain_id;
+
+        let reported = match reported {
+            Some(chain_id) => chain_id,
+            None => match self.fetch_chain_id(endpoint).await {
+                Some(chain_id) => chain_id,
+                None => return false,
+            },
+        };
+
+        reported == self.chain_id
+    }
+
+    async fn fetch_chain_id(&self, endpoint: &Endpoint) -> Option<u64> {
+        let started = Instant::now();
+
+        match endpoint.provider().get_chain_id().await {
+            Ok(chain_id) => {
+                endpoint.record_success(started.elapsed());
+                endpoint.health().chain_id = Some(chain_id);
+
+                if chain_id != self.chain_id {
+                    error!(
+                        "RPC {} configured for {} reports chain id {} instead of {}; refusing to use it",
+                        endpoint.label, self.chain, chain_id, self.chain_id
+                    );
+                }
+                Some(chain_id)
+            }
+            Err(e) => {
+                warn!("Cannot verify chain id of RPC {} on {}: {}", endpoint.label, self.chain, e);
+                endpoint.record_failure(&e);
+                None
+            }
+        }
+    }
+
+    fn best_height(&self) -> Option<u64> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| endpoint.health())
+            .filter(|health| health.chain_id == Some(self.chain_id))
+            .filter_map(|health| health.block_height)
+            .max()
+    }
+
+    /// Endpoints from best to worst score; ties keep the registry's order of preference.
+    ///
+    /// Endpoints known to serve another chain are left out entirely.
+    fn ranked(&self) -> Vec<Arc<Endpoint>> {
+        let best_height = self.best_height();
+        let mut ranked: Vec<(f64, Arc<Endpoint>)> = self
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.health().chain_id.is_none_or(|chain_id| chain_id == self.chain_id))
+            .map(|endpoint| (endpoint.score(best_height), endpoint.clone()))
+            .collect();
+
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0));
+        ranked.into_iter().map(|(_, endpoint)| endpoint).collect()
+    }
+
+    /// Re-checks the chain id and head block of every endpoint to refresh latency and block lag.
+    ///
+    /// Traffic only ever reaches the best endpoint, so without probing a lagging
+    /// or recovered endpoint would keep its stale score forever. The chain id is
+    /// asked again too, in case a URL was repointed at another network.
+    pub async fn probe(&self) {
+        for endpoint in &self.endpoints {
+            if self.fetch_chain_id(endpoint).await.is_none() {
+                continue;
+            }
+
+            let started = Instant::now();
+
+            match endpoint.provider().get_block_number().await {
+                Ok(height) => {
+                    endpoint.record_success(started.elapsed());
+                    endpoint.health().block_height = Some(height);
+                }
+                Err(e) => {
+                    warn!("RPC probe failed for {} on {}: {}", endpoint.label, self.chain, e);
+                    endpoint.record_failure(&e);
+                }
+            }
+        }
+    }
+
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        let best_height = self.best_height();
+
+        self.endpoints
+            .iter()
+            .map(|endpoint| {
+                let score = endpoint.score(best_height);
+                let health = endpoint.health();
+
+                EndpointStats {
+                    chain: self.chain.clone(),
+                    endpoint: endpoint.label.clone(),
+                    requests: health.requests,
+                    errors: health.errors,
+                    error_rate: health.error_rate,
+                    latency_ms: health.latency_ms,
+                    block_height: health.block_height,
+                    chain_id: health.chain_id,
+                    block_lag: best_height.zip(health.block_height).map_or(0, |(best, height)| best.saturating_sub(height)),
+                    score,
+                    last_error: health.last_error.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+
+/// Returns the shared endpoint pool of `chain`, rebuilding it when its RPC list or chain id changed.
+pub fn rpc_pool(chain: &ChainEntry) -> Arc<RpcPool> {
+    if let Some(pool) = POOLS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(&chain.name)
+        .filter(|pool| pool.matches(chain))
+    {
+        return pool.clone();
+    }
+
+    let mut pools = POOLS.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let pool = pools
+        .entry(chain.name.clone())
+        .and_modify(|pool| {
+            if !pool.matches(chain) {
+                *pool = Arc::new(RpcPool::new(chain));
+            }
+        })
+        .or_insert_with(|| Arc::new(RpcPool::new(chain)));
+
+    pool.clone()
+}
+
+
+/// Health of every endpoint of every chain that has been used so far.
+pub fn rpc_stats() -> Vec<EndpointStats> {
+    let pools: Vec<Arc<RpcPool>> = POOLS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .values()
+        .cloned()
+        .collect();
+
+    pools.iter().flat_map(|pool| pool.stats()).collect()
+}
+
+
+/// RPC client that routes each call to the healthiest endpoint of `pool`.
+pub fn failover_client(pool: Arc<RpcPool>) -> RpcClient {
+    RpcClient::new(FailoverTransport { pool }, false)
+}
+
+
+/// Transport that sends each request to the best-scored endpoint of a pool.
+///
+/// An endpoint only receives traffic once it has reported the chain id the
+/// registry expects, so a mainnet URL filed under a testnet is never used.
+///
+/// When an endpoint fails at the transport level (connection error, timeout,
+/// HTTP error status, unparseable body), reads are retried on the next endpoint
+/// by score. Transaction submissions are never replayed elsewhere: the first
+/// endpoint may have accepted the transaction before failing, and the outbox
+/// recovery already handles that case. JSON-RPC error responses (reverts, bad
+/// params...) come from a working node and are returned as-is.
+#[derive(Clone)]
+pub struct FailoverTransport {
+    pool: Arc<RpcPool>,
+}
+
+impl FailoverTransport {
+    async fn dispatch(self, request: RequestPacket) -> Result<ResponsePacket, TransportError> {
+        let replayable = request
+            .method_names()
+            .all(|method| !NON_IDEMPOTENT_METHODS.contains(&method));
+
+        // Endpoints that already reported the chain id go first. The health monitor
+        // verifies the others in the background, so one is only asked inline when
+        // nothing verified is left to try.
+        let (verified, unverified): (Vec<_>, Vec<_>) = self
+            .pool
+            .ranked()
+            .into_iter()
+            .partition(|endpoint| endpoint.health().chain_id.is_some());
+
+        let max_attempts = if replayable { usize::MAX } else { 1 };
+        let mut attempts = 0;
+        let mut last_error = None;
+
+        for endpoint in verified.into_iter().chain(unverified) {
+            if attempts == max_attempts {
+                break;
+            }
+
+            if !self.pool.verify(&endpoint).await {
+                continue;
+            }
+            attempts += 1;
+
+            let started = Instant::now();
+            let mut http = endpoint.http.clone();
+
+            match http.call(request.clone()).await {
+                Err(e) if is_endpoint_failure(&e) => {
+                    warn!("RPC {} failed on {}: {}", endpoint.label, self.pool.chain, e);
+                    endpoint.record_failure(&e);
+                    last_error = Some(e);
+                }
+                result => {
+                    endpoint.record_success(started.elapsed());
+                    return result;
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            TransportErrorKind::custom_str(&format!("No usable RPC endpoints for {}", self.pool.chain))
+        }))
+    }
+}
+
+impl Service<RequestPacket> for FailoverTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        Box::pin(self.clone().dispatch(request))
+    }
+}
+
+
+/// Whether `error` says the endpoint is unhealthy, rather than the request being bad.
+fn is_endpoint_failure(error: &TransportError) -> bool {
+    matches!(error, RpcError::Transport(_) | RpcError::DeserError { .. } | RpcError::NullResp)
+}
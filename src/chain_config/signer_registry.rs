@@ -0,0 +1,204 @@
+use std::{
+    collections::HashMap, sync::{Arc, Mutex, MutexGuard}, time::{Duration, Instant}
+};
+
+use alloy::{
+    consensus::{TxEnvelope, TypedTransaction}, network::{Ethereum, EthereumWallet, NetworkWallet}, primitives::Address
+};
+use alloy_signer_local::PrivateKeySigner;
+
+use crate::chain_config::{key_deriver::WalletKey, remote_signer::RemoteSigner};
+
+/// Most credentials held at once; the least recently used one goes first.
+const MAX_CREDENTIALS: usize = 1024;
+
+/// Credentials unused for this long are dropped on the next registration.
+const IDLE_TTL: Duration = Duration::from_secs(30 * 60);
+
+
+/// How the registry signs for one address.
+#[derive(Clone)]
+enum Credential {
+    /// A key held in this process (operator keys, or deposit keys without a signer service).
+    Local(PrivateKeySigner),
+    /// A deposit wallet whose key only the signer service can derive.
+    Remote(Arc<RemoteSigner>, WalletKey),
+}
+
+
+struct Entry {
+    credential: Credential,
+    last_used: Instant,
+}
+
+
+#[derive(Default)]
+struct Credentials {
+    by_address: HashMap<Address, Entry>,
+    /// Addresses of the remote credentials, so a wallet's address is fetched once.
+    remote_addresses: HashMap<WalletKey, Address>,
+}
+
+impl Credentials {
+    fn remove(&mut self, address: &Address) {
+        if let Some(Entry { credential: Credential::Remote(_, key), .. }) = self.by_address.remove(address) {
+            self.remote_addresses.remove(&key);
+        }
+    }
+
+    /// Drops idle credentials, then the least recently used ones until `incoming` more fit.
+    fn evict(&mut self, capacity: usize, idle_ttl: Duration, incoming: usize) {
+        let idle: Vec<Address> = self.by_address
+            .iter()
+            .filter(|(_, entry)| entry.last_used.elapsed() >= idle_ttl)
+            .map(|(address, _)| *address)
+            .collect();
+
+        for address in idle {
+            self.remove(&address);
+        }
+
+        while self.by_address.len() + incoming > capacity {
+            let Some(oldest) = self.by_address.iter().min_by_key(|(_, entry)| entry.last_used).map(|(address, _)| *address) else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+    }
+}
+
+
+/// Signers the process has derived or loaded recently, shared by all cached providers.
+///
+/// Lets one provider per chain sign for thousands of deposit wallets: a sweep
+/// registers its wallet's signer and sets `from`, and signing looks the key up
+/// by sender. There is no default signer, so a transaction without `from`
+/// fails to sign instead of going out from an arbitrary wallet.
+///
+/// Keys are not kept for the life of the process: at most `capacity`
+/// credentials are held, and one unused for `idle_ttl` is dropped. Callers
+/// register a signer every time they fetch a provider for it, which also marks
+/// it as used, so only wallets no sweep is working on are evicted.
+#[derive(Clone)]
+pub struct SignerRegistry {
+    credentials: Arc<Mutex<Credentials>>,
+    capacity: usize,
+    idle_ttl: Duration,
+}
+
+impl Default for SignerRegistry {
+    fn default() -> Self {
+        Self::bounded(MAX_CREDENTIALS, IDLE_TTL)
+    }
+}
+
+impl std::fmt::Debug for SignerRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SignerRegistry")
+            .field("credentials", &self.lock().by_address.len())
+            .finish()
+    }
+}
+
+impl SignerRegistry {
+    /// A registry holding at most `capacity` credentials, each for up to `idle_ttl` after its last use.
+    pub fn bounded(capacity: usize, idle_ttl: Duration) -> Self {
+        Self { credentials: Arc::default(), capacity: capacity.max(1), idle_ttl }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Credentials> {
+        self.credentials.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn insert(&self, address: Address, credential: Credential) {
+        let mut credentials = self.lock();
+
+        if let Some(entry) = credentials.by_address.get_mut(&address) {
+            entry.last_used = Instant::now();
+            return;
+        }
+
+        credentials.evict(self.capacity, self.idle_ttl, 1);
+
+        if let Credential::Remote(_, key) = &credential {
+            credentials.remote_addresses.insert(*key, address);
+        }
+        credentials.by_address.insert(address, Entry { credential, last_used: Instant::now() });
+    }
+
+    /// Adds `signer` (only marking it as used if already known) and returns its address.
+    pub fn register(&self, signer: PrivateKeySigner) -> Address {
+        let address = signer.address();
+        self.insert(address, Credential::Local(signer));
+
+        address
+    }
+
+    /// Routes signing for `address` to `remote`, which derives it from `key`.
+    pub fn register_remote(&self, address: Address, remote: Arc<RemoteSigner>, key: WalletKey) {
+        self.insert(address, Credential::Remote(remote, key));
+    }
+
+    /// Address already registered for the deposit wallet of `key`, if any; marks it as used.
+    pub fn remote_address(&self, key: &WalletKey) -> Option<Address> {
+        let mut credentials = self.lock();
+        let address = credentials.remote_addresses.get(key).copied()?;
+
+        if let Some(entry) = credentials.by_address.get_mut(&address) {
+            entry.last_used = Instant::now();
+        }
+
+        Some(address)
+    }
+
+    fn credential(&self, address: &Address) -> Option<Credential> {
+        let mut credentials = self.lock();
+        let entry = credentials.by_address.get_mut(address)?;
+        entry.last_used = Instant::now();
+
+        Some(entry.credential.clone())
+    }
+}
+
+impl NetworkWallet<Ethereum> for SignerRegistry {
+    fn default_signer_address(&self) -> Address {
+        Address::ZERO
+    }
+
+    fn has_signer_for(&self, address: &Address) -> bool {
+        self.lock().by_address.contains_key(address)
+    }
+
+    fn signer_addresses(&self) -> impl Iterator<Item = Address> {
+        self.lock().by_address.keys().copied().collect::<Vec<_>>().into_iter()
+    }
+
+    async fn sign_transaction_from(
+        &self,
+        sender: Address,
+        tx: TypedTransaction,
+    ) -> alloy::signers::Result<TxEnvelope> {
+        let credential = self
+            .credential(&sender)
+            .ok_or_else(|| alloy::signers::Error::other(format!("Missing signing credential for {sender}")))?;
+
+        match credential {
+            Credential::Local(signer) => {
+                NetworkWallet::<Ethereum>::sign_transaction_from(&EthereumWallet::new(signer), sender, tx).await
+            }
+            Credential::Remote(remote, key) => {
+                let envelope = remote.sign(&key, tx).await.map_err(alloy::signers::Error::other)?;
+
+                let signer = envelope
+                    .signature()
+                    .recover_address_from_prehash(&envelope.signature_hash())
+                    .map_err(alloy::signers::Error::other)?;
+                if signer != sender {
+                    return Err(alloy::signers::Error::other(format!("Signer service signed as {signer} instead of {sender}")));
+                }
+
+                Ok(envelope)
+            }
+        }
+    }
+}
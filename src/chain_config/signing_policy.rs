@@ -0,0 +1,86 @@
+use alloy::{
+    consensus::{Transaction, TypedTransaction},
+    primitives::{Address, TxKind},
+};
+
+use crate::{error::error::AppError, utils::token_decimals::decode_transfer};
+
+
+/// What a deposit wallet may sign: nothing but sweeps to a treasury wallet.
+///
+/// Enforced by the signer itself, so a compromised or buggy sweeper still
+/// cannot move deposits anywhere else. The treasuries are the hot (master)
+/// wallet and any cold wallets sweeps overflow to. Allowed are:
+/// - a native transfer to a treasury with no calldata;
+/// - a call carrying exactly `transfer(treasury, amount)` with no native value.
+///
+/// Contract creation, EIP-7702 authorizations, blob transactions and legacy
+/// transactions without replay protection are refused outright.
+#[derive(Debug, Clone)]
+pub struct SigningPolicy {
+    treasuries: Vec<Address>,
+}
+
+impl SigningPolicy {
+    /// Allows sweeps to `master` and to each of `cold_wallets`.
+    pub fn new(master: Address, cold_wallets: impl IntoIterator<Item = Address>) -> Self {
+        let mut treasuries = vec![master];
+        for cold in cold_wallets {
+            if !treasuries.contains(&cold) {
+                treasuries.push(cold);
+            }
+        }
+
+        SigningPolicy { treasuries }
+    }
+
+    pub fn treasuries(&self) -> &[Address] {
+        &self.treasuries
+    }
+
+    fn is_treasury(&self, address: Address) -> bool {
+        self.treasuries.contains(&address)
+    }
+
+    /// Fails with `Forbidden` when `tx` is outside the policy.
+    pub fn check(&self, tx: &TypedTransaction) -> Result<(), AppError> {
+        let forbidden = |reason: String| Err(AppError::Forbidden(reason));
+
+        match tx {
+            TypedTransaction::Legacy(_) | TypedTransaction::Eip2930(_) | TypedTransaction::Eip1559(_) => {}
+            TypedTransaction::Eip4844(_) => return forbidden("blob transactions are not signed".into()),
+            TypedTransaction::Eip7702(_) => return forbidden("EIP-7702 authorizations are not signed".into()),
+        }
+
+        if tx.chain_id().is_none() {
+            return forbidden("transactions without a chain id are not signed".into());
+        }
+
+        let TxKind::Call(to) = tx.kind() else {
+            return forbidden("contract creation is not signed".into());
+        };
+
+        let input = tx.input();
+
+        if input.is_empty() {
+            if !self.is_treasury(to) {
+                return forbidden(format!("native transfer to {} which is not a treasury wallet", to));
+            }
+            return Ok(());
+        }
+
+        if !tx.value().is_zero() {
+            return forbidden("contract calls may not carry native value".into());
+        }
+
+        let Some((recipient, _)) = decode_transfer(input) else {
+            return forbidden("calldata is not an ERC-20 transfer".into());
+        };
+
+        if !self.is_treasury(recipient) {
+            return forbidden(format!("ERC-20 transfer to {} which is not a treasury wallet", recipient));
+        }
+
+        Ok(())
+    }
+}
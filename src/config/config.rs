@@ -1,6 +1,8 @@
-use std::env;
+use std::{collections::HashMap, env, str::FromStr};
 
+use alloy::primitives::Address;
 use dotenv::dotenv;
+use rust_decimal::Decimal;
 use serde::Deserialize;
 
 use crate::error::error::AppError;
@@ -23,12 +25,55 @@ pub struct AppConfig {
     /// The full database connection URL. This tells SeaORM how to connect
     /// to our PostgreSQL (or other) database.
     pub database_url: String,
-    /// The address (IP:Port) where our Actix-Web server will listen for incoming requests.
-    /// Defaults to "127.0.0.1:8080" if not explicitly set in the environment.
-    ///Private Key of signer who signs the contract
-    pub master_wallet_address : String ,
+    /// The master (hot treasury) wallet, from `MASTER_WALLET_ADDRESS`: every sweep
+    /// pays out to it until its vault limit sends the rest to cold storage, and
+    /// nonce gap fillers are sent to it. Must also be on the `treasury_addresses`
+    /// allow-list of each enabled chain.
+    pub master_wallet_address: Address,
 
-    pub wallet_generation_secret:String,
+    /// Hex HMAC secret of the original (`key_version` 1) deposit keys, from
+    /// `WALLET_GENERATION_SECRET`. Keep it set while any v1 wallet or v1 dust remains.
+    pub wallet_generation_secret: Option<String>,
+
+    /// Hex BIP-32 seed of the HD (`key_version` 2) deposit keys, from `WALLET_HD_SEED`.
+    pub wallet_hd_seed: Option<String>,
+
+    /// Signer service holding the deposit-wallet secrets, from `SIGNER_URL`.
+    /// When set, deposit keys are never derived in this process and the two
+    /// secrets above belong to the signer's environment instead.
+    pub signer_url: Option<String>,
+
+    /// Bearer token presented to the signer service, from `SIGNER_AUTH_TOKEN`.
+    pub signer_auth_token: Option<String>,
+
+    /// Hex private key of the operator wallet that tops up deposit wallets with gas.
+    /// The gas station is disabled when `GAS_FUNDER_PRIVATE_KEY` is not set.
+    pub gas_funder_private_key: Option<String>,
+
+    /// Hex private key of the hot wallet (`MASTER_WALLET_ADDRESS`) that pays out
+    /// withdrawals, from `HOT_WALLET_PRIVATE_KEY`. No withdrawal worker runs without it.
+    pub hot_wallet_private_key: Option<String>,
+
+    /// Maximum native amount the gas station may donate per chain per UTC day,
+    /// parsed from `GAS_STATION_DAILY_CAPS` (e.g. `base_sepolia=0.05,bitlayer_testnet=0.001`).
+    /// Chains without an entry receive no donations.
+    pub gas_station_daily_caps: HashMap<String, Decimal>,
+
+    /// Sweeper workers to run per chain, parsed from `SWEEPER_WORKERS`
+    /// (e.g. `base_sepolia=4,bitlayer_testnet=1`).
+    pub sweeper_workers: HashMap<String, usize>,
+
+    /// Workers for enabled chains missing from `SWEEPER_WORKERS`, from
+    /// `SWEEPER_DEFAULT_WORKERS` (defaults to 1). Set it to 0 to only run
+    /// the chains listed explicitly.
+    pub sweeper_default_workers: usize,
+
+    /// `SWEEPER_DRY_RUN=true` plans one sweep cycle of every chain, writes the
+    /// report to `dry_run_report` and exits without starting any worker.
+    pub sweeper_dry_run: bool,
+
+    /// Where the dry-run report goes, from `DRY_RUN_REPORT` (defaults to `sweep_dry_run.json`).
+    pub dry_run_report: String,
 
 }
 
@@ -53,10 +98,92 @@ impl AppConfig {
         Ok(AppConfig { 
             database_url: env::var("DATABASE_URL")
                 .map_err(|e| AppError::ConfigError(format!("DATABASE_URL not set: {}", e)))?,
-            master_wallet_address:env::var("MASTER_WALLET_ADDRESS")
-                .map_err(|e| AppError::ConfigError(format!("MASTER_WALLET_ADDRESS not set: {}", e)))?,
-            wallet_generation_secret:env::var("WALLET_GENERATION_SECRET")
-                .map_err(|e| AppError::ConfigError(format!("WALLET_GENERATION_SECRET not set: {}", e)))?,
+            master_wallet_address: parse_master_address(&env::var("MASTER_WALLET_ADDRESS")
+                .map_err(|e| AppError::ConfigError(format!("MASTER_WALLET_ADDRESS not set: {}", e)))?)?,
+            wallet_generation_secret: env::var("WALLET_GENERATION_SECRET").ok(),
+            wallet_hd_seed: env::var("WALLET_HD_SEED").ok(),
+            signer_url: env::var("SIGNER_URL").ok().filter(|url| !url.trim().is_empty()),
+            signer_auth_token: env::var("SIGNER_AUTH_TOKEN").ok(),
+            gas_funder_private_key: env::var("GAS_FUNDER_PRIVATE_KEY").ok(),
+            hot_wallet_private_key: env::var("HOT_WALLET_PRIVATE_KEY").ok().filter(|key| !key.trim().is_empty()),
+            gas_station_daily_caps: parse_chain_values(
+                "GAS_STATION_DAILY_CAPS",
+                &env::var("GAS_STATION_DAILY_CAPS").unwrap_or_default(),
+            )?,
+            sweeper_workers: parse_chain_values(
+                "SWEEPER_WORKERS",
+                &env::var("SWEEPER_WORKERS").unwrap_or_default(),
+            )?,
+            sweeper_default_workers: env::var("SWEEPER_DEFAULT_WORKERS")
+                .ok()
+                .map(|raw| raw.trim().parse())
+                .transpose()
+                .map_err(|e| AppError::ConfigError(format!("SWEEPER_DEFAULT_WORKERS is invalid: {}", e)))?
+                .unwrap_or(1),
+            sweeper_dry_run: env::var("SWEEPER_DRY_RUN")
+                .map(|raw| matches!(raw.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            dry_run_report: env::var("DRY_RUN_REPORT").unwrap_or_else(|_| "sweep_dry_run.json".to_string()),
         })
     }
+}
+
+
+/// Parses the treasury address, refusing the zero address and, for mixed-case
+/// input, a failed EIP-55 checksum (a typo there would otherwise send every sweep astray).
+pub fn parse_master_address(raw: &str) -> Result<Address, AppError> {
+    parse_treasury_address("MASTER_WALLET_ADDRESS", raw)
+}
+
+
+/// Parses a comma-separated list of treasury addresses from `var`, each checked like [`parse_master_address`].
+pub fn parse_treasury_addresses(var: &str, raw: &str) -> Result<Vec<Address>, AppError> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| parse_treasury_address(var, entry))
+        .collect()
+}
+
+
+fn parse_treasury_address(var: &str, raw: &str) -> Result<Address, AppError> {
+    let raw = raw.trim();
+    let digits = raw.strip_prefix("0x").unwrap_or(raw);
+    let mixed_case = digits.chars().any(|c| c.is_ascii_uppercase()) && digits.chars().any(|c| c.is_ascii_lowercase());
+
+    let address = if mixed_case {
+        Address::parse_checksummed(raw, None).map_err(|e| e.to_string())
+    } else {
+        Address::from_str(raw).map_err(|e| e.to_string())
+    }
+    .map_err(|e| AppError::ConfigError(format!("{} {} is invalid: {}", var, raw, e)))?;
+
+    if address.is_zero() {
+        return Err(AppError::ConfigError(format!("{} is the zero address", var)));
+    }
+
+    Ok(address)
+}
+
+
+/// Parses a `chain=value,chain=value` list into a per-chain map.
+fn parse_chain_values<T>(var: &str, raw: &str) -> Result<HashMap<String, T>, AppError>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let mut map = HashMap::new();
+
+    for entry in raw.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let (chain, value) = entry
+            .split_once('=')
+            .ok_or_else(|| AppError::ConfigError(format!("{} entry '{}' is not chain=value", var, entry)))?;
+
+        let value = T::from_str(value.trim())
+            .map_err(|e| AppError::ConfigError(format!("{} value for {} is invalid: {}", var, chain, e)))?;
+
+        map.insert(chain.trim().to_string(), value);
+    }
+
+    Ok(map)
 }
\ No newline at end of file
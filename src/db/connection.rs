@@ -12,7 +12,7 @@ pub async fn init_db()->Result<DbConnection, AppError>{
     let db_url = &config.database_url;
     let db = Database::connect(db_url)
         .await
-        .map_err(|e| AppError::DbError(e))?;
+        .map_err(AppError::DbError)?;
 
     Ok(DbConnection(db))
 
@@ -9,6 +9,12 @@ pub struct Model {
     pub id: i32,
     #[sea_orm(column_type = "Decimal(Some((78, 18)))")]
     pub vault_limit: Decimal,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub chain: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub token: Option<String>,
+    #[sea_orm(column_type = "Decimal(Some((78, 18)))", nullable)]
+    pub hot_floor: Option<Decimal>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
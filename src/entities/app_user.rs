@@ -70,6 +70,8 @@ pub enum Relation {
     LimboBetCash,
     #[sea_orm(has_many = "super::limbo_bet_points::Entity")]
     LimboBetPoints,
+    #[sea_orm(has_many = "super::pending_deposit::Entity")]
+    PendingDeposit,
     #[sea_orm(has_many = "super::point_table::Entity")]
     PointTable,
     #[sea_orm(has_many = "super::quests_submissions::Entity")]
@@ -178,6 +180,12 @@ impl Related<super::limbo_bet_points::Entity> for Entity {
     }
 }
 
+impl Related<super::pending_deposit::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PendingDeposit.def()
+    }
+}
+
 impl Related<super::point_table::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PointTable.def()
@@ -2,25 +2,32 @@
 
 pub mod prelude;
 
+pub mod account_nonce;
 pub mod admin_limits;
 pub mod app_user;
 pub mod bet_co_games;
 pub mod betco_transaction_table;
 pub mod cash_table;
 pub mod casino_game_analytic;
+pub mod chains;
 pub mod coinflip_bet_cash;
 pub mod coinflip_bet_points;
 pub mod contract_action;
 pub mod crash_bets;
 pub mod crash_bets_cash;
 pub mod deposit_receipt;
+pub mod detected_deposit;
 pub mod dice_bet_cash;
 pub mod dice_bet_points;
+pub mod dust_ledger;
 pub mod flagged_users;
 pub mod gas_donation;
+pub mod indexer_cursor;
 pub mod leaderboard;
 pub mod limbo_bet_cash;
 pub mod limbo_bet_points;
+pub mod nonce_filler;
+pub mod pending_deposit;
 pub mod point_table;
 pub mod processed_transaction;
 pub mod quests_submissions;
@@ -35,10 +42,16 @@ pub mod rake_back_update;
 pub mod referral_balance;
 pub mod referral_map;
 pub mod sbt_table;
+pub mod sea_orm_active_enums;
 pub mod suspicious_activities;
+pub mod sweep_attempt;
+pub mod token_metadata;
+pub mod tokens;
 pub mod user_balance;
 pub mod user_connection;
 pub mod user_connection_testnet;
 pub mod user_wallet;
+pub mod wallet_key_rotation;
+pub mod wallet_status_audit;
 pub mod withdraw_receipt;
 pub mod withdraw_request;
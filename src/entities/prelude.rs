@@ -1,24 +1,31 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.12
 
+pub use super::account_nonce::Entity as AccountNonce;
 pub use super::admin_limits::Entity as AdminLimits;
 pub use super::app_user::Entity as AppUser;
 pub use super::bet_co_games::Entity as BetCoGames;
 pub use super::betco_transaction_table::Entity as BetcoTransactionTable;
 pub use super::cash_table::Entity as CashTable;
 pub use super::casino_game_analytic::Entity as CasinoGameAnalytic;
+pub use super::chains::Entity as Chains;
 pub use super::coinflip_bet_cash::Entity as CoinflipBetCash;
 pub use super::coinflip_bet_points::Entity as CoinflipBetPoints;
 pub use super::contract_action::Entity as ContractAction;
 pub use super::crash_bets::Entity as CrashBets;
 pub use super::crash_bets_cash::Entity as CrashBetsCash;
 pub use super::deposit_receipt::Entity as DepositReceipt;
+pub use super::detected_deposit::Entity as DetectedDeposit;
 pub use super::dice_bet_cash::Entity as DiceBetCash;
 pub use super::dice_bet_points::Entity as DiceBetPoints;
+pub use super::dust_ledger::Entity as DustLedger;
 pub use super::flagged_users::Entity as FlaggedUsers;
 pub use super::gas_donation::Entity as GasDonation;
+pub use super::indexer_cursor::Entity as IndexerCursor;
 pub use super::leaderboard::Entity as Leaderboard;
 pub use super::limbo_bet_cash::Entity as LimboBetCash;
 pub use super::limbo_bet_points::Entity as LimboBetPoints;
+pub use super::nonce_filler::Entity as NonceFiller;
+pub use super::pending_deposit::Entity as PendingDeposit;
 pub use super::point_table::Entity as PointTable;
 pub use super::processed_transaction::Entity as ProcessedTransaction;
 pub use super::quests_submissions::Entity as QuestsSubmissions;
@@ -34,9 +41,14 @@ pub use super::referral_balance::Entity as ReferralBalance;
 pub use super::referral_map::Entity as ReferralMap;
 pub use super::sbt_table::Entity as SbtTable;
 pub use super::suspicious_activities::Entity as SuspiciousActivities;
+pub use super::sweep_attempt::Entity as SweepAttempt;
+pub use super::token_metadata::Entity as TokenMetadata;
+pub use super::tokens::Entity as Tokens;
 pub use super::user_balance::Entity as UserBalance;
 pub use super::user_connection::Entity as UserConnection;
 pub use super::user_connection_testnet::Entity as UserConnectionTestnet;
 pub use super::user_wallet::Entity as UserWallet;
+pub use super::wallet_key_rotation::Entity as WalletKeyRotation;
+pub use super::wallet_status_audit::Entity as WalletStatusAudit;
 pub use super::withdraw_receipt::Entity as WithdrawReceipt;
 pub use super::withdraw_request::Entity as WithdrawRequest;
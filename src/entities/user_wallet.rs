@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.12
 
+use super::sea_orm_active_enums::WalletStatus;
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
@@ -10,8 +11,7 @@ pub struct Model {
     pub user_id: Uuid,
     #[sea_orm(column_type = "Text", unique)]
     pub wallet_address: String,
-    #[sea_orm(column_type = "Text")]
-    pub status: String,
+    pub status: WalletStatus,
     pub active_token: String,
     pub active_chain: String,
     #[sea_orm(column_type = "Decimal(Some((78, 18)))")]
@@ -19,10 +19,26 @@ pub struct Model {
     #[sea_orm(column_type = "Decimal(Some((78, 18)))")]
     pub active_gas: Decimal,
     pub created_at: DateTimeWithTimeZone,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub claimed_by: Option<String>,
+    pub lease_expires_at: Option<DateTimeWithTimeZone>,
+    pub next_attempt_at: Option<DateTimeWithTimeZone>,
+    pub key_version: i16,
+    pub key_index: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::detected_deposit::Entity")]
+    DetectedDeposit,
+    #[sea_orm(has_many = "super::dust_ledger::Entity")]
+    DustLedger,
+    #[sea_orm(has_many = "super::sweep_attempt::Entity")]
+    SweepAttempt,
+    #[sea_orm(has_many = "super::wallet_key_rotation::Entity")]
+    WalletKeyRotation,
+    #[sea_orm(has_many = "super::wallet_status_audit::Entity")]
+    WalletStatusAudit,
     #[sea_orm(
         belongs_to = "super::app_user::Entity",
         from = "Column::UserId",
@@ -39,4 +55,34 @@ impl Related<super::app_user::Entity> for Entity {
     }
 }
 
+impl Related<super::detected_deposit::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DetectedDeposit.def()
+    }
+}
+
+impl Related<super::dust_ledger::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DustLedger.def()
+    }
+}
+
+impl Related<super::sweep_attempt::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SweepAttempt.def()
+    }
+}
+
+impl Related<super::wallet_key_rotation::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WalletKeyRotation.def()
+    }
+}
+
+impl Related<super::wallet_status_audit::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WalletStatusAudit.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
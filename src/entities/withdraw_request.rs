@@ -15,6 +15,17 @@ pub struct Model {
     pub amount: Decimal,
     #[sea_orm(column_type = "Text")]
     pub status: String,
+    pub sender: Option<String>,
+    pub nonce: Option<i64>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub raw_tx: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub tx_hash: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub failure_reason: Option<String>,
+    pub dropped_at_block: Option<i64>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub replaced_tx_hashes: Option<String>,
     pub updated_at: DateTimeWithTimeZone,
     pub created_at: DateTimeWithTimeZone,
 }
@@ -170,9 +170,7 @@ impl ResponseError for AppError {
                 _ => StatusCode::INTERNAL_SERVER_ERROR,
             },
             AppError::NotFound => StatusCode::NOT_FOUND,
-            AppError::RedisErr(err)=> match err{
-                _=>StatusCode::INTERNAL_SERVER_ERROR
-            },
+            AppError::RedisErr(_)=> StatusCode::INTERNAL_SERVER_ERROR,
             AppError::ReqwestError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
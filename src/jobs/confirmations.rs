@@ -0,0 +1,366 @@
+use std::{str::FromStr, time::Duration};
+
+use alloy::{
+    eips::BlockNumberOrTag, primitives::TxHash, providers::{Provider, RootProvider}
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseTransaction, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, TransactionTrait
+};
+use serde_json::json;
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+
+use crate::{
+    chain_config::{chain_config::create_read_provider, registry::{ChainEntry, registry}},
+    entities::{pending_deposit, prelude::{PendingDeposit, SweepAttempt, UserWallet}, sea_orm_active_enums::WalletStatus, sweep_attempt, user_wallet},
+    error::error::AppError,
+    state_models::models::DbConnection,
+    utils::{
+        dust_policy::reopen_dust, suspicious_activity::record_suspicious_activity, sweep_outbox::{parse_address, rebroadcast, set_attempt_state},
+        update_deposit::{reverse_user_balance_and_receipt, upsert_user_balance_and_receipt}, wallet_lifecycle::transition_wallet,
+    },
+};
+
+const CONFIRMATION_INTERVAL: Duration = Duration::from_secs(15);
+
+
+/// Promotes mined sweeps to credited balances once they are deep enough.
+///
+/// Every cycle, for each registry chain:
+/// - `PENDING` sweeps are credited once they have `confirmations` blocks on top
+///   of them or fall behind the chain's `finalized` block.
+/// - `CREDITED` sweeps are watched until they are finalized, or until they are
+///   `finality_depth` blocks deep on chains without the `finalized` tag.
+/// - A sweep whose block left the canonical chain waits as `PENDING` again
+///   while it can still be mined. Once its nonce is used by another
+///   transaction it is marked `REORGED` (never credited) or `REVERSED` (credit
+///   rolled back), and raises a `suspicious_activities` alert either way.
+pub async fn run_confirmation_tracker(db: DbConnection) -> Result<(), AppError> {
+    println!("CONFIRMATION TRACKER RUNNING");
+    loop {
+        let registry = registry();
+
+        for chain in registry.chains() {
+            if let Err(e) = track_chain(chain, &db).await {
+                error!("Confirmation tracking failed on {}: {}", chain.name, e);
+            }
+        }
+
+        sleep(CONFIRMATION_INTERVAL).await;
+    }
+}
+
+
+
+/// One confirmation pass over the `PENDING` and `CREDITED` sweeps of `chain`.
+pub async fn track_chain(chain: &ChainEntry, db: &DbConnection) -> Result<(), AppError> {
+
+    let deposits = PendingDeposit::find()
+        .filter(pending_deposit::Column::Chain.eq(chain.name.as_str()))
+        .filter(pending_deposit::Column::Status.is_in(["PENDING", "CREDITED"]))
+        .order_by_asc(pending_deposit::Column::BlockNumber)
+        .all(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    if deposits.is_empty() {
+        return Ok(());
+    }
+
+    let provider = create_read_provider(&chain.name).await?;
+
+    let head = provider.get_block_number().await
+        .map_err(|e| AppError::InternalError(format!("Cannot fetch block number: {e}")))?;
+
+    // Not every chain serves the `finalized` tag; depth alone decides there
+    let finalized = match provider.get_block_by_number(BlockNumberOrTag::Finalized).await {
+        Ok(Some(block)) => Some(block.header.number),
+        _ => None,
+    };
+
+    for deposit in deposits {
+        if let Err(e) = track_deposit(&provider, db, chain, head, finalized, deposit).await {
+            warn!("Cannot track deposit on {}: {}", chain.name, e);
+        }
+    }
+
+    Ok(())
+}
+
+
+
+async fn track_deposit(
+    provider: &RootProvider,
+    db: &DbConnection,
+    chain: &ChainEntry,
+    head: u64,
+    finalized: Option<u64>,
+    deposit: pending_deposit::Model,
+) -> Result<(), AppError> {
+
+    let tx_hash = TxHash::from_str(&deposit.tx_hash)
+        .map_err(|e| AppError::InternalError(format!("Invalid tx hash {}: {e}", deposit.tx_hash)))?;
+
+    let receipt = provider.get_transaction_receipt(tx_hash).await
+        .map_err(|e| AppError::InternalError(format!("Cannot fetch receipt: {e}")))?;
+
+    let canonical = receipt
+        .filter(|receipt| receipt.status())
+        .and_then(|receipt| Some((receipt.block_number?, receipt.block_hash?)));
+
+    let Some((block_number, block_hash)) = canonical else {
+        return handle_orphaned(provider, db, deposit).await;
+    };
+
+    let txn = db.0.begin().await.map_err(AppError::DbError)?;
+    let status = deposit.status.clone();
+    let mut active: pending_deposit::ActiveModel = deposit.clone().into();
+
+    // Re-included in a different block after a shallow reorg: track the new one
+    if block_hash.to_string() != deposit.block_hash {
+        info!("Sweep {} moved from block {} to {}", deposit.tx_hash, deposit.block_number, block_number);
+        active.block_number = Set(block_number as i64);
+        active.block_hash = Set(block_hash.to_string());
+    }
+
+    let depth = head.saturating_sub(block_number) + 1;
+    let is_finalized = match finalized {
+        Some(finalized) => block_number <= finalized,
+        None => depth >= chain.finality_depth.max(chain.confirmations),
+    };
+
+    if status == "PENDING" && (depth >= chain.confirmations || is_finalized) {
+        upsert_user_balance_and_receipt(
+                &txn,
+                deposit.user_id,
+                &deposit.wallet_address,
+                &deposit.token,
+                &deposit.chain,
+                deposit.amount,
+                &deposit.tx_hash,
+            )
+            .await?;
+
+        println!("Credited sweep {} after {} confirmations", deposit.tx_hash, depth);
+        active.status = Set(if is_finalized { "FINALIZED" } else { "CREDITED" }.to_string());
+        active.credited_at = Set(Some(chrono::Utc::now().into()));
+    } else if status == "CREDITED" && is_finalized {
+        active.status = Set("FINALIZED".to_string());
+    }
+
+    active.updated_at = Set(chrono::Utc::now().into());
+    active.update(&txn).await.map_err(AppError::DbError)?;
+
+    release_confirmed_wallet(&txn, &deposit.wallet_address).await?;
+    txn.commit().await.map_err(AppError::DbError)?;
+
+    Ok(())
+}
+
+
+
+/// Frees a `CONFIRMING` wallet once none of its sweeps are still waiting for depth.
+async fn release_confirmed_wallet(txn: &DatabaseTransaction, wallet_address: &str) -> Result<(), AppError> {
+
+    let still_pending = PendingDeposit::find()
+        .filter(pending_deposit::Column::WalletAddress.eq(wallet_address))
+        .filter(pending_deposit::Column::Status.eq("PENDING"))
+        .count(txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    if still_pending > 0 {
+        return Ok(());
+    }
+
+    let wallet = UserWallet::find()
+        .filter(user_wallet::Column::WalletAddress.eq(wallet_address))
+        .filter(user_wallet::Column::Status.eq(WalletStatus::Confirming))
+        .one(txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    if let Some(wallet) = wallet {
+        transition_wallet(txn, wallet, WalletStatus::Free, "all sweeps credited").await?;
+    }
+
+    Ok(())
+}
+
+
+
+/// Handles a sweep with no successful receipt on the canonical chain.
+///
+/// A missing receipt alone can be RPC lag, so nothing happens while the block
+/// it was recorded in is still canonical. Once it is not, a reorged-out
+/// transaction usually goes back to the mempool and is mined again: while the
+/// node still knows it, or its nonce is still unused, the sweep goes back to
+/// waiting (see [`return_to_pending`]). It is only treated as reorged once the
+/// wallet's nonce was taken by a different transaction.
+async fn handle_orphaned(
+    provider: &RootProvider,
+    db: &DbConnection,
+    deposit: pending_deposit::Model,
+) -> Result<(), AppError> {
+
+    let canonical_block = provider
+        .get_block_by_number(BlockNumberOrTag::Number(deposit.block_number as u64))
+        .await
+        .map_err(|e| AppError::InternalError(format!("Cannot fetch block: {e}")))?;
+
+    if canonical_block.is_some_and(|block| block.header.hash.to_string() == deposit.block_hash) {
+        return Ok(());
+    }
+
+    let tx_hash = TxHash::from_str(&deposit.tx_hash)
+        .map_err(|e| AppError::InternalError(format!("Invalid tx hash {}: {e}", deposit.tx_hash)))?;
+
+    let known = provider.get_transaction_by_hash(tx_hash).await
+        .map_err(|e| AppError::InternalError(format!("Cannot fetch transaction: {e}")))?;
+
+    let attempt = SweepAttempt::find()
+        .filter(sweep_attempt::Column::TxHash.eq(deposit.tx_hash.as_str()))
+        .one(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    if let Some(known) = known {
+        // Mined again in a block whose receipt this endpoint does not serve yet
+        if known.block_number.is_some() {
+            return Ok(());
+        }
+        return return_to_pending(db, deposit, attempt.as_ref()).await;
+    }
+
+    if let Some(attempt) = &attempt {
+        let sender = parse_address(&attempt.wallet_address)?;
+        let mined_nonce = provider.get_transaction_count(sender).latest().await
+            .map_err(|e| AppError::InternalError(format!("Cannot fetch nonce: {e}")))?;
+
+        if mined_nonce <= attempt.nonce as u64 {
+            let raw_tx = hex::decode(&attempt.raw_tx)
+                .map_err(|e| AppError::InternalError(format!("Invalid raw tx for {}: {e}", attempt.tx_hash)))?;
+            rebroadcast(provider, &raw_tx, &attempt.tx_hash, &attempt.chain).await;
+
+            return return_to_pending(db, deposit, Some(attempt)).await;
+        }
+    }
+
+    let txn = db.0.begin().await.map_err(AppError::DbError)?;
+    let was_credited = deposit.status == "CREDITED";
+
+    if was_credited {
+        reverse_user_balance_and_receipt(
+                &txn,
+                deposit.user_id,
+                &deposit.wallet_address,
+                &deposit.token,
+                &deposit.chain,
+                deposit.amount,
+                &deposit.tx_hash,
+            )
+            .await?;
+    }
+
+    record_suspicious_activity(
+            &txn,
+            &deposit.user_id.to_string(),
+            "DEPOSIT_REORG",
+            if was_credited { "HIGH" } else { "MEDIUM" },
+            json!({
+                "chain": deposit.chain,
+                "token": deposit.token,
+                "wallet_address": deposit.wallet_address,
+                "amount": deposit.amount.to_string(),
+                "tx_hash": deposit.tx_hash,
+                "block_number": deposit.block_number,
+                "block_hash": deposit.block_hash,
+                "reversed": was_credited,
+            }),
+        )
+        .await?;
+
+    // Dust this sweep settled is back on the wallet with the reorged funds
+    reopen_dust(&txn, &deposit.tx_hash).await?;
+
+    let mut active: pending_deposit::ActiveModel = deposit.clone().into();
+    active.status = Set(if was_credited { "REVERSED" } else { "REORGED" }.to_string());
+    active.updated_at = Set(chrono::Utc::now().into());
+    active.update(&txn).await.map_err(AppError::DbError)?;
+
+    if let Some(attempt) = &attempt {
+        set_attempt_state(&txn, attempt.id, "DROPPED").await?;
+    }
+
+    // The reorged transfer may have put the funds back on the deposit wallet
+    let wallet = UserWallet::find()
+        .filter(user_wallet::Column::WalletAddress.eq(deposit.wallet_address.as_str()))
+        .filter(user_wallet::Column::Status.is_in([WalletStatus::Confirming, WalletStatus::Free]))
+        .one(&txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    if let Some(wallet) = wallet {
+        transition_wallet(&txn, wallet, WalletStatus::Sweepable, "sweep reorged out, re-checking balances").await?;
+    }
+
+    txn.commit().await.map_err(AppError::DbError)?;
+
+    error!("Sweep {} on {} was reorged out of block {}", deposit.tx_hash, deposit.chain, deposit.block_number);
+
+    Ok(())
+}
+
+
+
+/// Puts a sweep that was reorged out but can still be mined back to `PENDING`.
+///
+/// A credit it already got is rolled back, to be given again once the
+/// transaction is buried in its new block; `track_deposit` picks up the new
+/// block from the receipt. Its outbox attempt goes back to `BROADCAST` so the
+/// recovery pass re-broadcasts it and notices if its nonce is taken. The wallet
+/// stays `CONFIRMING` rather than being re-queued while the funds may still leave.
+async fn return_to_pending(
+    db: &DbConnection,
+    deposit: pending_deposit::Model,
+    attempt: Option<&sweep_attempt::Model>,
+) -> Result<(), AppError> {
+
+    let attempt = attempt.filter(|attempt| attempt.state == "MINED");
+
+    // Already waiting since an earlier pass
+    if deposit.status != "CREDITED" && attempt.is_none() {
+        return Ok(());
+    }
+
+    let txn = db.0.begin().await.map_err(AppError::DbError)?;
+
+    if deposit.status == "CREDITED" {
+        reverse_user_balance_and_receipt(
+                &txn,
+                deposit.user_id,
+                &deposit.wallet_address,
+                &deposit.token,
+                &deposit.chain,
+                deposit.amount,
+                &deposit.tx_hash,
+            )
+            .await?;
+
+        let mut active: pending_deposit::ActiveModel = deposit.clone().into();
+        active.status = Set("PENDING".to_string());
+        active.credited_at = Set(None);
+        active.updated_at = Set(chrono::Utc::now().into());
+        active.update(&txn).await.map_err(AppError::DbError)?;
+    }
+
+    if let Some(attempt) = attempt {
+        set_attempt_state(&txn, attempt.id, "BROADCAST").await?;
+    }
+
+    txn.commit().await.map_err(AppError::DbError)?;
+
+    warn!("Sweep {} on {} left block {} but can still be mined, waiting for it again", deposit.tx_hash, deposit.chain, deposit.block_number);
+
+    Ok(())
+}
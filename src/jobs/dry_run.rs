@@ -0,0 +1,197 @@
+use std::collections::BTreeMap;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    chain_config::registry::registry, entities::user_wallet, error::error::AppError, jobs::sweeper::dry_run_chain, state_models::models::DbConnection
+};
+
+
+/// Everything a sweep cycle would have done, written by `run_dry_run`.
+#[derive(Debug, Serialize)]
+pub struct DryRunReport {
+    pub generated_at: String,
+    pub chains: Vec<ChainReport>,
+}
+
+
+/// Planned sweeps of one chain with per-token totals.
+#[derive(Debug, Serialize)]
+pub struct ChainReport {
+    pub chain: String,
+    /// Keyed by token symbol (the native symbol for native sweeps).
+    pub totals: BTreeMap<String, TokenTotals>,
+    pub wallets_lacking_gas: usize,
+    pub wallets: Vec<WalletReport>,
+    /// Set when the chain could not be planned at all (e.g. every RPC is down).
+    pub error: Option<String>,
+}
+
+
+#[derive(Debug, Default, Serialize)]
+pub struct TokenTotals {
+    /// Amount the sweeps would move to the master wallet.
+    pub swept: Decimal,
+    /// Amount that would be credited to `user_balance`, dust included.
+    pub credited: Decimal,
+}
+
+
+/// What one queued wallet would go through.
+#[derive(Debug, Serialize)]
+pub struct WalletReport {
+    pub wallet_id: Uuid,
+    pub user_id: Uuid,
+    pub wallet_address: String,
+    pub actions: Vec<PlannedAction>,
+    /// Status the live sweep would leave the wallet in.
+    pub next_status: Option<String>,
+    pub reason: Option<String>,
+    pub error: Option<String>,
+}
+
+impl WalletReport {
+    pub fn new(wallet: &user_wallet::Model, actions: Vec<PlannedAction>) -> Self {
+        Self {
+            wallet_id: wallet.id,
+            user_id: wallet.user_id,
+            wallet_address: wallet.wallet_address.clone(),
+            actions,
+            next_status: None,
+            reason: None,
+            error: None,
+        }
+    }
+}
+
+
+/// A step the live sweep would take for one asset of a wallet.
+#[derive(Debug, Serialize)]
+#[serde(tag = "action", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PlannedAction {
+    /// Transfer `amount` to `destination` and credit `credit` once confirmed.
+    Sweep {
+        token: String,
+        symbol: String,
+        amount: Decimal,
+        credit: Decimal,
+        /// Treasury wallet the sweep pays out to.
+        destination: String,
+        /// Whether the hot wallet is at its vault limit and the sweep goes to cold storage.
+        cold: bool,
+        gas_limit: u64,
+        /// Worst-case fee in wei.
+        max_fee: String,
+    },
+    /// Leave a balance below the sweep threshold to the token's dust policy.
+    Dust {
+        token: String,
+        symbol: String,
+        amount: Decimal,
+        /// `ACCUMULATED`, `CREDITED`, or `UNCHANGED` when already recorded.
+        decision: String,
+        credit: Decimal,
+    },
+    /// The wallet holds the token but not the gas to move it; the gas station would be asked.
+    NeedsGas {
+        token: String,
+        symbol: String,
+        /// Native balance and the fee the transfer needs, in wei.
+        gas_balance: String,
+        minimum_gas: String,
+    },
+    /// Fees are above the chain's policy; the wallet would be retried later.
+    FeesTooHigh {
+        token: String,
+        symbol: String,
+    },
+}
+
+
+
+/// Plans one sweep cycle of every registry chain and writes the report to `path`.
+///
+/// Used instead of starting the workers when `SWEEPER_DRY_RUN` is set: nothing
+/// is claimed, signed, broadcast or credited.
+pub async fn run_dry_run(db: &DbConnection, path: &str) -> Result<(), AppError> {
+
+    let mut chains = Vec::new();
+
+    for chain in registry().chains() {
+        let (wallets, error) = match dry_run_chain(&chain.name, db).await {
+            Ok(wallets) => (wallets, None),
+            Err(e) => (Vec::new(), Some(e.to_string())),
+        };
+
+        chains.push(summarize_chain(&chain.name, wallets, error));
+    }
+
+    let report = DryRunReport {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        chains,
+    };
+
+    for chain in &report.chains {
+        println!(
+            "Dry run {}: {} wallets, {} lacking gas{}",
+            chain.chain,
+            chain.wallets.len(),
+            chain.wallets_lacking_gas,
+            chain.error.as_deref().map(|e| format!(", failed: {e}")).unwrap_or_default()
+        );
+        for (symbol, totals) in &chain.totals {
+            println!("  {} would sweep {} and credit {}", symbol, totals.swept, totals.credited);
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| AppError::InternalError(format!("Cannot serialize dry run report: {e}")))?;
+
+    tokio::fs::write(path, json).await
+        .map_err(|e| AppError::InternalError(format!("Cannot write dry run report to {path}: {e}")))?;
+
+    println!("Dry run report written to {}", path);
+
+    Ok(())
+}
+
+
+
+fn summarize_chain(chain: &str, wallets: Vec<WalletReport>, error: Option<String>) -> ChainReport {
+
+    let mut totals: BTreeMap<String, TokenTotals> = BTreeMap::new();
+    let mut wallets_lacking_gas = 0;
+
+    for wallet in &wallets {
+        let mut lacks_gas = false;
+
+        for action in &wallet.actions {
+            match action {
+                PlannedAction::Sweep { symbol, amount, credit, .. } => {
+                    let total = totals.entry(symbol.clone()).or_default();
+                    total.swept += amount;
+                    total.credited += credit;
+                }
+                PlannedAction::Dust { symbol, credit, .. } => {
+                    totals.entry(symbol.clone()).or_default().credited += credit;
+                }
+                PlannedAction::NeedsGas { .. } => lacks_gas = true,
+                PlannedAction::FeesTooHigh { .. } => {}
+            }
+        }
+
+        if lacks_gas {
+            wallets_lacking_gas += 1;
+        }
+    }
+
+    ChainReport {
+        chain: chain.to_string(),
+        totals,
+        wallets_lacking_gas,
+        wallets,
+        error,
+    }
+}
@@ -1,9 +1,12 @@
 
 
+use crate::chain_config::registry::{registry, reload_registry};
 use crate::error::error::AppError;
 use crate::jobs::sweeper::sweep_wallet;
+use crate::jobs::withdrawals::process_withdrawals;
 use crate::state_models::models::DbConnection;
 use std::time::Duration;  
+use alloy_signer_local::PrivateKeySigner;
 use sea_orm::ConnectionTrait;
 use tokio;
 use tokio::time::sleep;
@@ -13,20 +16,36 @@ const WITHDRAW_INTERVAL: Duration = Duration::from_secs(2);
 pub const MAX_RETRIES: u32 = 1;
 pub const RETRY_BACKOFF: Duration = Duration::from_secs(1);
 
+/// Sweeps wallets queued for `chain_name` until the process stops.
+///
+/// Each chain gets its own pool of these workers (see `SWEEPER_WORKERS`), so a
+/// degraded RPC only slows down the pool of the chain it serves.
 pub async fn run_sweeper(
+    chain_name: String,
     worker_id: u64,
     db: DbConnection,
 ) ->  Result<(), AppError> {
-    println!("WORKER:{} RUNNING", worker_id);
+    println!("WORKER:{}:{} RUNNING", chain_name, worker_id);
     loop {
+        // Pick up chain/token registry edits without a restart; keep the last snapshot on failure
+        if let Err(e) = reload_registry(&db).await {
+            warn!("Registry reload failed, using previous snapshot: {}", e);
+        }
+
+        // Disabled chains keep their pool idle until they are re-enabled
+        if registry().chain(&chain_name).is_none() {
+            sleep(WITHDRAW_INTERVAL).await;
+            continue;
+        }
+
         let mut retries = 0;
         let result = loop {
 
-            match sweep_wallet(worker_id, &db ).await {
+            match sweep_wallet(&chain_name, worker_id, &db ).await {
                 Ok(_) => break Ok(()),
                 Err(e) if retries >= MAX_RETRIES => break Err(e),
                 Err(e) => {
-                    warn!("Price submit failed (attempt {}): {}", retries + 1, e);
+                    warn!("Sweep cycle on {} failed (attempt {}): {}", chain_name, retries + 1, e);
                     retries += 1;
                     sleep(RETRY_BACKOFF * retries).await;
                 }
@@ -34,8 +53,8 @@ pub async fn run_sweeper(
         };
 
         match result {
-            Ok(_) => info!("Successfully submitted prices at {}", chrono::Local::now()),
-            Err(e) => error!("Fatal error submitting prices: {}", e),
+            Ok(_) => info!("Sweep cycle on {} (worker {}) finished at {}", chain_name, worker_id, chrono::Local::now()),
+            Err(e) => error!("Sweep cycle on {} (worker {}) gave up after {} retries: {}", chain_name, worker_id, MAX_RETRIES, e),
         }
 
         sleep(WITHDRAW_INTERVAL).await;
@@ -48,6 +67,29 @@ pub async fn run_sweeper(
 
 
 
+/// Pays out `PENDING` withdrawals of `chain_name` from the hot wallet until the process stops.
+///
+/// Runs next to the chain's sweeper pool. One worker per chain keeps the hot
+/// wallet's transfers in request order; its nonces come from the shared allocator.
+pub async fn run_withdrawal_worker(
+    chain_name: String,
+    db: DbConnection,
+    hot_wallet: PrivateKeySigner,
+) -> Result<(), AppError> {
+    info!("Withdrawal worker for {} running", chain_name);
+    loop {
+        // Disabled chains stop paying out until they are re-enabled; the sweeper pool reloads the registry
+        if registry().chain(&chain_name).is_some()
+            && let Err(e) = process_withdrawals(&chain_name, &db, &hot_wallet).await {
+                error!("Withdrawal cycle on {} failed: {}", chain_name, e);
+        }
+
+        sleep(WITHDRAW_INTERVAL).await;
+    }
+}
+
+
+
 pub async fn between_cycles_cleanup(db: &DbConnection) {
     // 1. Check connection health by executing a simple query
     if let Err(e) = db.0.execute_unprepared("SELECT 1").await {
@@ -55,10 +97,9 @@ pub async fn between_cycles_cleanup(db: &DbConnection) {
     }
 
     // 2. Log memory statistics (Linux-specific)
-    if cfg!(target_os = "linux") {
-        if let Ok(usage) = get_memory_usage() {
+    if cfg!(target_os = "linux")
+        && let Ok(usage) = get_memory_usage() {
             info!("Memory usage: {}MB resident", usage / 1024 / 1024);
-        }
     }
 
     // 3. Force Tokio to reclaim resources
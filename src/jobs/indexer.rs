@@ -0,0 +1,413 @@
+use std::{collections::HashMap, time::Duration};
+
+use alloy::{
+    primitives::Address, providers::{Provider, RootProvider}, rpc::types::{Filter, Log}, sol_types::SolEvent
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseTransaction, EntityTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait, sea_query::{Expr, OnConflict}
+};
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    chain_config::{chain_config::{create_read_provider, wallet_address}, key_deriver::WalletKey, registry::{ChainEntry, TokenEntry, registry}},
+    entities::{
+        detected_deposit, indexer_cursor, prelude::{DetectedDeposit, IndexerCursor, UserWallet, WalletKeyRotation}, sea_orm_active_enums::WalletStatus,
+        user_wallet, wallet_key_rotation,
+    },
+    error::error::AppError,
+    jobs::key_rotation::restore_rotated_key,
+    state_models::models::DbConnection,
+    utils::{token_decimals::{ERC20, u256_to_decimal}, wallet_lifecycle::transition_wallet},
+};
+
+const INDEXER_INTERVAL: Duration = Duration::from_secs(10);
+/// Most RPC providers cap `eth_getLogs` ranges somewhere between 1k and 10k blocks.
+const MAX_BLOCK_RANGE: u64 = 2_000;
+/// Blocks re-scanned behind the cursor so logs re-included by a shallow reorg are not missed.
+const REORG_RESCAN: u64 = 12;
+/// How far back a chain without a cursor starts scanning.
+const INITIAL_LOOKBACK: u64 = 5_000;
+
+/// Wallet states a detected deposit can move straight to `SWEEPABLE`.
+///
+/// Parked `GAS_PENDING`/`FAILED` wallets are included: a new deposit may bring
+/// the gas they lacked, and is worth another attempt either way.
+const QUEUEABLE_STATES: [WalletStatus; 6] = [
+    WalletStatus::Assigned,
+    WalletStatus::AwaitingDeposit,
+    WalletStatus::Free,
+    WalletStatus::Confirming,
+    WalletStatus::GasPending,
+    WalletStatus::Failed,
+];
+
+
+/// Detects deposits into user wallets from ERC-20 `Transfer` logs.
+///
+/// For each registry chain, scans the `Transfer` logs of every registered token
+/// from the chain's `indexer_cursor` to the head, in `MAX_BLOCK_RANGE` chunks.
+/// Transfers whose `to` is a `user_wallet` are written to `detected_deposit` and
+/// the wallet is marked `SWEEPABLE` with its `active_chain`/`active_token` set.
+/// Addresses a wallet was rotated away from are watched as well (see
+/// [`queue_deposit`]). Wallets that are busy (mid-sweep, quarantined...) keep their
+/// deposit `DEFERRED` and are re-queued on a later cycle.
+pub async fn run_deposit_indexer(db: DbConnection) -> Result<(), AppError> {
+    println!("DEPOSIT INDEXER RUNNING");
+    loop {
+        let registry = registry();
+
+        for chain in registry.chains() {
+            if let Err(e) = index_chain(chain, registry.tokens(&chain.name), &db).await {
+                error!("Deposit indexing failed on {}: {}", chain.name, e);
+            }
+        }
+
+        if let Err(e) = queue_deferred_deposits(&db).await {
+            warn!("Cannot queue deferred deposits: {}", e);
+        }
+
+        sleep(INDEXER_INTERVAL).await;
+    }
+}
+
+
+
+async fn index_chain(chain: &ChainEntry, tokens: &[TokenEntry], db: &DbConnection) -> Result<(), AppError> {
+
+    if tokens.is_empty() {
+        return Ok(());
+    }
+
+    let provider = create_read_provider(&chain.name).await?;
+
+    let head = provider.get_block_number().await
+        .map_err(|e| AppError::InternalError(format!("Cannot fetch block number: {e}")))?;
+
+    let cursor = IndexerCursor::find_by_id(chain.name.clone())
+        .one(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    let mut next_block = match cursor {
+        Some(cursor) => (cursor.last_block.max(0) as u64 + 1).saturating_sub(REORG_RESCAN),
+        None => head.saturating_sub(INITIAL_LOOKBACK),
+    };
+
+    while next_block <= head {
+        let to_block = head.min(next_block + MAX_BLOCK_RANGE - 1);
+        index_range(&provider, db, chain, tokens, next_block, to_block).await?;
+        next_block = to_block + 1;
+    }
+
+    Ok(())
+}
+
+
+
+/// Records the deposits in `from_block..=to_block` and advances the cursor atomically.
+async fn index_range(
+    provider: &RootProvider,
+    db: &DbConnection,
+    chain: &ChainEntry,
+    tokens: &[TokenEntry],
+    from_block: u64,
+    to_block: u64,
+) -> Result<(), AppError> {
+
+    let filter = Filter::new()
+        .address(tokens.iter().map(|token| token.address).collect::<Vec<_>>())
+        .event_signature(ERC20::Transfer::SIGNATURE_HASH)
+        .from_block(from_block)
+        .to_block(to_block);
+
+    let logs = provider.get_logs(&filter).await.map_err(|e|{
+            eprintln!("Error fetching Transfer logs {}..{} on {}: {:?}", from_block, to_block, chain.name, e);
+            AppError::InternalError(format!("Provider error: {e}"))
+    })?;
+
+    let wallets = find_wallets(db, &logs).await?;
+
+    let txn = db.0.begin().await.map_err(AppError::DbError)?;
+    let mut detected = 0;
+
+    for log in &logs {
+        let Ok(transfer) = log.log_decode::<ERC20::Transfer>() else {
+            continue;
+        };
+        let ERC20::Transfer { from, to, value } = transfer.inner.data;
+
+        let (Some(wallet), Some(token)) = (
+            wallets.get(&to),
+            tokens.iter().find(|token| token.address == log.address()),
+        ) else {
+            continue;
+        };
+
+        if value.is_zero() {
+            continue;
+        }
+
+        let (Some(tx_hash), Some(log_index), Some(block_number), Some(block_hash)) =
+            (log.transaction_hash, log.log_index, log.block_number, log.block_hash)
+        else {
+            continue;
+        };
+
+        let deposit = detected_deposit::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            user_id: Set(wallet.user_id),
+            wallet_id: Set(wallet.id),
+            wallet_address: Set(wallet.wallet_address.clone()),
+            chain: Set(chain.name.clone()),
+            token: Set(token.address.to_string()),
+            from_address: Set(from.to_string()),
+            amount: Set(u256_to_decimal(value, token.decimals)?),
+            tx_hash: Set(tx_hash.to_string()),
+            log_index: Set(log_index as i64),
+            block_number: Set(block_number as i64),
+            block_hash: Set(block_hash.to_string()),
+            status: Set("DEFERRED".to_string()),
+            created_at: Set(chrono::Utc::now().into()),
+        };
+
+        let inserted = DetectedDeposit::insert(deposit)
+            .on_conflict(
+                OnConflict::columns([
+                    detected_deposit::Column::Chain,
+                    detected_deposit::Column::TxHash,
+                    detected_deposit::Column::LogIndex,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(&txn)
+            .await
+            .map_err(AppError::DbError)?;
+
+        // Already seen on an earlier (overlapping) scan
+        if inserted == 0 {
+            continue;
+        }
+
+        println!("Detected deposit {} {} to Wallet:{} Chain:{} Tx:{}", value, token.symbol, wallet.wallet_address, chain.name, tx_hash);
+        detected += 1;
+
+        queue_deposit(&txn, wallet.id, &wallet.wallet_address, &chain.name, &token.address.to_string()).await?;
+    }
+
+    let cursor = indexer_cursor::ActiveModel {
+        chain: Set(chain.name.clone()),
+        last_block: Set(to_block as i64),
+        updated_at: Set(chrono::Utc::now().into()),
+    };
+
+    IndexerCursor::insert(cursor)
+        .on_conflict(
+            OnConflict::column(indexer_cursor::Column::Chain)
+                .update_columns([indexer_cursor::Column::LastBlock, indexer_cursor::Column::UpdatedAt])
+                .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    txn.commit().await.map_err(AppError::DbError)?;
+
+    if detected > 0 {
+        info!("Detected {} deposits on {} in blocks {}..{}", detected, chain.name, from_block, to_block);
+    }
+
+    Ok(())
+}
+
+
+
+/// Loads the user wallets receiving any of `logs`, keyed by address.
+///
+/// Addresses are matched in both checksummed and lowercase form, since older
+/// rows were not always stored checksummed. An address the wallet was rotated
+/// away from maps to the wallet as it was under that old key, as long as the
+/// old key is still derivable; once its secret is retired the address is dropped.
+async fn find_wallets(db: &DbConnection, logs: &[Log]) -> Result<HashMap<Address, user_wallet::Model>, AppError> {
+
+    let mut candidates: Vec<String> = logs
+        .iter()
+        .filter_map(|log| log.log_decode::<ERC20::Transfer>().ok())
+        .flat_map(|transfer| {
+            let to = transfer.inner.data.to;
+            [to.to_string(), to.to_string().to_lowercase()]
+        })
+        .collect();
+
+    candidates.sort();
+    candidates.dedup();
+
+    if candidates.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let wallets = UserWallet::find()
+        .filter(user_wallet::Column::WalletAddress.is_in(candidates.clone()))
+        .all(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    let mut found: HashMap<Address, user_wallet::Model> = wallets
+        .into_iter()
+        .filter_map(|wallet| Some((wallet.wallet_address.parse::<Address>().ok()?, wallet)))
+        .collect();
+
+    let rotations = WalletKeyRotation::find()
+        .filter(wallet_key_rotation::Column::OldAddress.is_in(candidates))
+        .order_by_desc(wallet_key_rotation::Column::RotatedAt)
+        .all(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    for rotation in rotations {
+        let Ok(old_address) = rotation.old_address.parse::<Address>() else {
+            continue;
+        };
+
+        // The wallet is on this address again, or a later rotation away from it was seen first
+        if found.contains_key(&old_address) {
+            continue;
+        }
+
+        let Some(wallet) = UserWallet::find_by_id(rotation.wallet_id).one(&db.0).await.map_err(AppError::DbError)? else {
+            continue;
+        };
+
+        let old_key = WalletKey { user_id: wallet.user_id, version: rotation.old_key_version, index: rotation.old_key_index };
+        if !wallet_address(&old_key).await.is_ok_and(|derived| derived == old_address) {
+            warn!("Ignoring transfer to {}: the key Wallet:{} was rotated away from is no longer derivable", old_address, wallet.id);
+            continue;
+        }
+
+        found.insert(old_address, user_wallet::Model {
+            wallet_address: rotation.old_address,
+            key_version: rotation.old_key_version,
+            key_index: rotation.old_key_index,
+            ..wallet
+        });
+    }
+
+    Ok(found)
+}
+
+
+
+/// Queues a deposit sent to `address`, the wallet's current address or one it was rotated away from.
+///
+/// The sweeper only sweeps a wallet's current address, so a deposit to an old
+/// one waits `DEFERRED` until the wallet is `FREE` and can be moved back onto
+/// the old key with [`restore_rotated_key`].
+pub async fn queue_deposit(
+    txn: &DatabaseTransaction,
+    wallet_id: Uuid,
+    address: &str,
+    chain: &str,
+    token: &str,
+) -> Result<bool, AppError> {
+
+    let Some(wallet) = UserWallet::find_by_id(wallet_id)
+        .lock_exclusive()
+        .one(txn)
+        .await
+        .map_err(AppError::DbError)?
+    else {
+        return Ok(false);
+    };
+
+    if wallet.wallet_address != address && !restore_rotated_key(txn, wallet, address).await? {
+        return Ok(false);
+    }
+
+    queue_wallet(txn, wallet_id, chain, token).await
+}
+
+
+
+/// Marks the wallet `SWEEPABLE` for `chain`/`token` if it is free to be swept.
+///
+/// Setting `active_chain` routes the wallet to that chain's sweeper pool.
+/// The `DEFERRED` deposits on `chain` to the wallet's current address become
+/// `QUEUED` when it is; otherwise they stay `DEFERRED` for [`queue_deferred_deposits`] to retry.
+pub async fn queue_wallet(txn: &DatabaseTransaction, wallet_id: Uuid, chain: &str, token: &str) -> Result<bool, AppError> {
+
+    let Some(wallet) = UserWallet::find_by_id(wallet_id)
+        .lock_exclusive()
+        .one(txn)
+        .await
+        .map_err(AppError::DbError)?
+    else {
+        return Ok(false);
+    };
+
+    // Sweepers only sweep a wallet's `active_chain`; a deposit on another chain waits its turn
+    if wallet.status == WalletStatus::Sweepable && wallet.active_chain != chain {
+        return Ok(false);
+    }
+
+    let address = wallet.wallet_address.clone();
+
+    if wallet.status != WalletStatus::Sweepable {
+        if !QUEUEABLE_STATES.contains(&wallet.status) {
+            return Ok(false);
+        }
+
+        let wallet = transition_wallet(txn, wallet, WalletStatus::Sweepable, &format!("deposit detected on {}", chain)).await?;
+
+        let mut active: user_wallet::ActiveModel = wallet.into();
+        active.active_chain = Set(chain.to_string());
+        active.active_token = Set(token.to_string());
+        active.next_attempt_at = Set(None);
+        active.update(txn).await.map_err(AppError::DbError)?;
+    }
+
+    DetectedDeposit::update_many()
+        .col_expr(detected_deposit::Column::Status, Expr::value("QUEUED"))
+        .filter(detected_deposit::Column::WalletId.eq(wallet_id))
+        .filter(detected_deposit::Column::WalletAddress.eq(address))
+        .filter(detected_deposit::Column::Chain.eq(chain))
+        .filter(detected_deposit::Column::Status.eq("DEFERRED"))
+        .exec(txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    Ok(true)
+}
+
+
+
+/// Retries deposits whose wallet was busy when they were detected.
+async fn queue_deferred_deposits(db: &DbConnection) -> Result<(), AppError> {
+
+    let deferred = DetectedDeposit::find()
+        .filter(detected_deposit::Column::Status.eq("DEFERRED"))
+        .all(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    let mut seen = Vec::new();
+
+    for deposit in deferred {
+        let key = (deposit.wallet_id, deposit.chain.clone(), deposit.wallet_address.clone());
+        if seen.contains(&key) {
+            continue;
+        }
+        seen.push(key);
+
+        let txn = db.0.begin().await.map_err(AppError::DbError)?;
+
+        if queue_deposit(&txn, deposit.wallet_id, &deposit.wallet_address, &deposit.chain, &deposit.token).await? {
+            info!("Queued deferred deposit {} for Wallet:{}", deposit.tx_hash, deposit.wallet_address);
+        }
+
+        txn.commit().await.map_err(AppError::DbError)?;
+    }
+
+    Ok(())
+}
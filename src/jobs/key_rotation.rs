@@ -0,0 +1,318 @@
+use alloy::primitives::Address;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseTransaction, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+    TransactionTrait, sea_query::Query,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    chain_config::{chain_config::{create_read_provider, wallet_address}, key_deriver::{CURRENT_KEY_VERSION, WalletKey, next_wallet_key}, registry::registry},
+    entities::{
+        pending_deposit, prelude::{PendingDeposit, SweepAttempt, UserWallet, WalletKeyRotation, WalletStatusAudit}, sea_orm_active_enums::WalletStatus,
+        sweep_attempt, user_wallet, wallet_key_rotation, wallet_status_audit,
+    },
+    error::error::AppError,
+    jobs::indexer::queue_wallet,
+    state_models::models::DbConnection,
+    utils::{balance_scanner::{WalletBalances, read_balances}, sweep_outbox::{IN_FLIGHT_STATES, parse_address}},
+};
+
+/// The only wallet state a key rotation may move. `ASSIGNED`/`AWAITING_DEPOSIT`
+/// addresses were just handed to a user, and every other state has a sweep in
+/// progress or pending review.
+const ROTATABLE_STATE: WalletStatus = WalletStatus::Free;
+
+
+/// What one run of [`rotate_wallet_keys`] did, or would do on a dry run.
+#[derive(Debug, Default, Serialize)]
+pub struct RotationReport {
+    pub target_version: i16,
+    pub dry_run: bool,
+    pub wallets: Vec<WalletRotation>,
+}
+
+
+#[derive(Debug, Serialize)]
+pub struct WalletRotation {
+    pub wallet_id: Uuid,
+    pub user_id: Uuid,
+    pub old_address: String,
+    pub old_key_version: i16,
+    /// Assigned address; `None` when the wallet was not rotated this run.
+    pub new_address: Option<String>,
+    pub new_key_index: Option<i64>,
+    /// Chain the wallet was queued on because it still holds sweepable funds.
+    pub queued_on: Option<String>,
+    /// Chains where balances below the sweep minimum stay behind on the old address.
+    pub dust_left_on: Vec<String>,
+    pub error: Option<String>,
+}
+
+
+/// Moves idle deposit wallets off older key versions onto [`CURRENT_KEY_VERSION`].
+///
+/// Only `FREE` wallets whose status has not changed for `min_idle` are picked,
+/// so an address a user was recently given or paid into is left alone. A wallet holding anything worth sweeping on a registry chain is queued
+/// `SWEEPABLE` for that chain and left on its old key; a later run rotates it
+/// once the sweep is finalized. Empty wallets get a fresh address from
+/// `wallet_key_index_seq` and a `wallet_key_rotation` row recording the move.
+///
+/// The deposit indexer keeps watching every `wallet_key_rotation.old_address`
+/// whose key is still derivable, and moves the wallet back onto the old key to
+/// sweep a late deposit there (see [`restore_rotated_key`]). Keep an old scheme's
+/// secret (e.g. `WALLET_GENERATION_SECRET` for v1) configured until no deposit
+/// is expected on its addresses any more; retiring it stops that indexing.
+pub async fn rotate_wallet_keys(
+    db: &DbConnection,
+    dry_run: bool,
+    limit: Option<u64>,
+    min_idle: chrono::Duration,
+) -> Result<RotationReport, AppError> {
+
+    // Fail before touching any wallet if the target scheme has no secret
+    wallet_address(&WalletKey { user_id: Uuid::nil(), version: CURRENT_KEY_VERSION, index: Some(0) }).await?;
+
+    let idle_since = chrono::Utc::now() - min_idle;
+
+    let wallets = UserWallet::find()
+        .filter(user_wallet::Column::KeyVersion.ne(CURRENT_KEY_VERSION))
+        .filter(user_wallet::Column::Status.eq(ROTATABLE_STATE))
+        .filter(user_wallet::Column::CreatedAt.lt(idle_since))
+        .filter(
+            user_wallet::Column::Id.not_in_subquery(
+                Query::select()
+                    .column(wallet_status_audit::Column::WalletId)
+                    .from(WalletStatusAudit)
+                    .and_where(wallet_status_audit::Column::CreatedAt.gte(idle_since))
+                    .to_owned(),
+            ),
+        )
+        .order_by_asc(user_wallet::Column::CreatedAt)
+        .limit(limit)
+        .all(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    let mut report = RotationReport { target_version: CURRENT_KEY_VERSION, dry_run, wallets: Vec::with_capacity(wallets.len()) };
+
+    for wallet in wallets {
+        let mut rotation = WalletRotation {
+            wallet_id: wallet.id,
+            user_id: wallet.user_id,
+            old_address: wallet.wallet_address.clone(),
+            old_key_version: wallet.key_version,
+            new_address: None,
+            new_key_index: None,
+            queued_on: None,
+            dust_left_on: Vec::new(),
+            error: None,
+        };
+
+        if let Err(e) = rotate_wallet(db, &wallet, dry_run, idle_since, &mut rotation).await {
+            eprintln!("Cannot rotate wallet {}: {}", wallet.id, e);
+            rotation.error = Some(e.to_string());
+        }
+
+        report.wallets.push(rotation);
+    }
+
+    Ok(report)
+}
+
+
+
+async fn rotate_wallet(
+    db: &DbConnection,
+    wallet: &user_wallet::Model,
+    dry_run: bool,
+    idle_since: chrono::DateTime<chrono::Utc>,
+    rotation: &mut WalletRotation,
+) -> Result<(), AppError> {
+
+    let address = parse_address(&wallet.wallet_address)?;
+    let registry = registry();
+
+    for chain in registry.chains() {
+        let tokens = registry.tokens(&chain.name);
+        let provider = create_read_provider(&chain.name).await?;
+        let balances = read_balances(&provider, tokens, address).await?;
+
+        if balances.worth_sweeping(chain, tokens) {
+            let token = tokens
+                .iter()
+                .find(|token| balances.token_worth_sweeping(token))
+                .map(|token| token.address)
+                .unwrap_or(Address::ZERO);
+
+            rotation.queued_on = Some(chain.name.clone());
+
+            if !dry_run {
+                let txn = db.0.begin().await.map_err(AppError::DbError)?;
+                queue_wallet(&txn, wallet.id, &chain.name, &token.to_string()).await?;
+                txn.commit().await.map_err(AppError::DbError)?;
+            }
+
+            return Ok(());
+        }
+
+        if holds_anything(&balances) {
+            rotation.dust_left_on.push(chain.name.clone());
+        }
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+
+    let txn = db.0.begin().await.map_err(AppError::DbError)?;
+
+    // Re-check under the row lock: a deposit may have queued the wallet since it was listed
+    let Some(locked) = UserWallet::find_by_id(wallet.id)
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(AppError::DbError)?
+        .filter(|locked| {
+            locked.status == ROTATABLE_STATE
+                && locked.key_version == wallet.key_version
+                && locked.wallet_address == wallet.wallet_address
+        })
+    else {
+        return Err(AppError::BadRequest("wallet changed while it was being checked".into()));
+    };
+
+    let recent_changes = WalletStatusAudit::find()
+        .filter(wallet_status_audit::Column::WalletId.eq(locked.id))
+        .filter(wallet_status_audit::Column::CreatedAt.gte(idle_since))
+        .count(&txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    if recent_changes > 0 {
+        return Err(AppError::BadRequest("wallet was used while it was being checked".into()));
+    }
+
+    if !sweeps_settled(&txn, &locked).await? {
+        return Err(AppError::BadRequest("sweeps of the wallet are not finalized yet".into()));
+    }
+
+    let key = next_wallet_key(&txn, locked.user_id).await?;
+    let new_address = wallet_address(&key).await?;
+
+    wallet_key_rotation::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        wallet_id: Set(locked.id),
+        old_address: Set(locked.wallet_address.clone()),
+        old_key_version: Set(locked.key_version),
+        old_key_index: Set(locked.key_index),
+        new_address: Set(new_address.to_string()),
+        new_key_version: Set(key.version),
+        new_key_index: Set(key.index),
+        rotated_at: Set(chrono::Utc::now().into()),
+    }
+    .insert(&txn)
+    .await
+    .map_err(AppError::DbError)?;
+
+    let mut active: user_wallet::ActiveModel = locked.into();
+    active.wallet_address = Set(new_address.to_string());
+    active.key_version = Set(key.version);
+    active.key_index = Set(key.index);
+    active.update(&txn).await.map_err(AppError::DbError)?;
+
+    txn.commit().await.map_err(AppError::DbError)?;
+
+    println!("Rotated wallet {} from {} to {}", wallet.id, wallet.wallet_address, new_address);
+    rotation.new_address = Some(new_address.to_string());
+    rotation.new_key_index = key.index;
+
+    Ok(())
+}
+
+
+
+/// Moves a `FREE` wallet back onto the key behind `old_address`, one of its
+/// rotated-away addresses, so the sweeper can sweep a deposit sent there late.
+///
+/// The move is itself recorded in `wallet_key_rotation`, so the address the
+/// wallet leaves stays indexed too. Returns `false` when the wallet is busy,
+/// still has sweeps to settle, or was never on `old_address`.
+pub async fn restore_rotated_key(txn: &DatabaseTransaction, wallet: user_wallet::Model, old_address: &str) -> Result<bool, AppError> {
+
+    if wallet.status != WalletStatus::Free {
+        return Ok(false);
+    }
+
+    let Some(rotation) = WalletKeyRotation::find()
+        .filter(wallet_key_rotation::Column::WalletId.eq(wallet.id))
+        .filter(wallet_key_rotation::Column::OldAddress.eq(old_address))
+        .order_by_desc(wallet_key_rotation::Column::RotatedAt)
+        .one(txn)
+        .await
+        .map_err(AppError::DbError)?
+    else {
+        return Ok(false);
+    };
+
+    if !sweeps_settled(txn, &wallet).await? {
+        return Ok(false);
+    }
+
+    wallet_key_rotation::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        wallet_id: Set(wallet.id),
+        old_address: Set(wallet.wallet_address.clone()),
+        old_key_version: Set(wallet.key_version),
+        old_key_index: Set(wallet.key_index),
+        new_address: Set(rotation.old_address.clone()),
+        new_key_version: Set(rotation.old_key_version),
+        new_key_index: Set(rotation.old_key_index),
+        rotated_at: Set(chrono::Utc::now().into()),
+    }
+    .insert(txn)
+    .await
+    .map_err(AppError::DbError)?;
+
+    println!("Moved wallet {} back from {} to {} for a late deposit", wallet.id, wallet.wallet_address, rotation.old_address);
+
+    let mut active: user_wallet::ActiveModel = wallet.into();
+    active.wallet_address = Set(rotation.old_address);
+    active.key_version = Set(rotation.old_key_version);
+    active.key_index = Set(rotation.old_key_index);
+    active.update(txn).await.map_err(AppError::DbError)?;
+
+    Ok(true)
+}
+
+
+
+/// Whether every sweep from the wallet's current address is finalized.
+///
+/// Reorg handling finds a sweep's wallet by address, so a wallet must not
+/// change address while one is in flight or still reorgable.
+async fn sweeps_settled(txn: &DatabaseTransaction, wallet: &user_wallet::Model) -> Result<bool, AppError> {
+
+    let unsettled = PendingDeposit::find()
+        .filter(pending_deposit::Column::WalletAddress.eq(wallet.wallet_address.as_str()))
+        .filter(pending_deposit::Column::Status.is_in(["PENDING", "CREDITED"]))
+        .count(txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    let in_flight = SweepAttempt::find()
+        .filter(sweep_attempt::Column::WalletId.eq(wallet.id))
+        .filter(sweep_attempt::Column::State.is_in(IN_FLIGHT_STATES))
+        .count(txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    Ok(unsettled == 0 && in_flight == 0)
+}
+
+
+
+fn holds_anything(balances: &WalletBalances) -> bool {
+    balances.native.is_some_and(|balance| !balance.is_zero())
+        || balances.tokens.values().any(|balance| balance.is_some_and(|balance| !balance.is_zero()))
+}
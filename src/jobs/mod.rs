@@ -1,3 +1,17 @@
 pub mod sweeper;
 
-pub mod index;
\ No newline at end of file
+pub mod index;
+
+pub mod confirmations;
+
+pub mod recovery;
+
+pub mod reaper;
+
+pub mod indexer;
+
+pub mod rpc_health;
+pub mod dry_run;
+pub mod key_rotation;
+pub mod treasury_monitor;
+pub mod withdrawals;
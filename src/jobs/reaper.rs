@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use sea_orm::{
+    ColumnTrait, Condition, EntityTrait, PaginatorTrait, QueryFilter, QuerySelect, TransactionTrait, sea_query::{LockBehavior, LockType}
+};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::{
+    entities::{pending_deposit, prelude::{PendingDeposit, SweepAttempt, UserWallet}, sea_orm_active_enums::WalletStatus, sweep_attempt, user_wallet},
+    error::error::AppError,
+    jobs::recovery::recover_wallet_attempts,
+    state_models::models::DbConnection,
+    utils::{sweep_outbox::IN_FLIGHT_STATES, wallet_lifecycle::transition_wallet},
+};
+
+const REAPER_INTERVAL: Duration = Duration::from_secs(60);
+const REAPER_BATCH: u64 = 100;
+
+
+/// Periodically takes back wallets whose sweep lease has expired and re-queues parked ones.
+pub async fn run_reaper(db: DbConnection) -> Result<(), AppError> {
+    println!("REAPER RUNNING");
+    loop {
+        match reap_expired_leases(&db).await {
+            Ok(0) => {}
+            Ok(reaped) => info!("Reaper returned {} wallets with expired leases", reaped),
+            Err(e) => warn!("Reaper failed: {}", e),
+        }
+
+        match rearm_parked_wallets(&db).await {
+            Ok(0) => {}
+            Ok(rearmed) => info!("Reaper re-queued {} parked wallets", rearmed),
+            Err(e) => warn!("Cannot re-queue parked wallets: {}", e),
+        }
+
+        sleep(REAPER_INTERVAL).await;
+    }
+}
+
+
+
+/// Re-examines `SWEEP_IN_PROGRESS` wallets whose worker stopped renewing its lease.
+///
+/// Rows a live worker is sweeping are row-locked for the duration of the sweep
+/// and skipped here. For the rest, the wallet's in-flight sweep attempts are
+/// first reconciled against chain receipts, then the wallet goes to:
+/// - `CONFIRMING` if a sweep is still in flight or waiting for depth;
+/// - `SWEEPABLE` otherwise, so the sweeper re-reads its on-chain balances and
+///   either sweeps what is left or frees it.
+///
+/// Rows without any lease (claimed before leases existed) count as expired.
+pub async fn reap_expired_leases(db: &DbConnection) -> Result<u64, AppError> {
+
+    let txn = db.0.begin().await.map_err(AppError::DbError)?;
+
+    let expired = UserWallet::find()
+        .filter(user_wallet::Column::Status.eq(WalletStatus::Sweeping))
+        .filter(
+            Condition::any()
+                .add(user_wallet::Column::LeaseExpiresAt.lt(chrono::Utc::now()))
+                .add(user_wallet::Column::LeaseExpiresAt.is_null()),
+        )
+        .limit(REAPER_BATCH)
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .all(&txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    let reaped = expired.len() as u64;
+
+    for wallet in expired {
+        if let Err(e) = recover_wallet_attempts(db, wallet.id).await {
+            warn!("Cannot reconcile attempts of wallet {}: {}", wallet.wallet_address, e);
+        }
+
+        let in_flight = SweepAttempt::find()
+            .filter(sweep_attempt::Column::WalletId.eq(wallet.id))
+            .filter(sweep_attempt::Column::State.is_in(IN_FLIGHT_STATES))
+            .count(&txn)
+            .await
+            .map_err(AppError::DbError)?;
+
+        let awaiting_depth = PendingDeposit::find()
+            .filter(pending_deposit::Column::WalletAddress.eq(wallet.wallet_address.as_str()))
+            .filter(pending_deposit::Column::Status.eq("PENDING"))
+            .count(&txn)
+            .await
+            .map_err(AppError::DbError)?;
+
+        let next_status = if in_flight > 0 || awaiting_depth > 0 {
+            WalletStatus::Confirming
+        } else {
+            WalletStatus::Sweepable
+        };
+
+        let reason = format!(
+            "lease of {} expired at {:?}",
+            wallet.claimed_by.as_deref().unwrap_or("unknown worker"),
+            wallet.lease_expires_at
+        );
+
+        transition_wallet(&txn, wallet, next_status, &reason).await?;
+    }
+
+    txn.commit().await.map_err(AppError::DbError)?;
+
+    Ok(reaped)
+}
+
+
+
+/// Re-queues `GAS_PENDING` and `FAILED` wallets whose `next_attempt_at` has passed.
+///
+/// The sweeper parks a wallet with a retry time (see `GAS_PENDING_RETRY_DELAY`
+/// and `FAILED_RETRY_DELAY`); wallets parked without one are re-queued at once.
+/// Back on `SWEEPABLE`, the wallet's `active_chain` pool re-reads its balances
+/// and sweeps, funds or frees it as usual.
+pub async fn rearm_parked_wallets(db: &DbConnection) -> Result<u64, AppError> {
+
+    let txn = db.0.begin().await.map_err(AppError::DbError)?;
+
+    let parked = UserWallet::find()
+        .filter(user_wallet::Column::Status.is_in([WalletStatus::GasPending, WalletStatus::Failed]))
+        .filter(
+            Condition::any()
+                .add(user_wallet::Column::NextAttemptAt.is_null())
+                .add(user_wallet::Column::NextAttemptAt.lte(chrono::Utc::now())),
+        )
+        .limit(REAPER_BATCH)
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .all(&txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    let rearmed = parked.len() as u64;
+
+    for wallet in parked {
+        let reason = match wallet.status {
+            WalletStatus::GasPending => "retrying gas top-up",
+            _ => "retrying failed sweep",
+        };
+
+        transition_wallet(&txn, wallet, WalletStatus::Sweepable, reason).await?;
+    }
+
+    txn.commit().await.map_err(AppError::DbError)?;
+
+    Ok(rearmed)
+}
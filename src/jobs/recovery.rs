@@ -0,0 +1,238 @@
+use std::{str::FromStr, time::Duration};
+
+use alloy::{
+    primitives::TxHash, providers::{Provider, RootProvider}
+};
+use sea_orm::{
+    ColumnTrait, DatabaseTransaction, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+    sea_query::{LockBehavior, LockType},
+};
+use tokio::time::sleep;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    chain_config::{chain_config::create_read_provider, registry::registry},
+    entities::{pending_deposit, prelude::{PendingDeposit, SweepAttempt, UserWallet}, sea_orm_active_enums::WalletStatus, sweep_attempt},
+    error::error::AppError,
+    jobs::sweeper::record_mined_sweep,
+    state_models::models::DbConnection,
+    utils::{sweep_outbox::{IN_FLIGHT_STATES, SweepIntent, is_stuck, parse_address, rebroadcast, replace_stuck_attempt, set_attempt_state}, wallet_lifecycle::transition_wallet},
+};
+
+const RECOVERY_INTERVAL: Duration = Duration::from_secs(60);
+/// Longer than a worker's receipt timeout, so live sweeps are left alone.
+const RECOVERY_GRACE: Duration = Duration::from_secs(300);
+
+
+/// Reconciles every in-flight `sweep_attempt` older than `min_age` with the chain.
+///
+/// For each attempt:
+/// - mined successfully: records the pending deposit (once) and marks it `MINED`;
+/// - mined but reverted: marks it `FAILED`;
+/// - unknown to the node, nonce already used by another transaction: `DROPPED`.
+///   A `CONFIRMING` wallet left with nothing in flight after either goes back
+///   to `SWEEPABLE`;
+/// - unknown to the node, nonce still free: replaces it with bumped fees once it
+///   is older than the chain's `rbf_after`, otherwise re-broadcasts the stored
+///   raw transaction. `REPLACED` attempts are left to their replacement.
+///
+/// Runs once with `min_age` zero at startup and periodically afterwards with a
+/// grace period, so it never races a worker that is still waiting for its receipt.
+pub async fn recover_sweep_attempts(db: &DbConnection, min_age: Duration) -> Result<(), AppError> {
+
+    let cutoff = chrono::Utc::now() - chrono::Duration::from_std(min_age).unwrap_or_default();
+
+    let attempts = SweepAttempt::find()
+        .filter(sweep_attempt::Column::State.is_in(IN_FLIGHT_STATES))
+        .filter(sweep_attempt::Column::UpdatedAt.lte(cutoff))
+        .order_by_asc(sweep_attempt::Column::CreatedAt)
+        .all(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    reconcile_attempts(db, attempts).await;
+
+    Ok(())
+}
+
+
+
+/// Reconciles the in-flight attempts of a single wallet, regardless of age.
+pub async fn recover_wallet_attempts(db: &DbConnection, wallet_id: Uuid) -> Result<(), AppError> {
+
+    let attempts = SweepAttempt::find()
+        .filter(sweep_attempt::Column::WalletId.eq(wallet_id))
+        .filter(sweep_attempt::Column::State.is_in(IN_FLIGHT_STATES))
+        .order_by_asc(sweep_attempt::Column::CreatedAt)
+        .all(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    reconcile_attempts(db, attempts).await;
+
+    Ok(())
+}
+
+
+
+/// Reconciles each attempt, logging failures so one bad RPC does not block the rest.
+async fn reconcile_attempts(db: &DbConnection, attempts: Vec<sweep_attempt::Model>) {
+    for attempt in attempts {
+        let provider = match create_read_provider(&attempt.chain).await {
+            Ok(provider) => provider,
+            Err(e) => {
+                warn!("Cannot reconcile sweep {} on {}: {}", attempt.tx_hash, attempt.chain, e);
+                continue;
+            }
+        };
+
+        if let Err(e) = reconcile_attempt(db, &provider, attempt.clone()).await {
+            warn!("Cannot reconcile sweep {} on {}: {}", attempt.tx_hash, attempt.chain, e);
+        }
+    }
+}
+
+
+
+async fn reconcile_attempt(
+    db: &DbConnection,
+    provider: &RootProvider,
+    attempt: sweep_attempt::Model,
+) -> Result<(), AppError> {
+
+    let tx_hash = TxHash::from_str(&attempt.tx_hash)
+        .map_err(|e| AppError::InternalError(format!("Invalid tx hash {}: {e}", attempt.tx_hash)))?;
+
+    let receipt = provider.get_transaction_receipt(tx_hash).await
+        .map_err(|e| AppError::InternalError(format!("Cannot fetch receipt: {e}")))?;
+
+    match receipt {
+        Some(receipt) if receipt.status() => {
+            let txn = db.0.begin().await.map_err(AppError::DbError)?;
+
+            let already_recorded = PendingDeposit::find()
+                .filter(pending_deposit::Column::TxHash.eq(attempt.tx_hash.as_str()))
+                .one(&txn)
+                .await
+                .map_err(AppError::DbError)?
+                .is_some();
+
+            if !already_recorded {
+                let intent = SweepIntent {
+                    user_id: attempt.user_id,
+                    wallet_id: attempt.wallet_id,
+                    wallet_address: parse_address(&attempt.wallet_address)?,
+                    chain: &attempt.chain,
+                    token: parse_address(&attempt.token)?,
+                    amount: attempt.amount,
+                };
+                record_mined_sweep(&txn, &intent, &receipt).await?;
+            }
+
+            set_attempt_state(&txn, attempt.id, "MINED").await?;
+            txn.commit().await.map_err(AppError::DbError)?;
+            info!("Recovered mined sweep {} on {}", attempt.tx_hash, attempt.chain);
+        }
+        Some(_) => {
+            let txn = db.0.begin().await.map_err(AppError::DbError)?;
+            set_attempt_state(&txn, attempt.id, "FAILED").await?;
+            requeue_unconfirmed_wallet(&txn, attempt.wallet_id, "sweep reverted, re-checking balances").await?;
+            txn.commit().await.map_err(AppError::DbError)?;
+            warn!("Recovered sweep {} on {} had reverted", attempt.tx_hash, attempt.chain);
+        }
+        None => {
+            let sender = parse_address(&attempt.wallet_address)?;
+            let mined_nonce = provider.get_transaction_count(sender).latest().await
+                .map_err(|e| AppError::InternalError(format!("Cannot fetch nonce: {e}")))?;
+
+            if mined_nonce > attempt.nonce as u64 {
+                let txn = db.0.begin().await.map_err(AppError::DbError)?;
+                set_attempt_state(&txn, attempt.id, "DROPPED").await?;
+                requeue_unconfirmed_wallet(&txn, attempt.wallet_id, "sweep dropped, re-checking balances").await?;
+                txn.commit().await.map_err(AppError::DbError)?;
+                warn!("Sweep {} on {} was dropped, nonce {} already used", attempt.tx_hash, attempt.chain, attempt.nonce);
+                return Ok(());
+            }
+
+            // The fee-bumped replacement now carries this nonce
+            if attempt.state == "REPLACED" {
+                return Ok(());
+            }
+
+            let stuck = registry().chain(&attempt.chain).is_some_and(|chain| is_stuck(chain, attempt.created_at));
+
+            if attempt.state == "BROADCAST" && stuck {
+                match replace_stuck_attempt(db, &attempt).await {
+                    Ok(true) => return Ok(()),
+                    Ok(false) => {}
+                    Err(e) => warn!("Cannot replace stuck sweep {} on {}: {}", attempt.tx_hash, attempt.chain, e),
+                }
+            }
+
+            let raw_tx = hex::decode(&attempt.raw_tx)
+                .map_err(|e| AppError::InternalError(format!("Invalid raw tx for {}: {e}", attempt.tx_hash)))?;
+
+            if rebroadcast(provider, &raw_tx, &attempt.tx_hash, &attempt.chain).await {
+                set_attempt_state(&db.0, attempt.id, "BROADCAST").await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+
+
+/// Sends a `CONFIRMING` wallet back to `SWEEPABLE` once nothing it was waiting on can still confirm.
+///
+/// The confirmation tracker only frees wallets through their `pending_deposit`
+/// rows, which a reverted or dropped sweep never gets; the funds it meant to
+/// move are still on the wallet for the sweeper to pick up again.
+async fn requeue_unconfirmed_wallet(txn: &DatabaseTransaction, wallet_id: Uuid, reason: &str) -> Result<(), AppError> {
+
+    // The reaper holds the lock of a wallet whose attempts it is recovering; that wallet is not `CONFIRMING`
+    let Some(wallet) = UserWallet::find_by_id(wallet_id)
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .one(txn)
+        .await
+        .map_err(AppError::DbError)?
+        .filter(|wallet| wallet.status == WalletStatus::Confirming)
+    else {
+        return Ok(());
+    };
+
+    let in_flight = SweepAttempt::find()
+        .filter(sweep_attempt::Column::WalletId.eq(wallet.id))
+        .filter(sweep_attempt::Column::State.is_in(IN_FLIGHT_STATES))
+        .count(txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    let awaiting_depth = PendingDeposit::find()
+        .filter(pending_deposit::Column::WalletAddress.eq(wallet.wallet_address.as_str()))
+        .filter(pending_deposit::Column::Status.eq("PENDING"))
+        .count(txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    if in_flight == 0 && awaiting_depth == 0 {
+        transition_wallet(txn, wallet, WalletStatus::Sweepable, reason).await?;
+    }
+
+    Ok(())
+}
+
+
+
+/// Periodically reconciles attempts a worker gave up waiting on, e.g. after a receipt timeout.
+pub async fn run_outbox_recovery(db: DbConnection) -> Result<(), AppError> {
+    println!("OUTBOX RECOVERY RUNNING");
+    loop {
+        sleep(RECOVERY_INTERVAL).await;
+
+        if let Err(e) = recover_sweep_attempts(&db, RECOVERY_GRACE).await {
+            warn!("Outbox recovery failed: {}", e);
+        }
+    }
+}
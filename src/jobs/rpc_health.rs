@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use tokio::time::sleep;
+use tracing::info;
+
+use crate::{
+    chain_config::{registry::registry, rpc_pool::{rpc_pool, rpc_stats}},
+    error::error::AppError,
+};
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+
+/// Probes every RPC endpoint of every chain and logs per-endpoint health.
+///
+/// The probe keeps block lag and latency fresh for endpoints that receive no
+/// traffic, so a lagging endpoint is demoted and a recovered one is promoted
+/// again by the failover transport.
+pub async fn run_rpc_health_monitor() -> Result<(), AppError> {
+    println!("RPC HEALTH MONITOR RUNNING");
+    loop {
+        let registry = registry();
+
+        for chain in registry.chains() {
+            rpc_pool(chain).probe().await;
+        }
+
+        for stats in rpc_stats() {
+            info!(
+                "RPC {} on {}: score {:.0}, latency {:.0}ms, error rate {:.2}, {} errors / {} requests, chain id {:?}, height {:?}, lag {}{}",
+                stats.endpoint,
+                stats.chain,
+                stats.score,
+                stats.latency_ms,
+                stats.error_rate,
+                stats.errors,
+                stats.requests,
+                stats.chain_id,
+                stats.block_height,
+                stats.block_lag,
+                stats.last_error.map(|e| format!(", last error: {}", e)).unwrap_or_default(),
+            );
+        }
+
+        sleep(PROBE_INTERVAL).await;
+    }
+}
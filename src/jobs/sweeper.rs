@@ -1,63 +1,103 @@
-use std::{ str::FromStr, time::Duration};
+use std::{ collections::HashMap, sync::Mutex, time::Duration};
 
 use alloy::{
-    primitives::{ Address, U256}, providers::Provider,  sol
+    network::TransactionBuilder, primitives::{ Address, U256}, providers::Provider, rpc::types::{TransactionReceipt, TransactionRequest}, sol_types::SolCall
 };
-use rust_decimal::{Decimal, prelude::FromPrimitive};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue::{ Set}, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait
+    ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Select, TransactionTrait,
+    sea_query::{LockBehavior, LockType},
 };
+use rust_decimal::Decimal;
 use uuid::Uuid;
 
 use tokio::time::sleep;
+use tracing::warn;
 use crate::{
-    chain_config::chain_config::create_provider, config::config::AppConfig, entities::{ prelude::UserWallet, user_wallet}, error::error::AppError, jobs::index::{MAX_RETRIES, RETRY_BACKOFF, between_cycles_cleanup}, state_models::models::DbConnection,  tokens::tokens::TOKENS, utils::{free_wallet::mark_wallet_free, token_decimals::get_token_decimals, update_deposit::upsert_user_balance_and_receipt},
+    chain_config::{chain_config::{create_provider, create_read_provider}, key_deriver::WalletKey, registry::{ChainEntry, TokenEntry, registry}}, config::config::AppConfig, entities::{ prelude::UserWallet, sea_orm_active_enums::WalletStatus, user_wallet}, error::error::AppError, jobs::{dry_run::{PlannedAction, WalletReport}, index::{MAX_RETRIES, RETRY_BACKOFF, between_cycles_cleanup}}, state_models::models::{DbConnection, SignerProvider}, utils::{balance_scanner::{WalletBalances, scan_balances}, dust_policy::{DustPolicy, confirmed_dust_balance, credited_dust, dust_decision, record_dust, settle_dust}, gas_station::fund_wallet_gas, token_decimals::{ERC20, get_token_decimals, u256_to_decimal}, token_metadata::verified_decimals, treasury_router::{Destination, sweep_destination}, fee_policy::{fee_within_ratio, l1_data_fee, quote_fees}, sweep_outbox::{SweepIntent, has_in_flight_attempt, set_attempt_state, sign_and_broadcast}, update_deposit::record_pending_deposit, wallet_lifecycle::{claim_wallet, defer_wallet, renew_lease, transition_wallet, worker_identity}},
 };
 
 
-sol!(
-    #[sol(rpc)]
-    ERC20,
-    "src/utils/abi/ERC20.json"
-);
+/// How long a claimed wallet stays reserved for its worker without a renewal.
+pub const WALLET_LEASE: Duration = Duration::from_secs(600);
 
+/// How long a wallet deferred for high fees waits before it is claimed again.
+const FEE_RETRY_DELAY: Duration = Duration::from_secs(300);
 
+/// How long a `GAS_PENDING` wallet waits before the reaper re-queues it; the
+/// gas station's daily cap resets at UTC midnight, so hourly retries pick that up.
+pub const GAS_PENDING_RETRY_DELAY: Duration = Duration::from_secs(3_600);
 
+/// How long a `FAILED` wallet waits before the reaper gives its sweep another try.
+pub const FAILED_RETRY_DELAY: Duration = Duration::from_secs(6 * 3_600);
+
+/// How long a worker waits for a sweep to be mined before leaving it to the recovery pass.
+const RECEIPT_TIMEOUT: Duration = Duration::from_secs(180);
+
+
+
+/// Claims up to 100 `SWEEPABLE` wallets queued for `chain_name` and sweeps them one by one.
+///
+/// Wallets are queued per chain through `user_wallet.active_chain`, so each
+/// chain's worker pool only ever touches its own RPC endpoints.
 pub async fn sweep_wallet(
+    chain_name: &str,
     worker_id: u64,
     db: &DbConnection,
 ) -> Result<(), AppError> {
 
 
+            let worker = worker_identity(chain_name, worker_id);
             let txn = db.0.begin().await.map_err(AppError::DbError)?;
 
-            // Select up to 100 pending requests and lock them for this worker
-            let user_wallets = UserWallet::find()
-                .filter(user_wallet::Column::Status.eq("SWEEPABLE"))
-                .filter(user_wallet::Column::ActiveGas.lt(0.1))
-                .order_by_asc(user_wallet::Column::CreatedAt)
+            // Select up to 100 pending requests and lock them for this worker;
+            // rows another worker of the pool already locked are skipped, not waited on
+            let sweepable_wallets = queued_wallets(chain_name)
                 .limit(100)
-                .lock_exclusive() // <- row-level exclusive lock
+                .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
                 .all(&txn)
                 .await
                 .map_err(AppError::DbError)?;
 
-            // Mark them as IN_PROGRESS so other workers skip them
-            for req in &user_wallets {
-                let mut active: user_wallet::ActiveModel = req.clone().into();
-                active.status = Set("SWEEP_IN_PROGRESS".to_string());
-                active.update(&txn).await.map_err(AppError::DbError)?;
+            // Mark them as SWEEP_IN_PROGRESS under a lease so other workers skip them
+            // and the reaper can take them back if this worker dies
+            let mut user_wallets = Vec::with_capacity(sweepable_wallets.len());
+            for req in sweepable_wallets {
+                let claimed = claim_wallet(&txn, req, &worker, WALLET_LEASE).await?;
+                user_wallets.push(claimed);
             }
 
             // Commit so locks are released and status is updated
             txn.commit().await.map_err(AppError::DbError)?;
 
+            // One Multicall3 scan for the whole batch instead of balance reads per wallet and token
+            let holdings = scan_claimed_wallets(chain_name, &user_wallets).await;
+
     for user_wallet in user_wallets{
 
+        let scanned = holdings
+            .as_ref()
+            .zip(user_wallet.wallet_address.parse::<Address>().ok())
+            .and_then(|(holdings, address)| holdings.get(&address).cloned());
+
+        let registry = registry();
+        if let (Some(chain), Some(scanned)) = (registry.chain(chain_name), &scanned)
+            && !scanned.worth_sweeping(chain, registry.tokens(chain_name))
+        {
+            // Nothing above the sweep thresholds: free it without a single per-wallet RPC call
+            let txn = db.0.begin().await.map_err(AppError::DbError)?;
+            let current = UserWallet::find_by_id(user_wallet.id).lock_exclusive().one(&txn).await.map_err(AppError::DbError)?;
+            if let Some(current) = current.filter(|wallet| is_claimed_by(wallet, &worker)) {
+                record_scanned_dust(&txn, chain, &current, registry.tokens(chain_name), scanned).await?;
+                transition_wallet(&txn, current, WalletStatus::Free, "balance scan found nothing to sweep").await?;
+            }
+            txn.commit().await.map_err(AppError::DbError)?;
+            continue;
+        }
+
         let mut retries = 0;
 
         loop {
-            let result = process_single_request(worker_id, user_wallet.id, &db).await;
+            let result = process_single_request(chain_name, worker_id, user_wallet.id, db, scanned.as_ref()).await;
 
             match result {
             Ok(_) => {
@@ -72,22 +112,21 @@ pub async fn sweep_wallet(
             },
 
             Err(e) => {
-                    //  Reset back to PENDING for retry in next cycle
+                    //  Park the wallet as FAILED instead of retrying it every cycle
 
                     eprintln!("Failed to process request {} after {} retries: {}", user_wallet.id, retries, e);
 
-                    let mut complete_request: user_wallet::ActiveModel = user_wallet.clone().into();
-                        complete_request.status = Set("FREE".to_string());
-                        complete_request
-                        .update(&db.0)
-                        .await
-                        .map_err(AppError::DbError)?;
-                        break;
-                
+                    // The reaper may have taken the wallet back while we were retrying
+                    let current = UserWallet::find_by_id(user_wallet.id).one(&db.0).await.map_err(AppError::DbError)?;
+                    if let Some(current) = current.filter(|wallet| is_claimed_by(wallet, &worker)) {
+                        let failed = transition_wallet(&db.0, current, WalletStatus::Failed, &format!("sweep failed after {} retries: {}", retries, e)).await?;
+                        defer_wallet(&db.0, failed, FAILED_RETRY_DELAY).await?;
+                    }
+                    break;
             }
         }
             sleep(Duration::from_millis(150)).await;
-            between_cycles_cleanup(&db).await;
+            between_cycles_cleanup(db).await;
             tokio::task::yield_now().await;
         }
     
@@ -99,25 +138,205 @@ pub async fn sweep_wallet(
 
 
 
+/// `SWEEPABLE` wallets queued for `chain_name` and due for an attempt, oldest first.
+fn queued_wallets(chain_name: &str) -> Select<UserWallet> {
+    UserWallet::find()
+        .filter(user_wallet::Column::Status.eq(WalletStatus::Sweepable))
+        .filter(user_wallet::Column::ActiveChain.eq(chain_name))
+        .filter(
+            Condition::any()
+                .add(user_wallet::Column::NextAttemptAt.is_null())
+                .add(user_wallet::Column::NextAttemptAt.lte(chrono::Utc::now())),
+        )
+        .filter(user_wallet::Column::ActiveGas.lt(0.1))
+        .order_by_asc(user_wallet::Column::CreatedAt)
+}
+
+
+
+/// Plans a sweep cycle of `chain_name` without claiming, signing or crediting anything.
+///
+/// Every wallet queued for the chain goes through the same balance reads and
+/// `eth_call`/`estimate_gas` simulations as a live sweep; what the live sweep
+/// would have broadcast, credited or asked the gas station for is reported
+/// instead. Reads run in a transaction that is rolled back.
+pub async fn dry_run_chain(chain_name: &str, db: &DbConnection) -> Result<Vec<WalletReport>, AppError> {
+
+    let config = AppConfig::from_env()?;
+    let master_wallet_address = config.master_wallet_address;
+
+    let wallets = queued_wallets(chain_name).all(&db.0).await.map_err(AppError::DbError)?;
+    let holdings = scan_claimed_wallets(chain_name, &wallets).await;
+
+    let mut reports = Vec::with_capacity(wallets.len());
+
+    for wallet in wallets {
+        let scanned = holdings
+            .as_ref()
+            .zip(wallet.wallet_address.parse::<Address>().ok())
+            .and_then(|(holdings, address)| holdings.get(&address).cloned());
+
+        let planned = Mutex::new(Vec::new());
+        let result = plan_wallet(chain_name, &wallet, db, master_wallet_address, scanned.as_ref(), &planned).await;
+
+        let mut report = WalletReport::new(&wallet, planned.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner()));
+        match result {
+            Ok((status, reason)) => {
+                report.next_status = Some(format!("{:?}", status));
+                report.reason = Some(reason.to_string());
+            }
+            Err(e) => report.error = Some(e.to_string()),
+        }
+        reports.push(report);
+    }
+
+    Ok(reports)
+}
+
+
+
+/// Runs the sweep of one wallet in planning mode and returns the status it would end in.
+async fn plan_wallet(
+    chain_name: &str,
+    wallet: &user_wallet::Model,
+    db: &DbConnection,
+    master_wallet_address: Address,
+    scanned: Option<&WalletBalances>,
+    planned: &Mutex<Vec<PlannedAction>>,
+) -> Result<(WalletStatus, &'static str), AppError> {
+
+    let registry = registry();
+    let chain = registry
+        .chain(chain_name)
+        .ok_or_else(|| AppError::InternalError(format!("Chain {} is not in the registry", chain_name)))?;
+
+    let wallet_address: Address = wallet.wallet_address.parse()
+        .map_err(|_| AppError::BadRequest("Invalid wallet address".to_string()))?;
+
+    let provider = create_provider(chain_name, &WalletKey::from(wallet)).await?;
+
+    // Dropped without commit: nothing read through it is ever written back
+    let txn = db.0.begin().await.map_err(AppError::DbError)?;
+
+    let ctx = SweepContext {
+        provider: &provider.0,
+        db,
+        txn: &txn,
+        chain,
+        chain_name,
+        user_id: wallet.user_id,
+        wallet_id: wallet.id,
+        wallet_address,
+        master_wallet_address,
+        scanned,
+        planned: Some(planned),
+    };
+
+    Ok(sweep_assets(&ctx, registry.tokens(chain_name)).await?.next_status())
+}
+
+
+
+/// Balances of the claimed wallets from one Multicall3 scan, or `None` to check each wallet on-chain.
+async fn scan_claimed_wallets(chain_name: &str, wallets: &[user_wallet::Model]) -> Option<HashMap<Address, WalletBalances>> {
+
+    let registry = registry();
+    let chain = registry.chain(chain_name)?;
+
+    let addresses: Vec<Address> = wallets
+        .iter()
+        .filter_map(|wallet| wallet.wallet_address.parse().ok())
+        .collect();
+
+    if addresses.is_empty() {
+        return None;
+    }
+
+    let provider = create_read_provider(chain_name).await.ok()?;
+
+    match scan_balances(&provider, chain, registry.tokens(chain_name), &addresses).await {
+        Ok(scanned) => scanned,
+        Err(e) => {
+            warn!("Balance scan failed on {}, checking wallets one by one: {}", chain_name, e);
+            None
+        }
+    }
+}
+
+
+
+/// Applies the dust policy to token balances the batch scan found below their sweep threshold.
+///
+/// Only a `CREDIT` policy reads the chain, for the confirmed balance, so the
+/// read provider is created on the first such token.
+async fn record_scanned_dust(
+    txn: &sea_orm::DatabaseTransaction,
+    chain: &ChainEntry,
+    wallet: &user_wallet::Model,
+    tokens: &[TokenEntry],
+    scanned: &WalletBalances,
+) -> Result<(), AppError> {
+
+    let wallet_address: Address = wallet.wallet_address.parse().map_err(|e|{
+        eprintln!("Invalid wallet address {:?}",e);
+        AppError::BadRequest("Invalid wallet address".to_string())
+    } )?;
+
+    let mut provider = None;
+
+    for token in tokens {
+        if let Some(amount) = scanned.token_amount(token)
+            && !amount.is_zero()
+            && amount < token.min_sweep_amount
+        {
+            let confirmed = match token.dust_policy {
+                DustPolicy::Accumulate => amount,
+                DustPolicy::Credit => {
+                    if provider.is_none() {
+                        provider = Some(create_read_provider(&chain.name).await?);
+                    }
+                    let provider = provider.as_ref().expect("read provider created above");
+                    confirmed_dust_balance(provider, chain, token, wallet_address, amount).await?
+                }
+            };
+            record_dust(txn, wallet.user_id, wallet.id, &wallet_address.to_string(), token, amount, confirmed).await?;
+        }
+    }
+
+    Ok(())
+}
+
+
+
 async fn process_single_request(
+    chain_name: &str,
     worker_id: u64,
     user_wallet_id: Uuid,
     db: &DbConnection,
+    scanned: Option<&WalletBalances>,
 )->Result<(), AppError> {
 
     let config = AppConfig::from_env()?;
+    let worker = worker_identity(chain_name, worker_id);
+
+    // Renew the lease outside the sweep transaction so the reaper sees it; while
+    // the transaction below holds the row lock the reaper skips the wallet anyway
+    let claimed = UserWallet::find_by_id(user_wallet_id).one(&db.0).await?;
+    match claimed.filter(|wallet| is_claimed_by(wallet, &worker)) {
+        Some(wallet) => renew_lease(&db.0, wallet, &worker, WALLET_LEASE).await?,
+        None => return Ok(()), // reaped and re-queued, deleted or processed
+    };
+
     let txn = db.0.begin().await.map_err(AppError::DbError)?;
-    let master_wallet_address = Address::from_str(config.master_wallet_address.as_str()).unwrap() ;
+    let master_wallet_address = config.master_wallet_address;
     let pending_wallet = UserWallet::find_by_id(user_wallet_id)
     .lock_exclusive()
     .one(&txn)
     .await?;
 
-    if pending_wallet.is_none() {
-        return Ok(()); // already deleted or processed
-    }
-
-    let pending_wallet = pending_wallet.unwrap();
+    let Some(pending_wallet) = pending_wallet.filter(|wallet| is_claimed_by(wallet, &worker)) else {
+        return Ok(()); // lost the lease between renewal and lock
+    };
 
     println!("Wallet : {} in Worker : {}", pending_wallet.wallet_address, worker_id);
     let wallet_address: Address = pending_wallet.wallet_address.parse().map_err(|e|{
@@ -126,164 +345,565 @@ async fn process_single_request(
     } )?;
 
     let user_id = pending_wallet.user_id;
+    let registry = registry();
+
+    // The chain was disabled since the wallet was claimed; leave it queued for when it comes back
+    let Some(chain) = registry.chain(chain_name) else {
+        transition_wallet(&txn, pending_wallet, WalletStatus::Sweepable, "chain disabled in registry").await?;
+        txn.commit().await.map_err(AppError::DbError)?;
+        return Ok(());
+    };
+    let chain_name = chain.name.as_str();
+
+    println!("Checking Chain {} on Wallet {}", chain_name, wallet_address);
+
+    let provider = create_provider(chain_name, &WalletKey::from(&pending_wallet)).await.map_err(|e| {
+        eprintln!("Cannot create provider on  {:?}: {:?}", chain_name, e);
+        AppError::InternalError(format!("Provider error: {e}"))
+    })?;
+
+    let ctx = SweepContext {
+        provider: &provider.0,
+        db,
+        txn: &txn,
+        chain,
+        chain_name,
+        user_id,
+        wallet_id: pending_wallet.id,
+        wallet_address,
+        master_wallet_address,
+        scanned,
+        planned: None,
+    };
+
+    let outcome = sweep_assets(&ctx, registry.tokens(chain_name)).await?;
+    let (next_status, reason) = outcome.next_status();
+
+    println!("Making Wallet {} {:?}", wallet_address, next_status);
+    let wallet = transition_wallet(&txn, pending_wallet, next_status, reason).await?;
+
+    // Keep the pool from re-claiming the wallet every cycle while fees stay high,
+    // and the reaper from re-queueing a gas-starved one before the gas station can help
+    let retry_delay = match wallet.status {
+        WalletStatus::GasPending => Some(GAS_PENDING_RETRY_DELAY),
+        WalletStatus::Sweepable if outcome.fees_too_high && !outcome.gas_funded => Some(FEE_RETRY_DELAY),
+        _ => None,
+    };
+
+    if let Some(delay) = retry_delay {
+        defer_wallet(&txn, wallet, delay).await?;
+    }
+
+    txn.commit().await.map_err(AppError::DbError)?;
 
-    for (chain_name , tokens) in TOKENS.iter(){
 
-        println!("Checking Chain {} on Wallet {}", chain_name, wallet_address);
+    Ok(())
+}
 
-        let mut usdc_token_balance ;
-        let mut usdt_token_balance;
 
-        let provider = create_provider(&chain_name , user_id).await.map_err(|e| {
-            eprintln!("Cannot create provider on  {:?}: {:?}", chain_name, e);
-            AppError::InternalError(format!("Provider error: {e}"))
-        })?;
-
-        for (token_name , token_address) in tokens {
-
-            let erc20 = ERC20::new(*token_address, &provider.0);
-            
-            let gas_balance  = provider.0.get_balance(wallet_address).await.map_err(|e| AppError::InternalError(format!("Cannot fetch native balance: {e}")))?;
-            let gas_price = provider.0.get_gas_price().await.map_err(|e|{
-                    eprintln!("Error Cannot get gas price {:?}: {:?}", wallet_address, e);
-                    AppError::InternalError(format!("Error Cannot get gas price : {e}"))
-            } )?;
-
-            if token_name ==  &"USDC"  {
-
-                let decimals = get_token_decimals(&provider.0, token_address.clone()).await?;
-
-                usdc_token_balance  = erc20.balanceOf(wallet_address).call().await.map_err(|e|{   
-                        eprintln!("Error fetching USDC balance for {:?}: {:?}", wallet_address, e);
-                        AppError::InternalError(format!("Provider error: {e}"))
-                    })?;
-
-                if usdc_token_balance > U256::ZERO {
-
-                        let call = erc20.transfer(master_wallet_address, usdc_token_balance);
-
-                        let usdc_transfer_gas = call.from(wallet_address)
-                            .estimate_gas()
-                            .await.map_err(|e|{
-                            eprintln!("Error Cannot estimate gas {:?}: {:?}", wallet_address, e);
-                            AppError::InternalError(format!("Error Cannot estimate gas : {e}"))
-                        } )?;
-
-                        let mut  minimum_gas = U256::from(usdc_transfer_gas) * U256::from(gas_price);
-
-                        minimum_gas = minimum_gas * U256::from(2);
-
-                    if gas_balance >= minimum_gas { 
-                            let tx_hash = erc20.transfer(master_wallet_address, usdc_token_balance).send().await.map_err(|e|{
-                            eprintln!("Error: Cannot sent {:?}: {:?}", master_wallet_address , e);
-                            AppError::InternalError(format!("Provider error: {e}"))
-                    })?.watch().await.map_err(|e|{
-                            eprintln!("Error : Cannot get receipt  {:?}: {:?}", master_wallet_address , e);
-                            AppError::InternalError(format!("Provider error: {e}"))
-                    })?;
-
-                    println!("Transaction Hash : {} Token:{}", tx_hash, token_name);
-                    let usdc_decimal = u256_to_decimal(usdc_token_balance, decimals)?;
-
-                    upsert_user_balance_and_receipt(
-                                &txn,
-                                user_id,
-                                &wallet_address.to_string(),
-                                &token_address.to_string(),
-                                &chain_name.to_string(),
-                                usdc_decimal,
-                                &tx_hash.to_string(),
-                            ).await?;
-                    
-                        }else{
-                            eprintln!("No Mininum gas Gas: {} Minimum Gas :{} Chain:{} Wallet:{} ", gas_balance, minimum_gas, chain_name, wallet_address);
-                        }
-                }else{
-                    println!("No token Balance {} Token :{} Chain:{} Wallet:{} ", usdc_token_balance, token_name, chain_name, wallet_address);
-                }
 
-            }else {
-
-                let decimals = get_token_decimals(&provider.0, token_address.clone()).await?;
-                usdt_token_balance  = erc20.balanceOf(wallet_address).call().await.map_err(|e|{
-                    eprintln!("Error fetching USDT balance for {:?}: {:?}", wallet_address, e);
-                    AppError::InternalError(format!("Provider error: {e}"))
-                } )?;
-
-
-            if usdt_token_balance > U256::ZERO  {
-                
-                let call = erc20.transfer(master_wallet_address, usdt_token_balance);
-
-                    let usdt_transfer_gas = call.from(wallet_address)
-                        .estimate_gas()
-                        .await.map_err(|e|{
-                        eprintln!("Error Cannot estimate gas {:?}: {:?}", wallet_address, e);
-                        AppError::InternalError(format!("Error Cannot estimate gas : {e}"))
-                    } )?;
-
-                    let mut  minimum_gas = U256::from(usdt_transfer_gas) * U256::from(gas_price);
-
-                    minimum_gas =  minimum_gas * U256::from(2);
-
-                if gas_balance >= minimum_gas {
-
-                    let tx_hash = erc20.transfer(master_wallet_address, usdt_token_balance).send().await.map_err(|e|{
-                                eprintln!("Error: Cannot sent Wallet:{:?} Gas : {:?} error: {:?}", master_wallet_address , gas_balance, e );
-                                AppError::InternalError(format!("Provider error: {e}"))
-                        })?.watch().await.map_err(|e|{
-                                eprintln!("Error : Cannot get receipt  {:?}: {:?}", master_wallet_address , e);
-                                AppError::InternalError(format!("Provider error: {e}"))
-                        })?;
-                    
-                    println!("Transaction Hash : {} Token:{}", tx_hash, token_name);
-
-                    let usdt_decimal = u256_to_decimal(usdt_token_balance, decimals)?;
-
-                    upsert_user_balance_and_receipt(
-                            &txn,
-                            user_id,
-                            &wallet_address.to_string(),
-                            &token_address.to_string(),
-                            &chain_name.to_string(),
-                            usdt_decimal,
-                            &tx_hash.to_string(),
-                        )
-                        .await?;
-
-                    }else{
-                        eprintln!("No Mininum gas Gas : {} Minimum Gas :{} Chain:{} Wallet:{} ", gas_balance, minimum_gas, chain_name, wallet_address);
-                    }
-                }else{
-                    println!("No token Balance {} Token :{} Chain:{} Wallet:{} ", usdt_token_balance, token_name, chain_name, wallet_address);
+/// What sweeping a wallet's assets ran into; decides the wallet's next status.
+#[derive(Debug, Default)]
+struct SweepOutcome {
+    swept_any: bool,
+    /// Some asset was left alone because an earlier sweep of it is still unconfirmed.
+    in_flight: bool,
+    needs_gas: bool,
+    gas_funded: bool,
+    fees_too_high: bool,
+}
+
+impl SweepOutcome {
+    fn next_status(&self) -> (WalletStatus, &'static str) {
+        if self.gas_funded {
+            (WalletStatus::Sweepable, "gas topped up, re-queued for sweeping")
+        } else if self.needs_gas {
+            (WalletStatus::GasPending, "holds tokens without gas to move them")
+        } else if self.fees_too_high {
+            (WalletStatus::Sweepable, "fees above policy, retrying later")
+        } else if self.swept_any {
+            (WalletStatus::Confirming, "sweeps waiting for confirmations")
+        } else if self.in_flight {
+            // Not `FREE`: if the sweep is dropped, recovery only re-queues `CONFIRMING` wallets
+            (WalletStatus::Confirming, "earlier sweeps still in flight")
+        } else {
+            (WalletStatus::Free, "nothing left to sweep")
+        }
+    }
+}
+
+
+
+/// Sweeps every registry token of the wallet, then its native balance.
+///
+/// When planning, a wallet short of gas is reported instead of funded.
+async fn sweep_assets(ctx: &SweepContext<'_>, tokens: &[TokenEntry]) -> Result<SweepOutcome, AppError> {
+
+    let mut outcome = SweepOutcome::default();
+
+    for token in tokens {
+
+        match sweep_token(ctx, token).await? {
+            TokenSweep::Swept => outcome.swept_any = true,
+            TokenSweep::InFlight => outcome.in_flight = true,
+            TokenSweep::Skipped => {}
+            TokenSweep::FeesTooHigh => {
+                ctx.plan(|| PlannedAction::FeesTooHigh { token: token.address.to_string(), symbol: token.symbol.clone() });
+                outcome.fees_too_high = true;
+            }
+            TokenSweep::InsufficientGas { gas_balance, minimum_gas } => {
+                if ctx.planned.is_some() {
+                    ctx.plan(|| PlannedAction::NeedsGas {
+                        token: token.address.to_string(),
+                        symbol: token.symbol.clone(),
+                        gas_balance: gas_balance.to_string(),
+                        minimum_gas: minimum_gas.to_string(),
+                    });
+                    outcome.needs_gas = true;
+                } else if request_gas_top_up(ctx.db, ctx.chain_name, ctx.user_id, ctx.wallet_address, gas_balance, minimum_gas).await {
+                    outcome.gas_funded = true;
+                } else {
+                    outcome.needs_gas = true;
                 }
             }
+        }
+    }
 
+    // A freshly funded wallet is re-queued; sweeping native now would send the donated gas straight back
+    if !outcome.gas_funded {
+        // Native sweep runs last so the ERC-20 transfers above can still pay for their gas
+        match sweep_native_balance(ctx).await? {
+            TokenSweep::Swept => outcome.swept_any = true,
+            TokenSweep::InFlight => outcome.in_flight = true,
+            TokenSweep::FeesTooHigh => {
+                ctx.plan(|| PlannedAction::FeesTooHigh { token: Address::ZERO.to_string(), symbol: ctx.chain.native_symbol.clone() });
+                outcome.fees_too_high = true;
+            }
+            TokenSweep::Skipped | TokenSweep::InsufficientGas { .. } => {}
+        }
+    }
+
+    Ok(outcome)
+}
+
+
+
+/// Whether `worker` still holds the sweep lease on `wallet`.
+fn is_claimed_by(wallet: &user_wallet::Model, worker: &str) -> bool {
+    wallet.status == WalletStatus::Sweeping && wallet.claimed_by.as_deref() == Some(worker)
+}
 
+
+
+/// Everything needed to sweep one deposit wallet on one chain.
+struct SweepContext<'a> {
+    provider: &'a SignerProvider,
+    db: &'a DbConnection,
+    /// The wallet's locking transaction; deposits are recorded in it.
+    txn: &'a sea_orm::DatabaseTransaction,
+    chain: &'a ChainEntry,
+    chain_name: &'a str,
+    user_id: Uuid,
+    wallet_id: Uuid,
+    wallet_address: Address,
+    /// The hot treasury wallet; `sweep_destination` redirects to cold storage once it is full.
+    master_wallet_address: Address,
+    /// Balances from the batch scan, used to skip assets with nothing worth sweeping.
+    scanned: Option<&'a WalletBalances>,
+    /// Set for a dry run: actions are collected here instead of being carried out.
+    planned: Option<&'a Mutex<Vec<PlannedAction>>>,
+}
+
+impl SweepContext<'_> {
+    /// Adds an action to the dry-run plan; does nothing on a live sweep.
+    fn plan(&self, action: impl FnOnce() -> PlannedAction) {
+        if let Some(planned) = self.planned {
+            planned.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push(action());
         }
     }
-    println!("Making Wallet {} FREE", wallet_address);
-    let mut complete_request: user_wallet::ActiveModel = pending_wallet.into();
-                        complete_request.status = Set("FREE".to_string());
-                        complete_request
-                        .update(&txn)
-                        .await
-                        .map_err(AppError::DbError)?;
+}
 
-    txn.commit().await.map_err(AppError::DbError)?;
 
 
+/// Result of sweeping one asset from a deposit wallet.
+enum TokenSweep {
+    /// The balance was transferred to the treasury and credited.
+    Swept,
+    /// Nothing to do: zero balance, or dust below the token's `min_sweep_amount`
+    /// (handled by its dust policy).
+    Skipped,
+    /// Fees are above the chain's cap or too large a share of the balance; retry later.
+    FeesTooHigh,
+    /// The wallet holds the token but not enough native gas to move it.
+    InsufficientGas { gas_balance: U256, minimum_gas: U256 },
+    /// An earlier sweep of the asset is still unconfirmed; the recovery pass settles it.
+    InFlight,
+}
+
+
+
+/// Sweeps a single registry token from `wallet_address` to the treasury picked by `sweep_destination`.
+///
+/// Token-specific behaviour comes from the registry entry rather than the
+/// symbol: `decimals` and `min_sweep_amount` decide what is worth moving (smaller
+/// balances go to `record_dust`), and
+/// `transfer_returns_bool` says whether `transfer` must return `true` or, like
+/// USDT, returns nothing at all. Every transfer is simulated with `eth_call`
+/// first and checked against its receipt afterwards, so a token that fails
+/// silently is never credited.
+async fn sweep_token(ctx: &SweepContext<'_>, token: &TokenEntry) -> Result<TokenSweep, AppError> {
+
+    let SweepContext { provider, chain_name, wallet_address, master_wallet_address, .. } = *ctx;
+    let erc20 = ERC20::new(token.address, provider);
+
+    if has_in_flight_attempt(ctx.db, ctx.wallet_id, chain_name, token.address).await? {
+        println!("Sweep already in flight Token:{} Chain:{} Wallet:{} ", token.symbol, chain_name, wallet_address);
+        return Ok(TokenSweep::InFlight);
+    }
+
+    if let Some(scanned) = ctx.scanned
+        && !scanned.token_worth_sweeping(token)
+    {
+        if let Some(amount) = scanned.token_amount(token).filter(|amount| !amount.is_zero()) {
+            handle_dust(ctx, token, amount).await?;
+        }
+        return Ok(TokenSweep::Skipped);
+    }
+
+    let decimals = verified_decimals(provider, ctx.db, token).await?;
+
+    let token_balance = erc20.balanceOf(wallet_address).call().await.map_err(|e|{
+            eprintln!("Error fetching {} balance for {:?}: {:?}", token.symbol, wallet_address, e);
+            AppError::InternalError(format!("Provider error: {e}"))
+    })?;
+
+    if token_balance.is_zero() {
+        println!("No token Balance {} Token :{} Chain:{} Wallet:{} ", token_balance, token.symbol, chain_name, wallet_address);
+        return Ok(TokenSweep::Skipped);
+    }
+
+    let token_decimal = u256_to_decimal(token_balance, decimals)?;
+
+    if token_decimal < token.min_sweep_amount {
+        println!("Token Balance {} below minimum sweep {} Token :{} Chain:{} Wallet:{} ", token_decimal, token.min_sweep_amount, token.symbol, chain_name, wallet_address);
+        handle_dust(ctx, token, token_decimal).await?;
+        return Ok(TokenSweep::Skipped);
+    }
+
+    let destination = sweep_destination(provider, &ctx.db.0, ctx.chain, master_wallet_address, token.address, decimals).await?;
+    let call = erc20.transfer(destination.address, token_balance).from(wallet_address);
+
+    let returned = call.call_raw().await.map_err(|e|{
+            eprintln!("Error: {} transfer simulation failed {:?}: {:?}", token.symbol, wallet_address, e);
+            AppError::InternalError(format!("Transfer simulation failed: {e}"))
+    })?;
+
+    if !transfer_succeeded(&returned, token.transfer_returns_bool) {
+        return Err(AppError::InternalError(format!(
+            "{} transfer on {} returned {} instead of success", token.symbol, chain_name, returned
+        )));
+    }
+
+    let gas_balance = provider.get_balance(wallet_address).await.map_err(|e| AppError::InternalError(format!("Cannot fetch native balance: {e}")))?;
+
+    let transfer_gas = call.estimate_gas().await.map_err(|e|{
+            eprintln!("Error Cannot estimate gas {:?}: {:?}", wallet_address, e);
+            AppError::InternalError(format!("Error Cannot estimate gas : {e}"))
+    } )?;
+
+    let Some(fees) = quote_fees(provider, ctx.chain).await? else {
+        println!("Fees above cap Token:{} Chain:{} Wallet:{} ", token.symbol, chain_name, wallet_address);
+        return Ok(TokenSweep::FeesTooHigh);
+    };
+
+    let minimum_gas = fees.worst_case_fee(transfer_gas);
+
+    if !fee_within_ratio(ctx.chain, minimum_gas, token.native_price.map(|price| token_decimal * price))? {
+        println!("Fee {} too high for {} {} Chain:{} Wallet:{} ", minimum_gas, token_decimal, token.symbol, chain_name, wallet_address);
+        return Ok(TokenSweep::FeesTooHigh);
+    }
+
+    if gas_balance < minimum_gas {
+        eprintln!("No Mininum gas Gas: {} Minimum Gas :{} Token:{} Chain:{} Wallet:{} ", gas_balance, minimum_gas, token.symbol, chain_name, wallet_address);
+        return Ok(TokenSweep::InsufficientGas { gas_balance, minimum_gas });
+    }
+
+    let intent = SweepIntent {
+        user_id: ctx.user_id,
+        wallet_id: ctx.wallet_id,
+        wallet_address,
+        chain: chain_name,
+        token: token.address,
+        amount: token_decimal,
+    };
+
+    if ctx.planned.is_some() {
+        plan_sweep(ctx, &intent, &token.symbol, destination, transfer_gas, minimum_gas).await?;
+        return Ok(TokenSweep::Swept);
+    }
+
+    let mut tx = call.into_transaction_request().with_gas_limit(transfer_gas);
+    fees.apply(&mut tx);
+    broadcast_and_record(ctx, &intent, tx).await?;
+
+    Ok(TokenSweep::Swept)
+}
+
+
+
+/// Applies the token's dust policy to a balance below its sweep threshold, or plans it in a dry run.
+async fn handle_dust(ctx: &SweepContext<'_>, token: &TokenEntry, amount: Decimal) -> Result<(), AppError> {
+
+    let confirmed = confirmed_dust_balance(ctx.provider, ctx.chain, token, ctx.wallet_address, amount).await?;
+
+    if ctx.planned.is_none() {
+        return record_dust(ctx.txn, ctx.user_id, ctx.wallet_id, &ctx.wallet_address.to_string(), token, amount, confirmed).await;
+    }
+
+    let decision = dust_decision(ctx.txn, ctx.wallet_id, token, amount, confirmed).await?;
+    ctx.plan(|| PlannedAction::Dust {
+        token: token.address.to_string(),
+        symbol: token.symbol.clone(),
+        amount,
+        decision: decision.map_or("UNCHANGED", |decision| decision.decision).to_string(),
+        credit: decision.map_or(Decimal::ZERO, |decision| decision.credited),
+    });
+
+    Ok(())
+}
+
+
+
+/// Reports a sweep a dry run would have signed, with what it would credit once confirmed.
+async fn plan_sweep(
+    ctx: &SweepContext<'_>,
+    intent: &SweepIntent<'_>,
+    symbol: &str,
+    destination: Destination,
+    gas_limit: u64,
+    max_fee: U256,
+) -> Result<(), AppError> {
+
+    let token = intent.token.to_string();
+    let dust_credited = credited_dust(ctx.txn, ctx.wallet_id, ctx.chain_name, &token).await?;
+
+    ctx.plan(|| PlannedAction::Sweep {
+        token,
+        symbol: symbol.to_string(),
+        amount: intent.amount,
+        credit: (intent.amount - dust_credited).max(Decimal::ZERO),
+        destination: destination.address.to_string(),
+        cold: destination.cold,
+        gas_limit,
+        max_fee: max_fee.to_string(),
+    });
+
     Ok(())
 }
 
 
 
-fn u256_to_decimal(amount: U256, decimals: u8) -> Result<Decimal, AppError> {
-    let base = Decimal::from_i128(10_i128.pow(decimals as u32))
-        .ok_or_else(|| AppError::InternalError("Decimal overflow".into()))?;
+/// Interprets the return data of a simulated `transfer`.
+///
+/// Standard tokens must return ABI-encoded `true`. Tokens flagged as not
+/// returning a bool (USDT on Ethereum) succeed with empty return data, but a
+/// `true` is accepted too in case the contract was upgraded.
+pub fn transfer_succeeded(returned: &[u8], returns_bool: bool) -> bool {
+    match ERC20::transferCall::abi_decode_returns(returned) {
+        Ok(ok) => ok,
+        Err(_) => !returns_bool && returned.is_empty(),
+    }
+}
+
 
-    let value = Decimal::from_str(&amount.to_string())
-        .map_err(|e| AppError::InternalError(format!("Decimal parse error: {e}")))?;
 
-    Ok(value / base)
-}
\ No newline at end of file
+/// Asks the gas station to top up a wallet that holds tokens but not enough gas to move them.
+///
+/// Returns `true` once the top-up is confirmed on-chain. Gas station failures are
+/// logged rather than propagated so they never roll back sweeps already sent for this wallet.
+async fn request_gas_top_up(
+    db: &DbConnection,
+    chain_name: &str,
+    user_id: Uuid,
+    wallet_address: Address,
+    gas_balance: U256,
+    minimum_gas: U256,
+) -> bool {
+    match fund_wallet_gas(db, chain_name, user_id, wallet_address, gas_balance, minimum_gas).await {
+        Ok(funded) => funded.is_some(),
+        Err(e) => {
+            eprintln!("Gas station failed for Wallet:{} Chain:{}: {}", wallet_address, chain_name, e);
+            false
+        }
+    }
+}
+
+
+
+/// Sweeps the chain's native gas token (ETH on Base, BTC on Bitlayer) to the treasury picked by `sweep_destination`.
+///
+/// The transferable amount is the full balance minus the worst-case fee of the
+/// transfer itself (`gas_limit * max_fee_per_gas`), so the wallet is left with
+/// at most the unused part of that fee. Fees come from the chain's fee policy
+/// (see `quote_fees`), and a sweep whose fee is too large a share of the amount
+/// is left for later.
+///
+/// The deposit is credited under the zero address, which `get_token_decimals`
+/// treats as the 18-decimal native asset.
+async fn sweep_native_balance(ctx: &SweepContext<'_>) -> Result<TokenSweep, AppError> {
+
+    let SweepContext { provider, chain_name, wallet_address, master_wallet_address, .. } = *ctx;
+
+    if has_in_flight_attempt(ctx.db, ctx.wallet_id, chain_name, Address::ZERO).await? {
+        println!("Native sweep already in flight Chain:{} Wallet:{} ", chain_name, wallet_address);
+        return Ok(TokenSweep::InFlight);
+    }
+
+    if ctx.scanned.is_some_and(|scanned| !scanned.native_worth_sweeping(ctx.chain)) {
+        return Ok(TokenSweep::Skipped);
+    }
+
+    let native_balance = provider.get_balance(wallet_address).await.map_err(|e| AppError::InternalError(format!("Cannot fetch native balance: {e}")))?;
+
+    if native_balance.is_zero() {
+        println!("No native Balance Chain:{} Wallet:{} ", chain_name, wallet_address);
+        return Ok(TokenSweep::Skipped);
+    }
+
+    let destination = sweep_destination(provider, &ctx.db.0, ctx.chain, master_wallet_address, Address::ZERO, 18).await?;
+
+    let estimate_request = TransactionRequest::default()
+        .with_from(wallet_address)
+        .with_to(destination.address)
+        .with_value(U256::ZERO);
+
+    let gas_limit = provider.estimate_gas(estimate_request).await.map_err(|e|{
+            eprintln!("Error Cannot estimate native transfer gas {:?}: {:?}", wallet_address, e);
+            AppError::InternalError(format!("Error Cannot estimate gas : {e}"))
+    } )?;
+
+    let mut tx = TransactionRequest::default()
+        .with_from(wallet_address)
+        .with_to(destination.address)
+        .with_gas_limit(gas_limit);
+
+    let Some(fees) = quote_fees(provider, ctx.chain).await? else {
+        println!("Fees above cap Chain:{} Wallet:{} ", chain_name, wallet_address);
+        return Ok(TokenSweep::FeesTooHigh);
+    };
+    fees.apply(&mut tx);
+
+    // On OP-stack chains the L1 data fee comes out of the balance on top of the gas
+    let l1_fee = l1_data_fee(provider, &tx.clone().with_value(native_balance), ctx.chain.chain_id).await?;
+    let transfer_fee = fees.worst_case_fee(gas_limit) + l1_fee;
+
+    if native_balance <= transfer_fee {
+        println!("Native Balance {} below transfer fee {} Chain:{} Wallet:{} ", native_balance, transfer_fee, chain_name, wallet_address);
+        return Ok(TokenSweep::Skipped);
+    }
+
+    let sweep_amount = native_balance - transfer_fee;
+    tx.set_value(sweep_amount);
+
+    let decimals = get_token_decimals(provider, Address::ZERO).await?;
+    let native_decimal = u256_to_decimal(sweep_amount, decimals)?;
+
+    if native_decimal < ctx.chain.min_native_sweep {
+        println!("Native Balance {} below minimum sweep {} Chain:{} Wallet:{} ", native_decimal, ctx.chain.min_native_sweep, chain_name, wallet_address);
+        return Ok(TokenSweep::Skipped);
+    }
+
+    if !fee_within_ratio(ctx.chain, transfer_fee, Some(native_decimal))? {
+        println!("Fee {} too high for native {} Chain:{} Wallet:{} ", transfer_fee, native_decimal, chain_name, wallet_address);
+        return Ok(TokenSweep::FeesTooHigh);
+    }
+
+    let intent = SweepIntent {
+        user_id: ctx.user_id,
+        wallet_id: ctx.wallet_id,
+        wallet_address,
+        chain: chain_name,
+        token: Address::ZERO,
+        amount: native_decimal,
+    };
+
+    if ctx.planned.is_some() {
+        plan_sweep(ctx, &intent, &ctx.chain.native_symbol, destination, gas_limit, transfer_fee).await?;
+        return Ok(TokenSweep::Swept);
+    }
+
+    broadcast_and_record(ctx, &intent, tx).await?;
+
+    Ok(TokenSweep::Swept)
+}
+
+
+
+/// Broadcasts a sweep through the outbox and waits for it to be mined.
+///
+/// On success the pending deposit and the attempt's `MINED` state are written
+/// in the wallet's transaction, so they commit together. A revert marks the
+/// attempt `FAILED`; a timeout leaves it `BROADCAST` for the recovery pass.
+async fn broadcast_and_record(
+    ctx: &SweepContext<'_>,
+    intent: &SweepIntent<'_>,
+    tx: TransactionRequest,
+) -> Result<(), AppError> {
+
+    let (attempt, pending) = sign_and_broadcast(ctx.provider, ctx.db, intent, tx).await?;
+
+    let receipt = pending
+        .with_timeout(Some(RECEIPT_TIMEOUT))
+        .get_receipt()
+        .await
+        .map_err(|e|{
+            eprintln!("Error : Cannot get receipt  {}: {:?}", attempt.tx_hash , e);
+            AppError::InternalError(format!("Provider error: {e}"))
+    })?;
+
+    if !receipt.status() {
+        set_attempt_state(&ctx.db.0, attempt.id, "FAILED").await?;
+        return Err(AppError::InternalError(format!("Sweep {} reverted on {}", receipt.transaction_hash, ctx.chain_name)));
+    }
+
+    println!("Transaction Hash : {} Token:{}", receipt.transaction_hash, intent.token);
+
+    record_mined_sweep(ctx.txn, intent, &receipt).await?;
+    set_attempt_state(ctx.txn, attempt.id, "MINED").await?;
+
+    Ok(())
+}
+
+
+
+/// Records a mined sweep for the confirmation tracker instead of crediting it straight away.
+///
+/// Dust of the same token that was already credited under the `CREDIT` dust
+/// policy is settled by this sweep and left out of the amount to credit.
+pub async fn record_mined_sweep(
+    txn: &sea_orm::DatabaseTransaction,
+    intent: &SweepIntent<'_>,
+    receipt: &TransactionReceipt,
+) -> Result<(), AppError> {
+
+    let (Some(block_number), Some(block_hash)) = (receipt.block_number, receipt.block_hash) else {
+        return Err(AppError::InternalError(format!("Receipt {} has no block", receipt.transaction_hash)));
+    };
+
+    let tx_hash = receipt.transaction_hash.to_string();
+    let token = intent.token.to_string();
+    let dust_credited = settle_dust(txn, intent.wallet_id, intent.chain, &token, &tx_hash).await?;
+
+    record_pending_deposit(
+            txn,
+            intent.user_id,
+            &intent.wallet_address.to_string(),
+            &token,
+            intent.chain,
+            (intent.amount - dust_credited).max(Decimal::ZERO),
+            &tx_hash,
+            block_number,
+            &block_hash.to_string(),
+        )
+        .await
+}
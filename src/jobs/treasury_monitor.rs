@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::{
+    chain_config::{chain_config::create_read_provider, registry::{ChainEntry, registry}},
+    error::error::AppError,
+    state_models::models::DbConnection,
+    utils::{token_decimals::u256_to_decimal, treasury_router::{asset_balance, treasury_limits}},
+};
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(300);
+
+
+/// Periodically compares the hot wallet's balances against their `hot_floor`.
+///
+/// Moving funds back from cold storage needs the cold wallet's keys, which this
+/// process never holds, so a shortfall is only reported for an operator to top up.
+pub async fn run_treasury_monitor(db: DbConnection, hot: Address) -> Result<(), AppError> {
+    println!("TREASURY MONITOR RUNNING");
+    loop {
+        let registry = registry();
+
+        for chain in registry.chains() {
+            if let Err(e) = check_hot_floors(&db, chain, hot).await {
+                warn!("Treasury monitor failed on {}: {}", chain.name, e);
+            }
+        }
+
+        sleep(MONITOR_INTERVAL).await;
+    }
+}
+
+
+
+async fn check_hot_floors(db: &DbConnection, chain: &ChainEntry, hot: Address) -> Result<(), AppError> {
+
+    let registry = registry();
+    let provider = create_read_provider(&chain.name).await?;
+
+    let assets = std::iter::once((chain.native_symbol.as_str(), Address::ZERO, 18))
+        .chain(registry.tokens(&chain.name).iter().map(|token| (token.symbol.as_str(), token.address, token.decimals)));
+
+    for (symbol, token, decimals) in assets {
+        let Some(floor) = treasury_limits(&db.0, &chain.name, token).await?.and_then(|limits| limits.hot_floor) else {
+            continue;
+        };
+
+        let balance = u256_to_decimal(asset_balance(&provider, token, hot).await?, decimals)?;
+
+        if balance >= floor {
+            info!("Hot wallet holds {} {} on {} (floor {})", balance, symbol, chain.name, floor);
+            continue;
+        }
+
+        match chain.cold_wallet_address {
+            Some(cold) => warn!(
+                "Hot wallet holds {} {} on {}, {} below its floor of {}: top up from cold wallet {}",
+                balance, symbol, chain.name, floor - balance, floor, cold
+            ),
+            None => warn!(
+                "Hot wallet holds {} {} on {}, {} below its floor of {}, and the chain has no cold wallet",
+                balance, symbol, chain.name, floor - balance, floor
+            ),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,727 @@
+use std::str::FromStr;
+
+use alloy::{
+    eips::eip2718::{Decodable2718, Encodable2718}, network::TransactionBuilder, primitives::{Address, TxHash, U256}, providers::Provider,
+    rpc::types::TransactionRequest, consensus::{Transaction, TxEnvelope}
+};
+use alloy_signer_local::PrivateKeySigner;
+use rust_decimal::Decimal;
+use sea_orm::{
+    ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait, sea_query::{Expr, LockBehavior, LockType}
+};
+use tracing::{error, info, warn};
+
+use crate::{
+    chain_config::{chain_config::connect_with_signer, registry::{ChainEntry, TokenEntry, registry}},
+    config::config::AppConfig,
+    entities::{prelude::WithdrawRequest, withdraw_request},
+    error::error::AppError,
+    jobs::sweeper::transfer_succeeded,
+    state_models::models::{DbConnection, SignerProvider},
+    utils::{
+        fee_policy::{FeeQuote, quote_fees},
+        nonce_allocator::{allocate_nonce, release_nonce},
+        sweep_outbox::{is_stuck, parse_address, rebroadcast, sign_for_chain},
+        token_decimals::{ERC20, decimal_to_u256},
+        treasury_router::asset_balance,
+        update_withdrawal::{SIGNED_WITHDRAWAL_STATES, debit_user_balance, record_withdraw_receipt, refund_user_balance},
+    },
+};
+
+/// Requests a worker takes from the queue per cycle, so settling earlier ones is never starved.
+const WITHDRAWAL_BATCH: usize = 20;
+
+/// Blocks a transfer must stay unknown, with its nonce used, before it is refunded as dropped.
+/// Chains with a deeper `confirmations` wait that long instead.
+const DROPPED_AFTER_BLOCKS: u64 = 12;
+
+/// States in which the user's balance is debited and the transfer not settled yet.
+const OPEN_STATES: [&str; 3] = ["PROCESSING", "SIGNED", "BROADCAST"];
+
+
+/// What a validated withdrawal pays out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WithdrawalTransfer {
+    pub recipient: Address,
+    /// Token contract, or the zero address for the native coin.
+    pub token: Address,
+    /// Amount in the asset's smallest unit.
+    pub raw_amount: U256,
+    pub transfer_returns_bool: bool,
+}
+
+
+/// Outcome of trying to take the oldest `PENDING` request of a chain.
+enum Claim {
+    Empty,
+    Rejected,
+    /// The hot wallet cannot cover the request yet; it stays `PENDING` and the
+    /// rest of the cycle skips requests for the same asset.
+    Waiting(Address),
+    Debited(Box<withdraw_request::Model>),
+}
+
+
+/// The signer of the hot wallet paying out withdrawals, or `None` when
+/// `HOT_WALLET_PRIVATE_KEY` is not set.
+///
+/// The key must belong to `MASTER_WALLET_ADDRESS`: that is the wallet sweeps
+/// fill, and the only one the treasury monitor watches.
+pub fn hot_wallet_signer(config: &AppConfig) -> Result<Option<PrivateKeySigner>, AppError> {
+
+    let Some(key) = config.hot_wallet_private_key.as_deref() else {
+        return Ok(None);
+    };
+
+    let signer: PrivateKeySigner = key.trim().parse()
+        .map_err(|_| AppError::ConfigError("Invalid HOT_WALLET_PRIVATE_KEY".into()))?;
+
+    if signer.address() != config.master_wallet_address {
+        return Err(AppError::ConfigError(format!(
+            "HOT_WALLET_PRIVATE_KEY belongs to {} instead of MASTER_WALLET_ADDRESS {}", signer.address(), config.master_wallet_address
+        )));
+    }
+
+    Ok(Some(signer))
+}
+
+
+
+/// Checks a withdrawal request against the registry before anything is debited.
+///
+/// The recipient must be a valid non-zero address outside the treasury, the
+/// token the chain's native coin (zero address) or a registered token, and the
+/// amount positive and representable in the token's decimals.
+pub fn validate_withdrawal(
+    chain: &ChainEntry,
+    tokens: &[TokenEntry],
+    hot: Address,
+    request: &withdraw_request::Model,
+) -> Result<WithdrawalTransfer, String> {
+
+    if request.chain != chain.name {
+        return Err(format!("request for chain {} checked against {}", request.chain, chain.name));
+    }
+
+    let recipient = Address::from_str(request.user_address.trim())
+        .map_err(|_| format!("invalid recipient address {}", request.user_address))?;
+
+    if recipient.is_zero() || recipient == hot || chain.is_treasury(recipient) {
+        return Err(format!("recipient {} is not a user address", recipient));
+    }
+
+    let token = Address::from_str(request.token.trim())
+        .map_err(|_| format!("invalid token address {}", request.token))?;
+
+    let (decimals, transfer_returns_bool) = if token.is_zero() {
+        (18, true)
+    } else {
+        let entry = tokens
+            .iter()
+            .find(|entry| entry.address == token)
+            .ok_or_else(|| format!("token {} is not registered on {}", token, chain.name))?;
+        (entry.decimals, entry.transfer_returns_bool)
+    };
+
+    if request.amount <= Decimal::ZERO {
+        return Err(format!("amount {} is not positive", request.amount));
+    }
+
+    let raw_amount = decimal_to_u256(request.amount, decimals).map_err(|e| e.to_string())?;
+
+    Ok(WithdrawalTransfer { recipient, token, raw_amount, transfer_returns_bool })
+}
+
+
+
+/// One withdrawal cycle of `chain_name`, paid out from the `hot` wallet.
+///
+/// Settles requests already debited, then takes up to `WITHDRAWAL_BATCH`
+/// `PENDING` requests in order. A request the hot wallet cannot cover waits,
+/// along with later requests for the same asset, without holding up the
+/// other assets of the chain. Each is validated and checked against
+/// `user_balance`, which is debited in the same transaction that moves it to
+/// `PROCESSING`. The transfer is then signed, stored as `SIGNED` and only then
+/// broadcast, so a crash at any point leaves either a request to sign again or
+/// a raw transaction to re-broadcast, never a second payout.
+pub async fn process_withdrawals(chain_name: &str, db: &DbConnection, hot: &PrivateKeySigner) -> Result<(), AppError> {
+
+    let registry = registry();
+    let chain = registry
+        .chain(chain_name)
+        .ok_or_else(|| AppError::InternalError(format!("Chain {} is not in the registry", chain_name)))?;
+    let tokens = registry.tokens(chain_name);
+
+    let hot_address = hot.address();
+    let provider = connect_with_signer(chain_name, hot.clone()).await?;
+
+    let Some(fees) = quote_fees(&provider.0, chain).await? else {
+        warn!("Fees above cap, withdrawals on {} wait", chain_name);
+        return Ok(());
+    };
+
+    settle_withdrawals(db, &provider.0, chain, &fees, hot_address).await?;
+
+    let mut short_assets = Vec::new();
+
+    for _ in 0..WITHDRAWAL_BATCH {
+        let request = match claim_withdrawal(db, &provider.0, chain, tokens, hot_address, &short_assets).await? {
+            Claim::Empty => break,
+            Claim::Waiting(asset) => {
+                short_assets.push(asset);
+                continue;
+            }
+            Claim::Rejected => continue,
+            Claim::Debited(request) => *request,
+        };
+
+        if let Err(e) = send_withdrawal(db, &provider.0, chain, &fees, hot_address, &request).await {
+            warn!("Cannot send withdrawal {} on {}, retrying next cycle: {}", request.id, chain_name, e);
+        }
+    }
+
+    Ok(())
+}
+
+
+
+/// Takes the oldest `PENDING` request of `chain` not paying out one of
+/// `short_assets`, and debits it or rejects it.
+async fn claim_withdrawal(
+    db: &DbConnection,
+    provider: &SignerProvider,
+    chain: &ChainEntry,
+    tokens: &[TokenEntry],
+    hot: Address,
+    short_assets: &[Address],
+) -> Result<Claim, AppError> {
+
+    let txn = db.0.begin().await.map_err(AppError::DbError)?;
+
+    // Requests store whatever casing the application wrote
+    let skipped: Vec<String> = short_assets
+        .iter()
+        .flat_map(|asset| [asset.to_string(), asset.to_string().to_lowercase()])
+        .collect();
+
+    let Some(request) = WithdrawRequest::find()
+        .filter(withdraw_request::Column::Chain.eq(chain.name.as_str()))
+        .filter(withdraw_request::Column::Status.eq("PENDING"))
+        .filter(withdraw_request::Column::Token.is_not_in(skipped))
+        .order_by_asc(withdraw_request::Column::CreatedAt)
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .one(&txn)
+        .await
+        .map_err(AppError::DbError)?
+    else {
+        return Ok(Claim::Empty);
+    };
+
+    let transfer = match validate_withdrawal(chain, tokens, hot, &request) {
+        Ok(transfer) => transfer,
+        Err(reason) => {
+            set_withdrawal_status(&txn, &request, &["PENDING"], "REJECTED", Some(&reason)).await?;
+            txn.commit().await.map_err(AppError::DbError)?;
+            warn!("Rejected withdrawal {} on {}: {}", request.id, chain.name, reason);
+            return Ok(Claim::Rejected);
+        }
+    };
+
+    let committed = committed_amount(db, chain, transfer.token).await?;
+    let hot_balance = asset_balance(provider, transfer.token, hot).await?;
+
+    if hot_balance < committed.saturating_add(transfer.raw_amount) {
+        warn!(
+            "Hot wallet {} cannot cover withdrawal {} of {} {} on {} ({} held, {} already committed), waiting for a top-up",
+            hot, request.id, request.amount, transfer.token, chain.name, hot_balance, committed
+        );
+        return Ok(Claim::Waiting(transfer.token));
+    }
+
+    let balance_token = transfer.token.to_string();
+
+    if !debit_user_balance(&txn, request.userid, &balance_token, &chain.name, request.amount).await? {
+        set_withdrawal_status(&txn, &request, &["PENDING"], "REJECTED", Some("insufficient balance")).await?;
+        txn.commit().await.map_err(AppError::DbError)?;
+        warn!("Rejected withdrawal {} on {}: insufficient balance", request.id, chain.name);
+        return Ok(Claim::Rejected);
+    }
+
+    set_withdrawal_status(&txn, &request, &["PENDING"], "PROCESSING", None).await?;
+    txn.commit().await.map_err(AppError::DbError)?;
+
+    info!("Debited withdrawal {} of {} {} on {} for user {}", request.id, request.amount, balance_token, chain.name, request.userid);
+
+    Ok(Claim::Debited(Box::new(request)))
+}
+
+
+
+/// Raw amount of `token` owed by withdrawals of `chain` that are debited but not mined yet.
+async fn committed_amount(db: &DbConnection, chain: &ChainEntry, token: Address) -> Result<U256, AppError> {
+
+    let open = WithdrawRequest::find()
+        .filter(withdraw_request::Column::Chain.eq(chain.name.as_str()))
+        .filter(withdraw_request::Column::Status.is_in(OPEN_STATES))
+        .all(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    let registry = registry();
+    let tokens = registry.tokens(&chain.name);
+
+    let committed = open
+        .iter()
+        .filter(|request| Address::from_str(request.token.trim()).is_ok_and(|open| open == token))
+        .filter_map(|request| validate_withdrawal(chain, tokens, Address::ZERO, request).ok())
+        .fold(U256::ZERO, |total, transfer| total.saturating_add(transfer.raw_amount));
+
+    Ok(committed)
+}
+
+
+
+/// Signs the transfer of a `PROCESSING` request, stores it as `SIGNED` and broadcasts it.
+///
+/// A transfer the chain would revert (or a request the registry no longer
+/// allows) fails the request and refunds it. Any other error leaves it
+/// `PROCESSING` for the next cycle.
+async fn send_withdrawal(
+    db: &DbConnection,
+    provider: &SignerProvider,
+    chain: &ChainEntry,
+    fees: &FeeQuote,
+    hot: Address,
+    request: &withdraw_request::Model,
+) -> Result<(), AppError> {
+
+    let registry = registry();
+
+    let transfer = match validate_withdrawal(chain, registry.tokens(&chain.name), hot, request) {
+        Ok(transfer) => transfer,
+        Err(reason) => return fail_withdrawal(db, request, &["PROCESSING"], &reason).await,
+    };
+
+    let mut tx = if transfer.token.is_zero() {
+        let tx = TransactionRequest::default()
+            .with_from(hot)
+            .with_to(transfer.recipient)
+            .with_value(transfer.raw_amount);
+
+        let gas_limit = provider.estimate_gas(tx.clone()).await
+            .map_err(|e| AppError::InternalError(format!("Cannot estimate withdrawal gas: {e}")))?;
+
+        tx.with_gas_limit(gas_limit)
+    } else {
+        let erc20 = ERC20::new(transfer.token, provider);
+        let call = erc20.transfer(transfer.recipient, transfer.raw_amount).from(hot);
+
+        let returned = match call.call_raw().await {
+            Ok(returned) => returned,
+            // The node answered: the transfer itself reverts
+            Err(e) if e.as_revert_data().is_some() => {
+                return fail_withdrawal(db, request, &["PROCESSING"], &format!("transfer simulation reverted: {e}")).await;
+            }
+            Err(e) => return Err(AppError::InternalError(format!("Withdrawal simulation failed: {e}"))),
+        };
+
+        if !transfer_succeeded(&returned, transfer.transfer_returns_bool) {
+            return fail_withdrawal(db, request, &["PROCESSING"], &format!("transfer returned {returned} instead of success")).await;
+        }
+
+        let gas_limit = call.estimate_gas().await
+            .map_err(|e| AppError::InternalError(format!("Cannot estimate withdrawal gas: {e}")))?;
+
+        call.into_transaction_request().with_gas_limit(gas_limit)
+    };
+
+    fees.apply(&mut tx);
+
+    let nonce = allocate_nonce(provider, db, &chain.name, hot).await?;
+    tx.set_nonce(nonce);
+
+    let envelope = match sign_for_chain(provider, tx, chain.chain_id).await {
+        Ok(envelope) => envelope,
+        Err(e) => {
+            release_nonce(db, &chain.name, hot, nonce).await?;
+            return Err(e);
+        }
+    };
+
+    let raw_tx = envelope.encoded_2718();
+    let tx_hash = envelope.tx_hash().to_string();
+
+    // Stored before broadcast, so a crash from here on re-broadcasts this exact transaction
+    let stored = WithdrawRequest::update_many()
+        .col_expr(withdraw_request::Column::Status, Expr::value("SIGNED"))
+        .col_expr(withdraw_request::Column::Sender, Expr::value(hot.to_string()))
+        .col_expr(withdraw_request::Column::Nonce, Expr::value(envelope.nonce() as i64))
+        .col_expr(withdraw_request::Column::RawTx, Expr::value(hex::encode(&raw_tx)))
+        .col_expr(withdraw_request::Column::TxHash, Expr::value(tx_hash.clone()))
+        .col_expr(withdraw_request::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+        .filter(withdraw_request::Column::Id.eq(request.id))
+        .filter(withdraw_request::Column::Status.eq("PROCESSING"))
+        .exec(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    if stored.rows_affected != 1 {
+        release_nonce(db, &chain.name, hot, nonce).await?;
+        return Err(AppError::InternalError(format!("Withdrawal {} left PROCESSING while it was signed", request.id)));
+    }
+
+    if let Err(e) = provider.send_raw_transaction(&raw_tx).await {
+        warn!("Cannot broadcast withdrawal {} ({}), re-broadcasting next cycle: {}", request.id, tx_hash, e);
+        return Ok(());
+    }
+
+    set_withdrawal_status(&db.0, request, &["SIGNED"], "BROADCAST", None).await?;
+    info!("Sent withdrawal {} of {} to {} on {}: {}", request.id, request.amount, transfer.recipient, chain.name, tx_hash);
+
+    Ok(())
+}
+
+
+
+/// Drives every debited request of `chain` one step further, logging failures per request.
+async fn settle_withdrawals(
+    db: &DbConnection,
+    provider: &SignerProvider,
+    chain: &ChainEntry,
+    fees: &FeeQuote,
+    hot: Address,
+) -> Result<(), AppError> {
+
+    let open = WithdrawRequest::find()
+        .filter(withdraw_request::Column::Chain.eq(chain.name.as_str()))
+        .filter(withdraw_request::Column::Status.is_in(OPEN_STATES))
+        .order_by_asc(withdraw_request::Column::CreatedAt)
+        .all(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    if open.is_empty() {
+        return Ok(());
+    }
+
+    let head = provider.get_block_number().await
+        .map_err(|e| AppError::InternalError(format!("Cannot fetch block number: {e}")))?;
+
+    for request in open {
+        let result = match request.status.as_str() {
+            "PROCESSING" => send_withdrawal(db, provider, chain, fees, hot, &request).await,
+            _ => reconcile_withdrawal(db, provider, chain, fees, head, &request).await,
+        };
+
+        if let Err(e) = result {
+            warn!("Cannot settle withdrawal {} on {}: {}", request.id, chain.name, e);
+        }
+    }
+
+    Ok(())
+}
+
+
+
+/// Reconciles a `SIGNED` or `BROADCAST` request with the chain.
+///
+/// Every transaction signed for the request is checked, since a fee-bumped
+/// replacement and the one it replaced share a nonce and either may be mined.
+///
+/// - mined and `chain.confirmations` deep: `COMPLETED` with a `withdraw_receipt`;
+/// - mined but reverted: `FAILED` and refunded;
+/// - unknown to the node with its nonce used by another transaction, on every
+///   check for `DROPPED_AFTER_BLOCKS` (or `chain.confirmations`) blocks: `FAILED`
+///   and refunded. Any single endpoint may be lagging, so one miss is never enough;
+/// - unmined for longer than the chain's `rbf_after`: replaced with bumped fees;
+/// - otherwise the stored raw transaction is re-broadcast.
+async fn reconcile_withdrawal(
+    db: &DbConnection,
+    provider: &SignerProvider,
+    chain: &ChainEntry,
+    fees: &FeeQuote,
+    head: u64,
+    request: &withdraw_request::Model,
+) -> Result<(), AppError> {
+
+    let (Some(tx_hash), Some(raw_tx), Some(nonce), Some(sender)) =
+        (request.tx_hash.as_deref(), request.raw_tx.as_deref(), request.nonce, request.sender.as_deref())
+    else {
+        return Err(AppError::InternalError(format!("Withdrawal {} is {} without a stored transaction", request.id, request.status)));
+    };
+
+    let mut hashes = Vec::new();
+    for signed in signed_tx_hashes(request) {
+        let hash = TxHash::from_str(signed)
+            .map_err(|e| AppError::InternalError(format!("Invalid tx hash {}: {e}", signed)))?;
+        hashes.push(hash);
+    }
+
+    let mut receipt = None;
+    for hash in &hashes {
+        receipt = provider.get_transaction_receipt(*hash).await
+            .map_err(|e| AppError::InternalError(format!("Cannot fetch receipt: {e}")))?;
+
+        if receipt.is_some() {
+            break;
+        }
+    }
+
+    if receipt.is_some() {
+        clear_dropped_at(db, request).await?;
+    }
+
+    match receipt {
+        Some(receipt) if receipt.status() => {
+            let depth = receipt.block_number.map_or(0, |block| head.saturating_sub(block) + 1);
+            if depth < chain.confirmations {
+                return Ok(());
+            }
+
+            let mined_hash = receipt.transaction_hash.to_string();
+
+            let txn = db.0.begin().await.map_err(AppError::DbError)?;
+            if set_withdrawal_status(&txn, request, &SIGNED_WITHDRAWAL_STATES, "COMPLETED", None).await? {
+                if mined_hash != tx_hash {
+                    WithdrawRequest::update_many()
+                        .col_expr(withdraw_request::Column::TxHash, Expr::value(mined_hash.clone()))
+                        .filter(withdraw_request::Column::Id.eq(request.id))
+                        .exec(&txn)
+                        .await
+                        .map_err(AppError::DbError)?;
+                }
+                record_withdraw_receipt(&txn, request, &mined_hash).await?;
+            }
+            txn.commit().await.map_err(AppError::DbError)?;
+
+            info!("Completed withdrawal {} after {} confirmations: {}", request.id, depth, mined_hash);
+        }
+        Some(receipt) => {
+            fail_withdrawal(db, request, &SIGNED_WITHDRAWAL_STATES, &format!("transfer {} reverted", receipt.transaction_hash)).await?;
+        }
+        None => {
+            let mined_nonce = provider.get_transaction_count(parse_address(sender)?).latest().await
+                .map_err(|e| AppError::InternalError(format!("Cannot fetch nonce: {e}")))?;
+
+            if mined_nonce > nonce as u64 {
+                // A lagging endpoint may not have the receipt yet; only refund what no node knows about
+                for hash in &hashes {
+                    let known = provider.get_transaction_by_hash(*hash).await
+                        .map_err(|e| AppError::InternalError(format!("Cannot fetch transaction: {e}")))?;
+
+                    if known.is_some() {
+                        return clear_dropped_at(db, request).await;
+                    }
+                }
+
+                let Some(dropped_at) = request.dropped_at_block else {
+                    WithdrawRequest::update_many()
+                        .col_expr(withdraw_request::Column::DroppedAtBlock, Expr::value(head as i64))
+                        .filter(withdraw_request::Column::Id.eq(request.id))
+                        .exec(&db.0)
+                        .await
+                        .map_err(AppError::DbError)?;
+
+                    warn!("Withdrawal {} on {} looks dropped at block {}, re-checking before refunding", tx_hash, chain.name, head);
+                    return Ok(());
+                };
+
+                if head < dropped_at.max(0) as u64 + chain.confirmations.max(DROPPED_AFTER_BLOCKS) {
+                    return Ok(());
+                }
+
+                return fail_withdrawal(
+                    db,
+                    request,
+                    &SIGNED_WITHDRAWAL_STATES,
+                    &format!("transfer {} dropped since block {}, nonce {} used by another transaction", tx_hash, dropped_at, nonce),
+                )
+                .await;
+            }
+
+            // The nonce is free again as far as this endpoint knows, so the drop checks start over
+            clear_dropped_at(db, request).await?;
+
+            let raw_tx = hex::decode(raw_tx)
+                .map_err(|e| AppError::InternalError(format!("Invalid raw tx for {}: {e}", tx_hash)))?;
+
+            // `updated_at` moves when the request is signed, broadcast or replaced
+            if request.status == "BROADCAST" && is_stuck(chain, request.updated_at) {
+                match replace_stuck_withdrawal(db, provider, chain, fees, request, &raw_tx).await {
+                    Ok(true) => return Ok(()),
+                    Ok(false) => {}
+                    Err(e) => warn!("Cannot replace stuck withdrawal {} on {}: {}", tx_hash, chain.name, e),
+                }
+            }
+
+            if rebroadcast(provider, &raw_tx, tx_hash, &chain.name).await && request.status == "SIGNED" {
+                set_withdrawal_status(&db.0, request, &["SIGNED"], "BROADCAST", None).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+
+
+/// Hashes of every transaction signed for `request`, the current one first.
+fn signed_tx_hashes(request: &withdraw_request::Model) -> Vec<&str> {
+    request
+        .tx_hash
+        .as_deref()
+        .into_iter()
+        .chain(request.replaced_tx_hashes.as_deref().unwrap_or_default().split(','))
+        .map(str::trim)
+        .filter(|hash| !hash.is_empty())
+        .collect()
+}
+
+
+
+/// Re-signs a stuck withdrawal with the same nonce and fees bumped over `current`, and broadcasts it.
+///
+/// The replacement takes over `raw_tx`/`tx_hash`; the hash it replaces moves
+/// to `replaced_tx_hashes`, since either transaction may still be mined.
+/// Returns `false` when the bump would break the chain's fee cap.
+async fn replace_stuck_withdrawal(
+    db: &DbConnection,
+    provider: &SignerProvider,
+    chain: &ChainEntry,
+    current: &FeeQuote,
+    request: &withdraw_request::Model,
+    raw_tx: &[u8],
+) -> Result<bool, AppError> {
+
+    let (Some(tx_hash), Some(sender)) = (request.tx_hash.as_deref(), request.sender.as_deref()) else {
+        return Ok(false);
+    };
+
+    let stuck = TxEnvelope::decode_2718(&mut &raw_tx[..])
+        .map_err(|e| AppError::InternalError(format!("Cannot decode raw tx {}: {e}", tx_hash)))?;
+
+    let signed = FeeQuote {
+        max_fee_per_gas: stuck.max_fee_per_gas(),
+        max_priority_fee_per_gas: stuck.max_priority_fee_per_gas(),
+    };
+
+    let Some(bumped) = signed.bumped(current, chain) else {
+        warn!("Cannot bump withdrawal {} on {} within the fee cap", tx_hash, chain.name);
+        return Ok(false);
+    };
+
+    let mut tx = TransactionRequest::from_transaction_with_sender(stuck, parse_address(sender)?);
+    bumped.apply(&mut tx);
+
+    let replacement = sign_for_chain(provider, tx, chain.chain_id).await?;
+    let replacement_raw = replacement.encoded_2718();
+    let replacement_hash = replacement.tx_hash().to_string();
+
+    let replaced = match request.replaced_tx_hashes.as_deref().filter(|older| !older.trim().is_empty()) {
+        Some(older) => format!("{},{}", older, tx_hash),
+        None => tx_hash.to_string(),
+    };
+
+    // Stored before broadcast, like the original, and only over the transaction it replaces
+    let stored = WithdrawRequest::update_many()
+        .col_expr(withdraw_request::Column::RawTx, Expr::value(hex::encode(&replacement_raw)))
+        .col_expr(withdraw_request::Column::TxHash, Expr::value(replacement_hash.clone()))
+        .col_expr(withdraw_request::Column::ReplacedTxHashes, Expr::value(replaced))
+        .col_expr(withdraw_request::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+        .filter(withdraw_request::Column::Id.eq(request.id))
+        .filter(withdraw_request::Column::TxHash.eq(tx_hash))
+        .filter(withdraw_request::Column::Status.eq("BROADCAST"))
+        .exec(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    if stored.rows_affected != 1 {
+        return Ok(false);
+    }
+
+    if let Err(e) = provider.send_raw_transaction(&replacement_raw).await {
+        warn!("Cannot broadcast replacement {} of withdrawal {}, re-broadcasting next cycle: {}", replacement_hash, request.id, e);
+    }
+
+    info!(
+        "Replaced stuck withdrawal {} on {} with {} (max fee {} -> {})",
+        tx_hash, chain.name, replacement_hash, signed.max_fee_per_gas, bumped.max_fee_per_gas
+    );
+
+    Ok(true)
+}
+
+
+
+/// Forgets that the transfer of `request` once looked dropped, now that a node knows it again.
+async fn clear_dropped_at(db: &DbConnection, request: &withdraw_request::Model) -> Result<(), AppError> {
+
+    if request.dropped_at_block.is_none() {
+        return Ok(());
+    }
+
+    WithdrawRequest::update_many()
+        .col_expr(withdraw_request::Column::DroppedAtBlock, Expr::value(Option::<i64>::None))
+        .filter(withdraw_request::Column::Id.eq(request.id))
+        .exec(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    Ok(())
+}
+
+
+
+/// Moves a debited request to `FAILED` and refunds its debit, once.
+async fn fail_withdrawal(
+    db: &DbConnection,
+    request: &withdraw_request::Model,
+    from: &[&str],
+    reason: &str,
+) -> Result<(), AppError> {
+
+    let txn = db.0.begin().await.map_err(AppError::DbError)?;
+
+    if !set_withdrawal_status(&txn, request, from, "FAILED", Some(reason)).await? {
+        return Ok(());
+    }
+
+    // Debited under the canonical token address, which validation guaranteed parses
+    let balance_token = parse_address(request.token.trim())?.to_string();
+    refund_user_balance(&txn, request.userid, &balance_token, &request.chain, request.amount).await?;
+
+    txn.commit().await.map_err(AppError::DbError)?;
+
+    error!("Withdrawal {} on {} failed and was refunded: {}", request.id, request.chain, reason);
+
+    Ok(())
+}
+
+
+
+/// Moves `request` to `status` if it is still in one of `from`; returns whether it was.
+async fn set_withdrawal_status<C: sea_orm::ConnectionTrait>(
+    conn: &C,
+    request: &withdraw_request::Model,
+    from: &[&str],
+    status: &str,
+    reason: Option<&str>,
+) -> Result<bool, AppError> {
+
+    let mut update = WithdrawRequest::update_many()
+        .col_expr(withdraw_request::Column::Status, Expr::value(status))
+        .col_expr(withdraw_request::Column::UpdatedAt, Expr::value(chrono::Utc::now()));
+
+    if let Some(reason) = reason {
+        update = update.col_expr(withdraw_request::Column::FailureReason, Expr::value(reason));
+    }
+
+    let result = update
+        .filter(withdraw_request::Column::Id.eq(request.id))
+        .filter(withdraw_request::Column::Status.is_in(from.iter().copied()))
+        .exec(conn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    Ok(result.rows_affected == 1)
+}
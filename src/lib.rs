@@ -0,0 +1,11 @@
+#![allow(clippy::module_inception)]
+
+pub mod db;
+pub mod error;
+pub mod config;
+pub mod state_models;
+pub mod chain_config;
+pub mod jobs;
+pub mod entities;
+pub mod tokens;
+pub mod utils;
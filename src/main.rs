@@ -1,34 +1,70 @@
+use std::time::Duration;
 
-use crate::{ db::connection::init_db, error::error::AppError,  jobs::index::{ run_sweeper}};
-pub mod db;
-pub mod error;
-pub mod config;
-pub mod state_models;
-pub mod chain_config;
-pub mod jobs;
-pub mod entities;
-pub mod tokens;
-pub mod utils;
+use avitus_casino_sweeper::{ chain_config::registry::{registry, reload_registry}, config::config::AppConfig, db::connection::init_db, error::error::AppError,  jobs::{confirmations::run_confirmation_tracker, dry_run::run_dry_run, index::{ run_sweeper, run_withdrawal_worker}, indexer::run_deposit_indexer, reaper::run_reaper, rpc_health::run_rpc_health_monitor, treasury_monitor::run_treasury_monitor, withdrawals::hot_wallet_signer, recovery::{recover_sweep_attempts, run_outbox_recovery}}, utils::{token_metadata::warm_token_metadata, tx_policy::check_treasuries_allowed}};
 
 
 #[actix_web::main] 
 async fn main() -> Result<(), AppError> {
     // Initialize logging first
-    
+
+    // An invalid MASTER_WALLET_ADDRESS (or any other setting) stops startup here
+    let config = AppConfig::from_env()
+        .inspect_err(|e| tracing::error!("Invalid configuration: {}", e))?;
+
     let db = init_db().await
         .map_err(|e| {
             tracing::error!("Database initialization failed: {}", e);
             AppError::InternalError(format!("DB init error: {}", e))
         })?;
-    
-    
+
+    reload_registry(&db).await
+        .inspect_err(|e| tracing::error!("Chain registry load failed: {}", e))?;
+
+    // Refuse to start sweeping tokens whose on-chain decimals disagree with the registry
+    warm_token_metadata(&db).await
+        .inspect_err(|e| tracing::error!("Token metadata verification failed: {}", e))?;
+
+    check_treasuries_allowed(config.master_wallet_address)
+        .inspect_err(|e| tracing::error!("Treasury allow-list check failed: {}", e))?;
+
+    // A hot wallet key that does not match MASTER_WALLET_ADDRESS stops startup here
+    let hot_wallet = hot_wallet_signer(&config)
+        .inspect_err(|e| tracing::error!("Invalid hot wallet key: {}", e))?;
+
+    // Report what a sweep cycle would do and stop before anything is signed or recovered
+    if config.sweeper_dry_run {
+        return run_dry_run(&db, &config.dry_run_report).await
+            .inspect_err(|e| tracing::error!("Dry run failed: {}", e));
+    }
+
+    // Settle transactions left in flight by a previous run before any worker starts
+    recover_sweep_attempts(&db, Duration::ZERO).await
+        .inspect_err(|e| tracing::error!("Sweep recovery failed: {}", e))?;
+
     // Use join_all to wait for all workers
-    let workers = vec![
-        tokio::spawn(run_sweeper(0, db.clone())),
-        tokio::spawn(run_sweeper(1, db.clone())),
-        tokio::spawn(run_sweeper(2, db.clone())),
-        tokio::spawn(run_sweeper(3, db.clone())),
+    let mut workers = vec![
+        tokio::spawn(run_confirmation_tracker(db.clone())),
+        tokio::spawn(run_outbox_recovery(db.clone())),
+        tokio::spawn(run_reaper(db.clone())),
+        tokio::spawn(run_deposit_indexer(db.clone())),
+        tokio::spawn(run_rpc_health_monitor()),
+        tokio::spawn(run_treasury_monitor(db.clone(), config.master_wallet_address)),
     ];
+
+    // One sweeper pool per enabled chain; chains enabled later need a restart to get workers
+    for chain in registry().chains() {
+        let pool_size = config.sweeper_workers.get(&chain.name).copied().unwrap_or(config.sweeper_default_workers);
+        tracing::info!("Starting {} sweeper workers for {}", pool_size, chain.name);
+
+        for worker_id in 0..pool_size as u64 {
+            workers.push(tokio::spawn(run_sweeper(chain.name.clone(), worker_id, db.clone())));
+        }
+
+        match &hot_wallet {
+            Some(hot_wallet) => workers.push(tokio::spawn(run_withdrawal_worker(chain.name.clone(), db.clone(), hot_wallet.clone()))),
+            None => tracing::warn!("HOT_WALLET_PRIVATE_KEY not set, withdrawals on {} are not processed", chain.name),
+        }
+    }
     
     // Wait for all workers (they should run forever unless error)
     for worker in workers {
@@ -1,12 +1,17 @@
 use sea_orm::DatabaseConnection;
-use alloy::{network::EthereumWallet, providers::{fillers::{BlobGasFiller, ChainIdFiller, FillProvider, GasFiller, JoinFill, NonceFiller, WalletFiller}, Identity, RootProvider}};
+use alloy::providers::{fillers::{ChainIdFiller, FillProvider, GasFiller, JoinFill, NonceFiller, SimpleNonceManager, WalletFiller}, Identity, RootProvider};
+
+use crate::chain_config::signer_registry::SignerRegistry;
 
 #[derive(Debug, Clone)]
 pub struct DbConnection(pub DatabaseConnection);
 
 
+/// Provider returned by `create_provider`: gas and nonce fillers, the registry chain id and the shared signer registry.
+pub type SignerProvider = FillProvider<JoinFill<JoinFill<JoinFill<JoinFill<Identity, GasFiller>, NonceFiller<SimpleNonceManager>>, ChainIdFiller>, WalletFiller<SignerRegistry>>, RootProvider>;
+
 #[derive(Debug, Clone)]
-pub struct ProviderConnection(pub FillProvider<JoinFill<JoinFill<JoinFill<Identity, JoinFill<GasFiller, JoinFill<BlobGasFiller, JoinFill<NonceFiller, ChainIdFiller>>>>, NonceFiller>, WalletFiller<EthereumWallet>>, RootProvider>);
+pub struct ProviderConnection(pub SignerProvider);
 
 
 
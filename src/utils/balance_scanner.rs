@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use alloy::{
+    primitives::{Address, Bytes, U256}, providers::Provider, sol, sol_types::SolCall
+};
+
+use rust_decimal::Decimal;
+
+use crate::{
+    chain_config::registry::{ChainEntry, TokenEntry},
+    error::error::AppError,
+    utils::token_decimals::{ERC20, u256_to_decimal},
+};
+
+sol! {
+    #[sol(rpc)]
+    interface IMulticall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
+
+        function aggregate3(Call3[] calldata calls) external payable returns (Result[] memory returnData);
+        function getEthBalance(address addr) external view returns (uint256 balance);
+    }}
+
+/// Sub-calls per `aggregate3`; keeps each `eth_call` well under node gas and response limits.
+const CALLS_PER_BATCH: usize = 500;
+
+
+/// Balances of one deposit wallet read in a scan. `None` means the sub-call failed.
+#[derive(Debug, Default, Clone)]
+pub struct WalletBalances {
+    pub native: Option<U256>,
+    pub tokens: HashMap<Address, Option<U256>>,
+}
+
+impl WalletBalances {
+    /// Whether `token` may hold at least its `min_sweep_amount`; unknown balances count as yes.
+    pub fn token_worth_sweeping(&self, token: &TokenEntry) -> bool {
+        match self.tokens.get(&token.address).copied().flatten() {
+            None => true,
+            Some(balance) if balance.is_zero() => false,
+            Some(balance) => u256_to_decimal(balance, token.decimals)
+                .map(|amount| amount >= token.min_sweep_amount)
+                .unwrap_or(true),
+        }
+    }
+
+    /// Balance of `token` in whole tokens, if the scan read it.
+    pub fn token_amount(&self, token: &TokenEntry) -> Option<Decimal> {
+        let balance = self.tokens.get(&token.address).copied().flatten()?;
+        u256_to_decimal(balance, token.decimals).ok()
+    }
+
+    /// Whether the native balance may reach the chain's `min_native_sweep`; unknown counts as yes.
+    pub fn native_worth_sweeping(&self, chain: &ChainEntry) -> bool {
+        match self.native {
+            None => true,
+            Some(balance) if balance.is_zero() => false,
+            Some(balance) => u256_to_decimal(balance, 18)
+                .map(|amount| amount >= chain.min_native_sweep)
+                .unwrap_or(true),
+        }
+    }
+
+    /// Whether anything in the wallet is worth a sweep.
+    pub fn worth_sweeping(&self, chain: &ChainEntry, tokens: &[TokenEntry]) -> bool {
+        self.native_worth_sweeping(chain) || tokens.iter().any(|token| self.token_worth_sweeping(token))
+    }
+}
+
+
+/// Reads the native and registry-token balances of `wallets` through Multicall3.
+///
+/// One `aggregate3` call covers `CALLS_PER_BATCH` balance reads, so a few
+/// hundred wallets cost a handful of RPC calls instead of one per wallet and
+/// token. Sub-calls may fail individually (e.g. a paused token) without failing
+/// the scan. Returns `Ok(None)` when the chain has no Multicall3 configured.
+pub async fn scan_balances<P: Provider>(
+    provider: &P,
+    chain: &ChainEntry,
+    tokens: &[TokenEntry],
+    wallets: &[Address],
+) -> Result<Option<HashMap<Address, WalletBalances>>, AppError> {
+
+    let Some(multicall_address) = chain.multicall3 else {
+        return Ok(None);
+    };
+
+    // (wallet, token) per sub-call, the zero address standing for the native balance
+    let mut targets: Vec<(Address, Address)> = Vec::with_capacity(wallets.len() * (tokens.len() + 1));
+    let mut calls = Vec::with_capacity(targets.capacity());
+
+    for &wallet in wallets {
+        targets.push((wallet, Address::ZERO));
+        calls.push(IMulticall3::Call3 {
+            target: multicall_address,
+            allowFailure: true,
+            callData: Bytes::from(IMulticall3::getEthBalanceCall { addr: wallet }.abi_encode()),
+        });
+
+        for token in tokens {
+            targets.push((wallet, token.address));
+            calls.push(IMulticall3::Call3 {
+                target: token.address,
+                allowFailure: true,
+                callData: Bytes::from(ERC20::balanceOfCall { account: wallet }.abi_encode()),
+            });
+        }
+    }
+
+    let multicall = IMulticall3::new(multicall_address, provider);
+    let mut scanned: HashMap<Address, WalletBalances> = HashMap::with_capacity(wallets.len());
+
+    for (batch_targets, batch_calls) in targets.chunks(CALLS_PER_BATCH).zip(calls.chunks(CALLS_PER_BATCH)) {
+        let results = multicall.aggregate3(batch_calls.to_vec()).call().await.map_err(|e|{
+                eprintln!("Error: Multicall3 balance scan failed on {}: {:?}", chain.name, e);
+                AppError::InternalError(format!("Multicall3 error: {e}"))
+        })?;
+
+        if results.len() != batch_calls.len() {
+            return Err(AppError::InternalError(format!(
+                "Multicall3 on {} returned {} results for {} calls", chain.name, results.len(), batch_calls.len()
+            )));
+        }
+
+        for (&(wallet, token), result) in batch_targets.iter().zip(results) {
+            let balance = result
+                .success
+                .then(|| decode_balance(token, &result.returnData))
+                .flatten();
+
+            let entry = scanned.entry(wallet).or_default();
+            if token.is_zero() {
+                entry.native = balance;
+            } else {
+                entry.tokens.insert(token, balance);
+            }
+        }
+    }
+
+    Ok(Some(scanned))
+}
+
+
+
+/// Reads the native and registry-token balances of one wallet with a call per asset.
+///
+/// For one-off checks of a single address, where a Multicall3 batch buys nothing.
+/// A failed token read is left `None` like a failed scan sub-call.
+pub async fn read_balances<P: Provider>(
+    provider: &P,
+    tokens: &[TokenEntry],
+    wallet: Address,
+) -> Result<WalletBalances, AppError> {
+
+    let native = provider.get_balance(wallet).await
+        .map_err(|e| AppError::InternalError(format!("Cannot fetch native balance: {e}")))?;
+
+    let mut balances = WalletBalances { native: Some(native), tokens: HashMap::with_capacity(tokens.len()) };
+
+    for token in tokens {
+        let balance = ERC20::new(token.address, provider).balanceOf(wallet).call().await.ok();
+        balances.tokens.insert(token.address, balance);
+    }
+
+    Ok(balances)
+}
+
+
+
+fn decode_balance(token: Address, data: &[u8]) -> Option<U256> {
+    if token.is_zero() {
+        IMulticall3::getEthBalanceCall::abi_decode_returns(data).ok()
+    } else {
+        ERC20::balanceOfCall::abi_decode_returns(data).ok()
+    }
+}
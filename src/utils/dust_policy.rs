@@ -0,0 +1,257 @@
+use std::str::FromStr;
+
+use alloy::{eips::BlockId, primitives::Address, providers::Provider};
+use rust_decimal::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseTransaction, EntityTrait, QueryFilter, QueryOrder, sea_query::Expr
+};
+use uuid::Uuid;
+
+use crate::{
+    chain_config::registry::{ChainEntry, TokenEntry},
+    entities::{dust_ledger, prelude::DustLedger},
+    error::error::AppError,
+    utils::{token_decimals::{ERC20, u256_to_decimal}, update_deposit::upsert_user_balance_and_receipt},
+};
+
+
+/// What happens to a token balance below its `min_sweep_amount`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DustPolicy {
+    /// Leave it on the wallet until later deposits push it past the threshold.
+    #[default]
+    Accumulate,
+    /// Credit it right away and net it out of the sweep that eventually moves it.
+    Credit,
+}
+
+impl FromStr for DustPolicy {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "ACCUMULATE" => Ok(Self::Accumulate),
+            "CREDIT" => Ok(Self::Credit),
+            other => Err(AppError::ConfigError(format!("Unknown dust policy {other}"))),
+        }
+    }
+}
+
+
+
+/// A dust policy decision about to be written to `dust_ledger`.
+#[derive(Debug, Clone, Copy)]
+pub struct DustDecision {
+    /// `ACCUMULATED` or `CREDITED`.
+    pub decision: &'static str,
+    /// What the decision adds to `user_balance`.
+    pub credited: Decimal,
+}
+
+
+
+/// How much of `balance` a `CREDIT` dust policy may credit: the wallet's balance
+/// at the block `chain.confirmations` deep, capped at `balance`.
+///
+/// Dust is credited without a sweep, so this is what keeps it behind the same
+/// confirmation depth as every swept deposit. Other policies credit nothing
+/// and skip the read.
+pub async fn confirmed_dust_balance<P: Provider>(
+    provider: &P,
+    chain: &ChainEntry,
+    token: &TokenEntry,
+    wallet: Address,
+    balance: Decimal,
+) -> Result<Decimal, AppError> {
+
+    if token.dust_policy != DustPolicy::Credit {
+        return Ok(balance);
+    }
+
+    let head = provider.get_block_number().await
+        .map_err(|e| AppError::InternalError(format!("Cannot fetch block number: {e}")))?;
+
+    // Same depth rule as the confirmation tracker: the head block counts as one confirmation
+    let confirmed_block = (head + 1).saturating_sub(chain.confirmations);
+
+    let confirmed = ERC20::new(token.address, provider)
+        .balanceOf(wallet)
+        .block(BlockId::number(confirmed_block))
+        .call()
+        .await
+        .map_err(|e| AppError::InternalError(format!("Cannot fetch confirmed {} balance of {}: {e}", token.symbol, wallet)))?;
+
+    Ok(u256_to_decimal(confirmed, token.decimals)?.min(balance))
+}
+
+
+
+/// Decides what the token's dust policy does with `balance`, without writing anything.
+///
+/// `confirmed` is the part of `balance` deep enough to credit, from
+/// [`confirmed_dust_balance`]. Returns `None` when the balance is unchanged
+/// since the last decision and nothing more has become creditable.
+pub async fn dust_decision(
+    txn: &DatabaseTransaction,
+    wallet_id: Uuid,
+    token: &TokenEntry,
+    balance: Decimal,
+    confirmed: Decimal,
+) -> Result<Option<DustDecision>, AppError> {
+
+    let open = open_dust(txn, wallet_id, &token.chain, &token.address.to_string()).await?;
+    let already_credited: Decimal = open.iter().map(|row| row.credited).sum();
+
+    let credit = match token.dust_policy {
+        DustPolicy::Accumulate => Decimal::ZERO,
+        DustPolicy::Credit => (confirmed.min(balance) - already_credited).max(Decimal::ZERO),
+    };
+
+    if open.first().is_some_and(|last| last.balance == balance) && credit.is_zero() {
+        return Ok(None);
+    }
+
+    Ok(Some(match token.dust_policy {
+        DustPolicy::Accumulate => DustDecision { decision: "ACCUMULATED", credited: Decimal::ZERO },
+        DustPolicy::Credit => DustDecision { decision: "CREDITED", credited: credit },
+    }))
+}
+
+
+
+/// Records dust left on a deposit wallet and applies the token's dust policy.
+///
+/// Every decision is written to `dust_ledger`. Under `CREDIT` the part of the
+/// `confirmed` balance not credited yet goes to `user_balance` with a
+/// `deposit_receipt`, so the user sees the deposit even though nothing moved
+/// on-chain; dust still short of the confirmation depth is credited by a later
+/// decision. A balance unchanged since the last decision, with nothing more to
+/// credit, is not recorded again.
+pub async fn record_dust(
+    txn: &DatabaseTransaction,
+    user_id: Uuid,
+    wallet_id: Uuid,
+    wallet_address: &str,
+    token: &TokenEntry,
+    balance: Decimal,
+    confirmed: Decimal,
+) -> Result<(), AppError> {
+
+    let Some(DustDecision { decision, credited }) = dust_decision(txn, wallet_id, token, balance, confirmed).await? else {
+        return Ok(());
+    };
+
+    let token_address = token.address.to_string();
+
+    let id = Uuid::new_v4();
+    dust_ledger::ActiveModel {
+        id: Set(id),
+        user_id: Set(user_id),
+        wallet_id: Set(wallet_id),
+        wallet_address: Set(wallet_address.to_string()),
+        chain: Set(token.chain.clone()),
+        token: Set(token_address.clone()),
+        balance: Set(balance),
+        credited: Set(credited),
+        decision: Set(decision.to_string()),
+        swept_tx_hash: Set(None),
+        created_at: Set(chrono::Utc::now().into()),
+    }
+    .insert(txn)
+    .await
+    .map_err(AppError::DbError)?;
+
+    if !credited.is_zero() {
+        upsert_user_balance_and_receipt(
+                txn,
+                user_id,
+                wallet_address,
+                &token_address,
+                &token.chain,
+                credited,
+                &format!("dust:{id}"),
+            )
+            .await?;
+    }
+
+    println!("Dust {} {} on {} Wallet:{} {}", balance, token.symbol, token.chain, wallet_address, decision);
+
+    Ok(())
+}
+
+
+
+/// Marks the dust of a wallet and token as moved by sweep `tx_hash`.
+///
+/// Returns how much of it was already credited, which the sweep must not credit again.
+pub async fn settle_dust(
+    txn: &DatabaseTransaction,
+    wallet_id: Uuid,
+    chain: &str,
+    token: &str,
+    tx_hash: &str,
+) -> Result<Decimal, AppError> {
+
+    let open = open_dust(txn, wallet_id, chain, token).await?;
+
+    if open.is_empty() {
+        return Ok(Decimal::ZERO);
+    }
+
+    DustLedger::update_many()
+        .col_expr(dust_ledger::Column::SweptTxHash, Expr::value(tx_hash))
+        .filter(dust_ledger::Column::Id.is_in(open.iter().map(|row| row.id)))
+        .exec(txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    Ok(open.iter().map(|row| row.credited).sum())
+}
+
+
+
+/// Dust of a wallet and token already credited but not yet moved by a sweep.
+pub async fn credited_dust(
+    txn: &DatabaseTransaction,
+    wallet_id: Uuid,
+    chain: &str,
+    token: &str,
+) -> Result<Decimal, AppError> {
+    Ok(open_dust(txn, wallet_id, chain, token).await?.iter().map(|row| row.credited).sum())
+}
+
+
+
+/// Puts dust back on its wallet after the sweep `tx_hash` that moved it was reorged out.
+pub async fn reopen_dust(txn: &DatabaseTransaction, tx_hash: &str) -> Result<(), AppError> {
+
+    DustLedger::update_many()
+        .col_expr(dust_ledger::Column::SweptTxHash, Expr::value(Option::<String>::None))
+        .filter(dust_ledger::Column::SweptTxHash.eq(tx_hash))
+        .exec(txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    Ok(())
+}
+
+
+
+/// Dust decisions of a wallet and token not yet moved by a sweep, newest first.
+async fn open_dust(
+    txn: &DatabaseTransaction,
+    wallet_id: Uuid,
+    chain: &str,
+    token: &str,
+) -> Result<Vec<dust_ledger::Model>, AppError> {
+
+    DustLedger::find()
+        .filter(dust_ledger::Column::WalletId.eq(wallet_id))
+        .filter(dust_ledger::Column::Chain.eq(chain))
+        .filter(dust_ledger::Column::Token.eq(token))
+        .filter(dust_ledger::Column::SweptTxHash.is_null())
+        .order_by_desc(dust_ledger::Column::CreatedAt)
+        .all(txn)
+        .await
+        .map_err(AppError::DbError)
+}
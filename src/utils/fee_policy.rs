@@ -0,0 +1,168 @@
+use alloy::{
+    consensus::SignableTransaction, eips::BlockNumberOrTag, network::TransactionBuilder, primitives::{Address, U256, address}, providers::Provider,
+    rpc::types::TransactionRequest, sol,
+};
+use rust_decimal::Decimal;
+
+use crate::{
+    chain_config::registry::ChainEntry, error::error::AppError, utils::token_decimals::u256_to_decimal
+};
+
+/// Blocks of `eth_feeHistory` the priority fee is estimated from.
+const FEE_HISTORY_BLOCKS: u64 = 10;
+/// Percentile of each block's priority fees; the median keeps sweeps cheap but timely.
+const PRIORITY_FEE_PERCENTILE: f64 = 50.0;
+/// Base fee headroom: a max fee of twice the next base fee survives several full blocks.
+const BASE_FEE_MULTIPLIER: u128 = 2;
+
+/// OP-stack predeploy (Base, Optimism...) quoting the L1 data fee charged on top of L2 gas.
+const GAS_PRICE_ORACLE: Address = address!("0x420000000000000000000000000000000000000F");
+/// L1 data fee headroom, for an L1 base fee rising between the quote and inclusion.
+const L1_FEE_MULTIPLIER: u64 = 2;
+/// Nonce a transaction is quoted with before its own is allocated; encodes wider than any real one.
+const QUOTE_NONCE: u64 = u32::MAX as u64;
+
+sol! {
+    #[sol(rpc)]
+    interface IGasPriceOracle {
+        function getL1Fee(bytes memory _data) external view returns (uint256);
+    }
+}
+
+
+/// Fees to sign a sweep with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeQuote {
+    /// Max fee per gas (EIP-1559) or gas price (legacy).
+    pub max_fee_per_gas: u128,
+    /// `None` on chains without EIP-1559, where a legacy gas price is used.
+    pub max_priority_fee_per_gas: Option<u128>,
+}
+
+impl FeeQuote {
+    pub fn apply(&self, tx: &mut TransactionRequest) {
+        match self.max_priority_fee_per_gas {
+            Some(priority_fee) => {
+                tx.set_max_fee_per_gas(self.max_fee_per_gas);
+                tx.set_max_priority_fee_per_gas(priority_fee);
+            }
+            None => tx.set_gas_price(self.max_fee_per_gas),
+        }
+    }
+
+    /// Most a transaction of `gas_limit` can cost at these fees, in wei.
+    pub fn worst_case_fee(&self, gas_limit: u64) -> U256 {
+        U256::from(gas_limit) * U256::from(self.max_fee_per_gas)
+    }
+
+    /// Fees for a replacement of a transaction signed with `self`.
+    ///
+    /// Both fees rise by at least `rbf_bump_percent` (nodes reject smaller bumps)
+    /// and at least to `current`. Returns `None` when that would break the
+    /// chain's max fee cap.
+    pub fn bumped(&self, current: &FeeQuote, chain: &ChainEntry) -> Option<FeeQuote> {
+        let bump = |fee: u128| fee.saturating_mul(100 + chain.rbf_bump_percent as u128).div_ceil(100);
+
+        let max_fee_per_gas = bump(self.max_fee_per_gas).max(current.max_fee_per_gas);
+        let max_priority_fee_per_gas = self
+            .max_priority_fee_per_gas
+            .map(|fee| bump(fee).max(current.max_priority_fee_per_gas.unwrap_or_default()).min(max_fee_per_gas));
+
+        if chain.max_fee_per_gas.is_some_and(|cap| max_fee_per_gas > cap) {
+            return None;
+        }
+
+        Some(FeeQuote { max_fee_per_gas, max_priority_fee_per_gas })
+    }
+}
+
+
+/// Quotes fees for a sweep on `chain` under its fee policy.
+///
+/// On EIP-1559 chains the next block's base fee and the median priority fee of
+/// recent blocks come from `eth_feeHistory`; the max fee is twice the base fee
+/// plus the priority fee, clamped to `max_fee_per_gas`. Chains without fee
+/// history are priced with `eth_gasPrice`. Returns `None` while the current
+/// fees alone exceed the cap, i.e. the sweep should wait.
+pub async fn quote_fees<P: Provider>(provider: &P, chain: &ChainEntry) -> Result<Option<FeeQuote>, AppError> {
+
+    let history = provider
+        .get_fee_history(FEE_HISTORY_BLOCKS, BlockNumberOrTag::Latest, &[PRIORITY_FEE_PERCENTILE])
+        .await;
+
+    let quote = match history.ok().and_then(|history| Some((history.next_block_base_fee()?, history.reward?))) {
+        Some((base_fee, rewards)) if base_fee > 0 => {
+            let mut priority_fees: Vec<u128> = rewards.iter().filter_map(|block| block.first().copied()).collect();
+            priority_fees.sort_unstable();
+            let priority_fee = priority_fees.get(priority_fees.len() / 2).copied().unwrap_or_default();
+
+            if chain.max_fee_per_gas.is_some_and(|cap| base_fee + priority_fee > cap) {
+                return Ok(None);
+            }
+
+            let max_fee_per_gas = (base_fee * BASE_FEE_MULTIPLIER + priority_fee)
+                .min(chain.max_fee_per_gas.unwrap_or(u128::MAX));
+
+            FeeQuote { max_fee_per_gas, max_priority_fee_per_gas: Some(priority_fee) }
+        }
+        _ => {
+            let gas_price = provider.get_gas_price().await.map_err(|e|{
+                    eprintln!("Error Cannot get gas price on {}: {:?}", chain.name, e);
+                    AppError::InternalError(format!("Error Cannot get gas price : {e}"))
+            })?;
+
+            if chain.max_fee_per_gas.is_some_and(|cap| gas_price > cap) {
+                return Ok(None);
+            }
+
+            FeeQuote { max_fee_per_gas: gas_price, max_priority_fee_per_gas: None }
+        }
+    };
+
+    Ok(Some(quote))
+}
+
+
+
+/// Whether paying `fee` (in wei) to move `value` (in whole native coins) respects `max_fee_ratio`.
+///
+/// Always `true` when the chain has no ratio configured or `value` is unknown.
+pub fn fee_within_ratio(chain: &ChainEntry, fee: U256, value: Option<Decimal>) -> Result<bool, AppError> {
+    let (Some(ratio), Some(value)) = (chain.max_fee_ratio, value) else {
+        return Ok(true);
+    };
+
+    Ok(u256_to_decimal(fee, 18)? <= value * ratio)
+}
+
+
+
+/// L1 data fee, with headroom, an OP-stack chain charges for `tx` on top of its L2 gas.
+///
+/// The fee is paid from the sender's balance but never shows up in the gas
+/// limit, so a transfer of the whole balance minus the L2 fee is rejected for
+/// insufficient funds. Zero on chains without the `GasPriceOracle` predeploy.
+pub async fn l1_data_fee<P: Provider>(provider: &P, tx: &TransactionRequest, chain_id: u64) -> Result<U256, AppError> {
+
+    let oracle_code = provider.get_code_at(GAS_PRICE_ORACLE).await
+        .map_err(|e| AppError::InternalError(format!("Cannot check for an L1 fee oracle: {e}")))?;
+
+    if oracle_code.is_empty() {
+        return Ok(U256::ZERO);
+    }
+
+    let mut quoted = tx.clone();
+    quoted.set_chain_id(chain_id);
+    quoted.set_nonce(QUOTE_NONCE);
+
+    let unsigned = quoted.build_unsigned()
+        .map_err(|e| AppError::InternalError(format!("Cannot encode transaction for an L1 fee quote: {e}")))?;
+
+    let l1_fee = IGasPriceOracle::new(GAS_PRICE_ORACLE, provider)
+        .getL1Fee(unsigned.encoded_for_signing().into())
+        .call()
+        .await
+        .map_err(|e| AppError::InternalError(format!("Cannot quote L1 data fee: {e}")))?;
+
+    Ok(l1_fee.saturating_mul(U256::from(L1_FEE_MULTIPLIER)))
+}
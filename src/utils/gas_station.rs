@@ -0,0 +1,228 @@
+use std::time::Duration;
+
+use alloy::{
+    network::TransactionBuilder, primitives::{Address, TxHash, U256}, providers::Provider, rpc::types::TransactionRequest
+};
+use alloy_signer_local::PrivateKeySigner;
+use chrono::{TimeZone, Utc};
+use rust_decimal::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseTransaction, EntityTrait, QueryFilter, QuerySelect, TransactionTrait, sea_query::Expr
+};
+use uuid::Uuid;
+
+use crate::{
+    chain_config::{chain_config::connect_with_signer, registry::registry}, config::config::AppConfig, entities::{chains, gas_donation, prelude::{Chains, GasDonation}}, error::error::AppError, state_models::models::DbConnection, utils::{nonce_allocator::{allocate_nonce, release_nonce}, token_decimals::u256_to_decimal}
+};
+
+/// How long to wait for a top-up to be mined before giving up on this sweep attempt.
+const RECEIPT_TIMEOUT: Duration = Duration::from_secs(180);
+
+
+/// Tops up a deposit wallet with just enough native gas to pay for a sweep.
+///
+/// Sends `required_gas - current_gas` from the `GAS_FUNDER_PRIVATE_KEY` wallet
+/// and waits up to `RECEIPT_TIMEOUT` for the transfer to be mined. Donations are
+/// capped per chain per UTC day by `GAS_STATION_DAILY_CAPS`; each one is
+/// reserved in `gas_donation` before it is sent, so concurrent workers cannot
+/// overshoot the cap, and the reservation is dropped if the transfer fails.
+///
+/// # Returns
+/// - `Ok(Some(tx_hash))` when the wallet was funded and the transfer confirmed.
+/// - `Ok(None)` when the gas station is disabled, the wallet already has enough
+///   gas, or the donation would exceed the chain's daily cap.
+pub async fn fund_wallet_gas(
+    db: &DbConnection,
+    chain_name: &str,
+    user_id: Uuid,
+    wallet_address: Address,
+    current_gas: U256,
+    required_gas: U256,
+) -> Result<Option<TxHash>, AppError> {
+
+    let config = AppConfig::from_env()?;
+
+    let Some(funder_key) = config.gas_funder_private_key.as_deref() else {
+        println!("Gas station disabled, cannot fund Wallet:{} Chain:{}", wallet_address, chain_name);
+        return Ok(None);
+    };
+
+    if current_gas >= required_gas {
+        return Ok(None);
+    }
+
+    let top_up = required_gas - current_gas;
+    let top_up_decimal = u256_to_decimal(top_up, 18)?;
+
+    let daily_cap = config.gas_station_daily_caps.get(chain_name).copied().unwrap_or(Decimal::ZERO);
+
+    let Some(donation_id) = reserve_donation(db, chain_name, user_id, wallet_address, top_up_decimal, daily_cap).await? else {
+        return Ok(None);
+    };
+
+    let result = send_donation(db, chain_name, funder_key, wallet_address, top_up).await;
+
+    // Only a transfer that may still land keeps its share of the cap
+    if matches!(result, Err(_) | Ok(TopUp::Reverted(_))) {
+        cancel_donation(db, donation_id).await?;
+    }
+
+    match result? {
+        TopUp::Mined(tx_hash) => {
+            println!("Gas Top-up Hash : {} Amount:{} Chain:{} Wallet:{}", tx_hash, top_up_decimal, chain_name, wallet_address);
+            Ok(Some(tx_hash))
+        }
+        TopUp::Reverted(tx_hash) => Err(AppError::InternalError(format!("Gas top-up {} reverted", tx_hash))),
+    }
+}
+
+
+
+/// Outcome of a top-up transfer that reached the chain.
+enum TopUp {
+    Mined(TxHash),
+    Reverted(TxHash),
+}
+
+
+
+/// Signs and sends one top-up from the funder wallet and waits for its receipt.
+///
+/// A receipt timeout is an error like any other, although the transfer may
+/// still be mined later.
+async fn send_donation(
+    db: &DbConnection,
+    chain_name: &str,
+    funder_key: &str,
+    wallet_address: Address,
+    top_up: U256,
+) -> Result<TopUp, AppError> {
+
+    let funder: PrivateKeySigner = funder_key.trim().parse()
+        .map_err(|_| AppError::ConfigError("Invalid GAS_FUNDER_PRIVATE_KEY".into()))?;
+
+    let chain_id = registry()
+        .chain(chain_name)
+        .map(|chain| chain.chain_id)
+        .ok_or_else(|| AppError::InternalError(format!("Chain {} is not in the registry", chain_name)))?;
+
+    let funder_address = funder.address();
+    let provider = connect_with_signer(chain_name, funder).await?;
+
+    // Every worker on the chain funds from this one address, so its nonces come from the shared allocator
+    let nonce = allocate_nonce(&provider.0, db, chain_name, funder_address).await?;
+
+    // Signed for the registry's chain id, never the one the RPC reports
+    let tx = TransactionRequest::default()
+        .with_from(funder_address)
+        .with_to(wallet_address)
+        .with_value(top_up)
+        .with_nonce(nonce)
+        .with_chain_id(chain_id);
+
+    let pending = match provider.0.send_transaction(tx).await {
+        Ok(pending) => pending,
+        Err(e) => {
+            eprintln!("Error: Cannot send gas to {:?}: {:?}", wallet_address, e);
+            release_nonce(db, chain_name, funder_address, nonce).await?;
+            return Err(AppError::InternalError(format!("Provider error: {e}")));
+        }
+    };
+
+    let receipt = pending
+        .with_timeout(Some(RECEIPT_TIMEOUT))
+        .get_receipt()
+        .await
+        .map_err(|e|{
+            eprintln!("Error : Cannot get gas top-up receipt {:?}: {:?}", wallet_address, e);
+            AppError::InternalError(format!("Provider error: {e}"))
+    })?;
+
+    Ok(if receipt.status() { TopUp::Mined(receipt.transaction_hash) } else { TopUp::Reverted(receipt.transaction_hash) })
+}
+
+
+
+/// Records a donation of `amount` to `wallet_address` if it fits under the
+/// chain's daily cap, returning the new `gas_donation` id.
+///
+/// The chain's `chains` row is locked around the cap check and the insert, so
+/// workers funding wallets on the same chain take turns and each sees the
+/// donations reserved before it.
+async fn reserve_donation(
+    db: &DbConnection,
+    chain_name: &str,
+    user_id: Uuid,
+    wallet_address: Address,
+    amount: Decimal,
+    daily_cap: Decimal,
+) -> Result<Option<Uuid>, AppError> {
+
+    let txn = db.0.begin().await.map_err(AppError::DbError)?;
+
+    Chains::find()
+        .filter(chains::Column::Name.eq(chain_name))
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(AppError::DbError)?
+        .ok_or_else(|| AppError::InternalError(format!("Chain {} is not in the chains table", chain_name)))?;
+
+    let donated_today = donated_today(&txn, chain_name).await?;
+
+    if donated_today + amount > daily_cap {
+        eprintln!(
+            "Gas station daily cap reached Chain:{} Donated:{} Requested:{} Cap:{} Wallet:{}",
+            chain_name, donated_today, amount, daily_cap, wallet_address
+        );
+        return Ok(None);
+    }
+
+    let id = Uuid::new_v4();
+    gas_donation::ActiveModel {
+        id: Set(id),
+        user_id: Set(user_id),
+        wallet_address: Set(wallet_address.to_string()),
+        chain: Set(chain_name.to_string()),
+        gas: Set(amount),
+        created_at: Set(Utc::now().into()),
+    }
+    .insert(&txn)
+    .await
+    .map_err(AppError::DbError)?;
+
+    txn.commit().await.map_err(AppError::DbError)?;
+
+    Ok(Some(id))
+}
+
+
+
+/// Drops the reservation of a donation that never left the funder wallet.
+async fn cancel_donation(db: &DbConnection, donation_id: Uuid) -> Result<(), AppError> {
+    GasDonation::delete_by_id(donation_id)
+        .exec(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    Ok(())
+}
+
+
+
+/// Sums the gas donated on `chain_name` since midnight UTC, one row per donation.
+async fn donated_today(txn: &DatabaseTransaction, chain_name: &str) -> Result<Decimal, AppError> {
+    let start_of_day = Utc.from_utc_datetime(&Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap_or_default());
+
+    let total: Option<Option<Decimal>> = GasDonation::find()
+        .select_only()
+        .column_as(Expr::col(gas_donation::Column::Gas).sum(), "total")
+        .filter(gas_donation::Column::Chain.eq(chain_name))
+        .filter(gas_donation::Column::CreatedAt.gte(start_of_day))
+        .into_tuple()
+        .one(txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    Ok(total.flatten().unwrap_or(Decimal::ZERO))
+}
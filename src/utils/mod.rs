@@ -1,4 +1,15 @@
 
 pub mod update_deposit;
 pub mod token_decimals;
-pub mod free_wallet;
\ No newline at end of file
+pub mod wallet_lifecycle;
+pub mod gas_station;
+pub mod suspicious_activity;
+pub mod sweep_outbox;
+pub mod balance_scanner;
+pub mod token_metadata;
+pub mod fee_policy;
+pub mod dust_policy;
+pub mod nonce_allocator;
+pub mod tx_policy;
+pub mod treasury_router;
+pub mod update_withdrawal;
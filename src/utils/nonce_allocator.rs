@@ -0,0 +1,284 @@
+use std::{collections::HashSet, time::Duration};
+
+use alloy::{
+    eips::eip2718::Encodable2718, network::TransactionBuilder, primitives::{Address, U256}, providers::Provider, rpc::types::TransactionRequest
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseTransaction, EntityTrait, QueryFilter, QuerySelect, TransactionTrait, sea_query::{Expr, OnConflict}
+};
+use tracing::{info, warn};
+
+use crate::{
+    chain_config::registry::registry,
+    config::config::AppConfig,
+    entities::{account_nonce, nonce_filler, prelude::{AccountNonce, NonceFiller, SweepAttempt, WithdrawRequest}, sweep_attempt, withdraw_request},
+    error::error::AppError,
+    state_models::models::{DbConnection, SignerProvider},
+    utils::{sweep_outbox::{IN_FLIGHT_STATES, sign_for_chain}, update_withdrawal::SIGNED_WITHDRAWAL_STATES},
+};
+
+/// How long an address must go without allocations before nonces the node has
+/// not seen are treated as gaps rather than transactions still on their way.
+const GAP_GRACE: Duration = Duration::from_secs(120);
+
+
+
+/// Hands out the next nonce of `address` on `chain`.
+///
+/// The allocation is the larger of the stored `account_nonce.next_nonce` and
+/// the node's pending transaction count, taken under a row lock so concurrent
+/// workers and restarted processes never reuse a nonce and never start below
+/// what the chain has already seen.
+///
+/// When the stored counter is ahead of the node and the address has been idle
+/// for `GAP_GRACE`, the missing nonces were allocated but never reached the
+/// chain and block everything after them. Those without an outbox row (which
+/// the recovery pass re-broadcasts) are filled with zero-value transfers to the
+/// master wallet, the one native send a remote signer's policy allows. The gaps
+/// are only collected under the row lock; the fillers are signed and sent once
+/// it is released, and bumping `updated_at` keeps other allocations from
+/// filling the same gaps for another `GAP_GRACE`.
+pub async fn allocate_nonce(
+    provider: &SignerProvider,
+    db: &DbConnection,
+    chain: &str,
+    address: Address,
+) -> Result<u64, AppError> {
+
+    let pending = provider.get_transaction_count(address).pending().await
+        .map_err(|e| AppError::InternalError(format!("Cannot fetch nonce: {e}")))?;
+
+    let txn = db.0.begin().await.map_err(AppError::DbError)?;
+    let account = lock_account(&txn, chain, address, pending).await?;
+    let stored = account.next_nonce as u64;
+
+    let idle = chrono::Utc::now().signed_duration_since(account.updated_at)
+        .to_std()
+        .is_ok_and(|idle| idle >= GAP_GRACE);
+
+    let gaps = if stored > pending && idle {
+        unclaimed_gaps(&txn, chain, address, pending..stored).await?
+    } else {
+        Vec::new()
+    };
+
+    let nonce = stored.max(pending);
+
+    let mut active: account_nonce::ActiveModel = account.into();
+    active.next_nonce = Set(nonce as i64 + 1);
+    active.updated_at = Set(chrono::Utc::now().into());
+    active.update(&txn).await.map_err(AppError::DbError)?;
+
+    txn.commit().await.map_err(AppError::DbError)?;
+
+    // The nonce is already handed out, so a failed fill is only logged
+    if !gaps.is_empty()
+        && let Err(e) = fill_nonce_gaps(provider, db, chain, address, gaps).await
+    {
+        warn!("Cannot fill nonce gaps of {} on {}: {}", address, chain, e);
+    }
+
+    Ok(nonce)
+}
+
+
+
+/// Gives back `nonce` when the transaction it was allocated for was never sent.
+///
+/// Only the most recent allocation can be returned; an older one is left for
+/// gap filling, since later nonces may already be in use.
+pub async fn release_nonce(db: &DbConnection, chain: &str, address: Address, nonce: u64) -> Result<(), AppError> {
+
+    AccountNonce::update_many()
+        .col_expr(account_nonce::Column::NextNonce, Expr::value(nonce as i64))
+        .filter(account_nonce::Column::Chain.eq(chain))
+        .filter(account_nonce::Column::Address.eq(address.to_string()))
+        .filter(account_nonce::Column::NextNonce.eq(nonce as i64 + 1))
+        .exec(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    Ok(())
+}
+
+
+
+/// Locks the counter row of `address`, creating it at the node's `pending` count on first use.
+async fn lock_account(
+    txn: &DatabaseTransaction,
+    chain: &str,
+    address: Address,
+    pending: u64,
+) -> Result<account_nonce::Model, AppError> {
+
+    AccountNonce::insert(account_nonce::ActiveModel {
+            chain: Set(chain.to_string()),
+            address: Set(address.to_string()),
+            next_nonce: Set(pending as i64),
+            updated_at: Set(chrono::Utc::now().into()),
+        })
+        .on_conflict(
+            OnConflict::columns([account_nonce::Column::Chain, account_nonce::Column::Address])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    AccountNonce::find_by_id((chain.to_string(), address.to_string()))
+        .lock_exclusive()
+        .one(txn)
+        .await
+        .map_err(AppError::DbError)?
+        .ok_or_else(|| AppError::InternalError(format!("Nonce counter of {} on {} vanished", address, chain)))
+}
+
+
+
+/// The nonces in `gaps` that no sweep or withdrawal outbox row holds.
+async fn unclaimed_gaps(
+    txn: &DatabaseTransaction,
+    chain: &str,
+    address: Address,
+    gaps: std::ops::Range<u64>,
+) -> Result<Vec<u64>, AppError> {
+
+    let mut outboxed: HashSet<u64> = SweepAttempt::find()
+        .select_only()
+        .column(sweep_attempt::Column::Nonce)
+        .filter(sweep_attempt::Column::WalletAddress.eq(address.to_string()))
+        .filter(sweep_attempt::Column::Chain.eq(chain))
+        .filter(sweep_attempt::Column::State.is_in(IN_FLIGHT_STATES))
+        .into_tuple::<i64>()
+        .all(txn)
+        .await
+        .map_err(AppError::DbError)?
+        .into_iter()
+        .map(|nonce| nonce as u64)
+        .collect();
+
+    // The hot wallet's stored withdrawals are re-broadcast by the withdrawal worker
+    let withdrawals: Vec<Option<i64>> = WithdrawRequest::find()
+        .select_only()
+        .column(withdraw_request::Column::Nonce)
+        .filter(withdraw_request::Column::Sender.eq(address.to_string()))
+        .filter(withdraw_request::Column::Chain.eq(chain))
+        .filter(withdraw_request::Column::Status.is_in(SIGNED_WITHDRAWAL_STATES))
+        .into_tuple()
+        .all(txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    outboxed.extend(withdrawals.into_iter().flatten().map(|nonce| nonce as u64));
+
+    Ok(gaps.filter(|nonce| !outboxed.contains(nonce)).collect())
+}
+
+
+
+/// Fills each nonce in `gaps` with a zero-value transfer to the master wallet.
+///
+/// Every filler is written to the `nonce_filler` outbox before it is
+/// broadcast; a gap that already has one (its broadcast was lost, or the
+/// process died before sending it) gets the same transaction again rather
+/// than a second signature. A gap that cannot be filled is logged and left
+/// for the next allocation.
+async fn fill_nonce_gaps(
+    provider: &SignerProvider,
+    db: &DbConnection,
+    chain: &str,
+    address: Address,
+    gaps: Vec<u64>,
+) -> Result<(), AppError> {
+
+    let registry = registry();
+    let entry = registry
+        .chain(chain)
+        .ok_or_else(|| AppError::InternalError(format!("Chain {} is not in the registry", chain)))?;
+    let chain_id = entry.chain_id;
+
+    let master_wallet_address = AppConfig::from_env()?.master_wallet_address;
+
+    if !entry.is_treasury(master_wallet_address) {
+        warn!("Not filling nonce gaps of {} on {}: the master wallet is not on the treasury allow-list", address, chain);
+        return Ok(());
+    }
+
+    for nonce in gaps {
+        let filler = match outboxed_filler(db, chain, address, nonce).await? {
+            Some(filler) => filler,
+            None => {
+                warn!("Nonce gap at {} for {} on {}, filling it with an empty transfer", nonce, address, chain);
+
+                let tx = TransactionRequest::default()
+                    .with_from(address)
+                    .with_to(master_wallet_address)
+                    .with_value(U256::ZERO)
+                    .with_nonce(nonce)
+                    .with_chain_id(chain_id);
+
+                match sign_for_chain(provider, tx, chain_id).await {
+                    Ok(envelope) => record_filler(db, chain, address, nonce, &envelope).await?,
+                    Err(e) => {
+                        warn!("Cannot sign filler for nonce {} of {} on {}: {}", nonce, address, chain, e);
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let raw_tx = hex::decode(&filler.raw_tx)
+            .map_err(|e| AppError::InternalError(format!("Stored filler {} is not hex: {e}", filler.tx_hash)))?;
+
+        match provider.send_raw_transaction(&raw_tx).await {
+            Ok(_) => info!("Filled nonce {} of {} on {} with {}", nonce, address, chain, filler.tx_hash),
+            Err(e) => warn!("Cannot fill nonce {} of {} on {}: {}", nonce, address, chain, e),
+        }
+    }
+
+    Ok(())
+}
+
+
+
+async fn outboxed_filler(db: &DbConnection, chain: &str, address: Address, nonce: u64) -> Result<Option<nonce_filler::Model>, AppError> {
+    NonceFiller::find_by_id((chain.to_string(), address.to_string(), nonce as i64))
+        .one(&db.0)
+        .await
+        .map_err(AppError::DbError)
+}
+
+
+
+/// Writes a signed filler to the outbox, keeping the one already there if
+/// another worker recorded the same nonce first.
+async fn record_filler(
+    db: &DbConnection,
+    chain: &str,
+    address: Address,
+    nonce: u64,
+    envelope: &alloy::consensus::TxEnvelope,
+) -> Result<nonce_filler::Model, AppError> {
+
+    NonceFiller::insert(nonce_filler::ActiveModel {
+            chain: Set(chain.to_string()),
+            address: Set(address.to_string()),
+            nonce: Set(nonce as i64),
+            raw_tx: Set(hex::encode(envelope.encoded_2718())),
+            tx_hash: Set(envelope.tx_hash().to_string()),
+            created_at: Set(chrono::Utc::now().into()),
+        })
+        .on_conflict(
+            OnConflict::columns([nonce_filler::Column::Chain, nonce_filler::Column::Address, nonce_filler::Column::Nonce])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    outboxed_filler(db, chain, address, nonce)
+        .await?
+        .ok_or_else(|| AppError::InternalError(format!("Filler for nonce {} of {} on {} vanished", nonce, address, chain)))
+}
@@ -0,0 +1,29 @@
+use sea_orm::{ActiveModelTrait, ActiveValue::{NotSet, Set}, ConnectionTrait};
+use serde_json::Value;
+
+use crate::{entities::suspicious_activities, error::error::AppError};
+
+/// Raises an unresolved alert in `suspicious_activities` for an operator to review.
+pub async fn record_suspicious_activity<C: ConnectionTrait>(
+    conn: &C,
+    user_id: &str,
+    activity_type: &str,
+    severity: &str,
+    details: Value,
+) -> Result<(), AppError> {
+    suspicious_activities::ActiveModel {
+        id: NotSet,
+        user_id: Set(user_id.to_string()),
+        activity_type: Set(activity_type.to_string()),
+        severity: Set(severity.to_string()),
+        details: Set(details),
+        resolved: Set(false),
+        created_at: Set(chrono::Utc::now().into()),
+        resolved_at: Set(None),
+    }
+    .insert(conn)
+    .await
+    .map_err(AppError::DbError)?;
+
+    Ok(())
+}
@@ -0,0 +1,347 @@
+use std::str::FromStr;
+
+use alloy::{
+    eips::eip2718::{Decodable2718, Encodable2718}, network::{Ethereum, TransactionBuilder}, primitives::Address, providers::{PendingTransactionBuilder, Provider}, rpc::types::TransactionRequest, consensus::{Transaction, TxEnvelope}
+};
+use rust_decimal::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter, sea_query::Expr
+};
+use serde_json::json;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    chain_config::{chain_config::create_provider, key_deriver::WalletKey, registry::{ChainEntry, registry}}, entities::{prelude::{SweepAttempt, UserWallet}, sweep_attempt}, error::error::AppError, state_models::models::{DbConnection, SignerProvider},
+    utils::{fee_policy::{FeeQuote, l1_data_fee, quote_fees}, nonce_allocator::{allocate_nonce, release_nonce}, suspicious_activity::record_suspicious_activity, token_decimals::u256_to_decimal, tx_policy::check_sweep},
+};
+
+/// Attempt states that may still change on-chain and need reconciling.
+///
+/// `REPLACED` attempts were superseded by a fee-bumped transaction with the same
+/// nonce, but can still be the one that gets mined.
+pub const IN_FLIGHT_STATES: [&str; 3] = ["SIGNED", "BROADCAST", "REPLACED"];
+
+
+/// What a sweep transaction moves, recorded alongside the signed transaction.
+pub struct SweepIntent<'a> {
+    pub user_id: Uuid,
+    pub wallet_id: Uuid,
+    pub wallet_address: Address,
+    pub chain: &'a str,
+    /// Token contract, or the zero address for the native asset.
+    pub token: Address,
+    pub amount: Decimal,
+}
+
+
+/// Signs `tx`, writes it to the `sweep_attempt` outbox and only then broadcasts it.
+///
+/// Unless `tx` already carries one, the nonce comes from the persistent
+/// allocator and is given back if signing fails.
+///
+/// The transaction is signed for the registry's chain id of `intent.chain`
+/// rather than whatever the RPC reports, so a mis-routed endpoint can at worst
+/// reject it, never replay it on another network.
+///
+/// The outbox row is committed on its own connection before the transaction
+/// leaves the process, so if we crash at any later point the startup recovery
+/// pass still knows the exact raw transaction, nonce and hash to reconcile.
+pub async fn sign_and_broadcast(
+    provider: &SignerProvider,
+    db: &DbConnection,
+    intent: &SweepIntent<'_>,
+    tx: TransactionRequest,
+) -> Result<(sweep_attempt::Model, PendingTransactionBuilder<Ethereum>), AppError> {
+
+    let registry = registry();
+    let chain = registry
+        .chain(intent.chain)
+        .ok_or_else(|| AppError::InternalError(format!("Chain {} is not in the registry", intent.chain)))?;
+    let chain_id = chain.chain_id;
+
+    let mut tx = tx.with_chain_id(chain_id);
+
+    if let Err(violation) = check_sweep(chain, registry.tokens(intent.chain), intent, &tx) {
+        refuse_sweep(db, intent, &tx, &violation).await?;
+        return Err(AppError::Forbidden(format!("Sweep refused by the treasury policy: {violation}")));
+    }
+
+    // Replacements keep the nonce of the transaction they replace
+    let allocated = match tx.nonce {
+        Some(_) => None,
+        None => {
+            let nonce = allocate_nonce(provider, db, intent.chain, intent.wallet_address).await?;
+            tx.set_nonce(nonce);
+            Some(nonce)
+        }
+    };
+
+    let envelope = match sign_for_chain(provider, tx, chain_id).await {
+        Ok(envelope) => envelope,
+        Err(e) => {
+            if let Some(nonce) = allocated {
+                release_nonce(db, intent.chain, intent.wallet_address, nonce).await?;
+            }
+            return Err(e);
+        }
+    };
+
+    let raw_tx = envelope.encoded_2718();
+
+    let attempt = sweep_attempt::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(intent.user_id),
+        wallet_id: Set(intent.wallet_id),
+        wallet_address: Set(intent.wallet_address.to_string()),
+        chain: Set(intent.chain.to_string()),
+        token: Set(intent.token.to_string()),
+        amount: Set(intent.amount),
+        nonce: Set(envelope.nonce() as i64),
+        raw_tx: Set(hex::encode(&raw_tx)),
+        tx_hash: Set(envelope.tx_hash().to_string()),
+        state: Set("SIGNED".to_string()),
+        updated_at: Set(chrono::Utc::now().into()),
+        created_at: Set(chrono::Utc::now().into()),
+    }
+    .insert(&db.0)
+    .await
+    .map_err(AppError::DbError)?;
+
+    let pending = provider.send_raw_transaction(&raw_tx).await.map_err(|e|{
+            eprintln!("Error: Cannot broadcast sweep {}: {:?}", attempt.tx_hash, e);
+            AppError::InternalError(format!("Provider error: {e}"))
+    })?;
+
+    set_attempt_state(&db.0, attempt.id, "BROADCAST").await?;
+
+    Ok((attempt, pending))
+}
+
+
+
+/// Records a sweep the treasury policy refused; nothing was signed.
+async fn refuse_sweep(db: &DbConnection, intent: &SweepIntent<'_>, tx: &TransactionRequest, violation: &str) -> Result<(), AppError> {
+
+    error!("Refused sweep from {} on {}: {}", intent.wallet_address, intent.chain, violation);
+
+    record_suspicious_activity(
+            &db.0,
+            &intent.user_id.to_string(),
+            "SWEEP_POLICY_VIOLATION",
+            "HIGH",
+            json!({
+                "chain": intent.chain,
+                "wallet_id": intent.wallet_id,
+                "wallet_address": intent.wallet_address.to_string(),
+                "token": intent.token.to_string(),
+                "amount": intent.amount.to_string(),
+                "violation": violation,
+                "to": tx.to.and_then(|to| to.to().copied()).map(|to| to.to_string()),
+                "value": tx.value.map(|value| value.to_string()),
+                "input": tx.input.input().map(hex::encode),
+            }),
+        )
+        .await
+}
+
+
+
+/// Fills and signs `tx`, refusing an envelope signed for any chain but `chain_id`.
+pub async fn sign_for_chain(provider: &SignerProvider, tx: TransactionRequest, chain_id: u64) -> Result<TxEnvelope, AppError> {
+
+    let envelope = provider.fill(tx).await
+        .map_err(|e| AppError::InternalError(format!("Cannot fill transaction: {e}")))?
+        .try_into_envelope()
+        .map_err(|e| AppError::InternalError(format!("Cannot sign transaction: {e}")))?;
+
+    if envelope.chain_id() != Some(chain_id) {
+        return Err(AppError::InternalError(format!(
+            "Refusing to broadcast transaction signed for chain {:?} instead of {}", envelope.chain_id(), chain_id
+        )));
+    }
+
+    Ok(envelope)
+}
+
+
+
+/// Moves an attempt to `state`. Pass the sweep's database transaction when the
+/// change must commit atomically with the deposit it produced.
+pub async fn set_attempt_state<C: ConnectionTrait>(conn: &C, attempt_id: Uuid, state: &str) -> Result<(), AppError> {
+    SweepAttempt::update_many()
+        .col_expr(sweep_attempt::Column::State, Expr::value(state))
+        .col_expr(sweep_attempt::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+        .filter(sweep_attempt::Column::Id.eq(attempt_id))
+        .exec(conn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    Ok(())
+}
+
+
+
+/// Whether a sweep of `token` from this wallet is already in flight on `chain`.
+///
+/// The sweeper skips such tokens instead of signing a second transfer of a
+/// balance that an unconfirmed transaction is about to move.
+pub async fn has_in_flight_attempt(
+    db: &DbConnection,
+    wallet_id: Uuid,
+    chain: &str,
+    token: Address,
+) -> Result<bool, AppError> {
+    let count = SweepAttempt::find()
+        .filter(sweep_attempt::Column::WalletId.eq(wallet_id))
+        .filter(sweep_attempt::Column::Chain.eq(chain))
+        .filter(sweep_attempt::Column::Token.eq(token.to_string()))
+        .filter(sweep_attempt::Column::State.is_in(IN_FLIGHT_STATES))
+        .count(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    Ok(count > 0)
+}
+
+
+
+/// Whether a transaction signed (or last replaced) at `signed_at` has waited
+/// longer than the chain's `rbf_after` to be mined.
+pub fn is_stuck(chain: &ChainEntry, signed_at: sea_orm::prelude::DateTimeWithTimeZone) -> bool {
+    let age = chrono::Utc::now().signed_duration_since(signed_at);
+    age.to_std().is_ok_and(|age| age >= chain.rbf_after)
+}
+
+
+
+/// Sends a stored signed transaction, sweep or withdrawal, whose nonce the
+/// chain has not used yet, so a node that lost it picks it up again.
+///
+/// Returns whether the node now holds it. An "already known" rejection counts:
+/// the node still has it in its mempool. Any other rejection is only logged,
+/// since the stored transaction stays the one to reconcile.
+pub async fn rebroadcast<P: Provider>(provider: &P, raw_tx: &[u8], tx_hash: &str, chain: &str) -> bool {
+    match provider.send_raw_transaction(raw_tx).await {
+        Ok(_) => true,
+        Err(e) => {
+            let message = e.to_string().to_lowercase();
+            let known = message.contains("already known") || message.contains("known transaction");
+
+            if !known {
+                warn!("Re-broadcast of {} on {}: {}", tx_hash, chain, e);
+            }
+            known
+        }
+    }
+}
+
+
+
+/// Replaces a sweep that has been pending too long with a fee-bumped copy.
+///
+/// The replacement reuses the nonce, recipient and calldata of the stuck
+/// transaction with fees raised by the chain's `rbf_bump_percent` (or to the
+/// current quote, if higher). A native sweep is re-priced the way
+/// `sweep_native_balance` prices it: the wallet's balance minus the bumped
+/// worst-case fee and the L1 data fee. The replacement gets its own outbox row and the original becomes
+/// `REPLACED`; whichever of the two is mined is recorded by the recovery pass.
+///
+/// Returns `false` without sending anything when the bump would break the fee
+/// cap or the wallet cannot pay the higher fee.
+pub async fn replace_stuck_attempt(db: &DbConnection, attempt: &sweep_attempt::Model) -> Result<bool, AppError> {
+
+    let registry = registry();
+    let chain = registry
+        .chain(&attempt.chain)
+        .ok_or_else(|| AppError::InternalError(format!("Chain {} is not in the registry", attempt.chain)))?;
+
+    let raw_tx = hex::decode(&attempt.raw_tx)
+        .map_err(|e| AppError::InternalError(format!("Invalid raw tx for {}: {e}", attempt.tx_hash)))?;
+    let stuck = TxEnvelope::decode_2718(&mut raw_tx.as_slice())
+        .map_err(|e| AppError::InternalError(format!("Cannot decode raw tx {}: {e}", attempt.tx_hash)))?;
+
+    let wallet_address = parse_address(&attempt.wallet_address)?;
+    let token = parse_address(&attempt.token)?;
+    let wallet = UserWallet::find_by_id(attempt.wallet_id)
+        .one(&db.0)
+        .await
+        .map_err(AppError::DbError)?
+        .ok_or_else(|| AppError::InternalError(format!("Wallet {} of attempt {} not found", attempt.wallet_id, attempt.tx_hash)))?;
+
+    // A wallet rotated to a new key no longer signs for the attempt's address
+    if parse_address(&wallet.wallet_address)? != wallet_address {
+        return Ok(false);
+    }
+
+    let provider = create_provider(&attempt.chain, &WalletKey::from(&wallet)).await?;
+
+    let Some(current) = quote_fees(&provider.0, chain).await? else {
+        return Ok(false);
+    };
+
+    let signed = FeeQuote {
+        max_fee_per_gas: stuck.max_fee_per_gas(),
+        max_priority_fee_per_gas: stuck.max_priority_fee_per_gas(),
+    };
+
+    let Some(bumped) = signed.bumped(&current, chain) else {
+        println!("Cannot bump sweep {} on {} within the fee cap", attempt.tx_hash, attempt.chain);
+        return Ok(false);
+    };
+
+    let gas_limit = stuck.gas_limit();
+    let mut tx = TransactionRequest::from_transaction_with_sender(stuck.clone(), wallet_address);
+    bumped.apply(&mut tx);
+
+    let mut amount = attempt.amount;
+
+    // The stuck sweep is unmined, so the balance still holds everything it meant to move
+    let native_balance = provider.0.get_balance(wallet_address).await
+        .map_err(|e| AppError::InternalError(format!("Cannot fetch native balance: {e}")))?;
+
+    if token.is_zero() {
+        let l1_fee = l1_data_fee(&provider.0, &tx.clone().with_value(native_balance), chain.chain_id).await?;
+        let transfer_fee = bumped.worst_case_fee(gas_limit) + l1_fee;
+
+        let Some(value) = native_balance.checked_sub(transfer_fee).filter(|value| !value.is_zero()) else {
+            println!("Wallet {} cannot pay the bumped fee of sweep {}", attempt.wallet_address, attempt.tx_hash);
+            return Ok(false);
+        };
+        tx.set_value(value);
+        amount = u256_to_decimal(value, 18)?;
+    } else {
+        let l1_fee = l1_data_fee(&provider.0, &tx, chain.chain_id).await?;
+
+        if native_balance < bumped.worst_case_fee(gas_limit) + l1_fee {
+            println!("Wallet {} cannot pay the bumped fee of sweep {}", attempt.wallet_address, attempt.tx_hash);
+            return Ok(false);
+        }
+    }
+
+    let intent = SweepIntent {
+        user_id: attempt.user_id,
+        wallet_id: attempt.wallet_id,
+        wallet_address,
+        chain: &attempt.chain,
+        token,
+        amount,
+    };
+
+    let (replacement, _) = sign_and_broadcast(&provider.0, db, &intent, tx).await?;
+    set_attempt_state(&db.0, attempt.id, "REPLACED").await?;
+
+    info!(
+        "Replaced stuck sweep {} on {} with {} (max fee {} -> {})",
+        attempt.tx_hash, attempt.chain, replacement.tx_hash, signed.max_fee_per_gas, bumped.max_fee_per_gas
+    );
+
+    Ok(true)
+}
+
+
+
+pub fn parse_address(address: &str) -> Result<Address, AppError> {
+    Address::from_str(address)
+        .map_err(|e| AppError::InternalError(format!("Invalid address {address}: {e}")))
+}
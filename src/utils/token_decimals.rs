@@ -1,4 +1,7 @@
-use alloy::{primitives::Address, providers::Provider, sol};
+use std::str::FromStr;
+
+use alloy::{primitives::{Address, U256}, providers::Provider, sol, sol_types::SolCall};
+use rust_decimal::{Decimal, prelude::FromPrimitive};
 
 use crate::error::error::AppError;
 
@@ -8,6 +11,23 @@ sol!(
     "src/utils/abi/ERC20.json"
 );
 
+
+/// Recipient and amount of `input` if it is exactly an ERC-20 `transfer` call.
+///
+/// Both the treasury allow-list and the signer policy read sweeps through this,
+/// so they cannot disagree on what counts as a transfer.
+pub fn decode_transfer(input: &[u8]) -> Option<(Address, U256)> {
+    // Decoding tolerates trailing bytes; a transfer is exactly selector + two words
+    if input.len() != 4 + 64 {
+        return None;
+    }
+
+    let transfer = ERC20::transferCall::abi_decode_validate(input).ok()?;
+
+    Some((transfer.to, transfer.amount))
+}
+
+
 pub async fn get_token_decimals<P: Provider>(
     provider: &P,
     token: Address,
@@ -29,4 +49,38 @@ pub async fn get_token_decimals<P: Provider>(
     }
 
     Ok(decimals)
-}
\ No newline at end of file
+}
+
+
+/// Converts a raw on-chain amount into a `Decimal` scaled by the token's decimals.
+pub fn u256_to_decimal(amount: U256, decimals: u8) -> Result<Decimal, AppError> {
+    let base = Decimal::from_i128(10_i128.pow(decimals as u32))
+        .ok_or_else(|| AppError::InternalError("Decimal overflow".into()))?;
+
+    let value = Decimal::from_str(&amount.to_string())
+        .map_err(|e| AppError::InternalError(format!("Decimal parse error: {e}")))?;
+
+    Ok(value / base)
+}
+
+
+
+/// Converts a whole-token `Decimal` into the raw on-chain amount, the inverse of [`u256_to_decimal`].
+///
+/// Fails on negative amounts and on precision finer than the token's `decimals`.
+pub fn decimal_to_u256(amount: Decimal, decimals: u8) -> Result<U256, AppError> {
+    if amount.is_sign_negative() {
+        return Err(AppError::BadRequest(format!("Negative amount {amount}")));
+    }
+
+    let amount = amount.normalize();
+    if amount.scale() > decimals as u32 {
+        return Err(AppError::BadRequest(format!("Amount {amount} has more than {decimals} decimals")));
+    }
+
+    let raw = U256::from(amount.mantissa() as u128);
+    let scale = U256::from(10u64).pow(U256::from(decimals as u32 - amount.scale()));
+
+    raw.checked_mul(scale)
+        .ok_or_else(|| AppError::BadRequest(format!("Amount {amount} overflows 256 bits")))
+}
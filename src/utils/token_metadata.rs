@@ -0,0 +1,216 @@
+use std::{collections::HashMap, sync::RwLock, time::{Duration, Instant}};
+
+use alloy::{primitives::{Address, keccak256}, providers::Provider};
+use once_cell::sync::Lazy;
+use sea_orm::{ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter, TransactionTrait, sea_query::{Expr, OnConflict}};
+use serde_json::json;
+use tracing::{error, info, warn};
+
+use crate::{
+    chain_config::{chain_config::create_read_provider, registry::{TokenEntry, registry}},
+    entities::{prelude::{TokenMetadata, Tokens}, token_metadata, tokens},
+    error::error::AppError,
+    state_models::models::DbConnection,
+    utils::{suspicious_activity::record_suspicious_activity, token_decimals::get_token_decimals},
+};
+
+/// How long verified decimals are trusted before the contract's code is checked again.
+const REFRESH_AFTER: Duration = Duration::from_secs(3600);
+
+/// Verified decimals and when the token's code hash was last checked.
+type Verified = (u8, Instant);
+
+/// Decimals already verified in this process, keyed by chain and token address.
+static VERIFIED: Lazy<RwLock<HashMap<(String, Address), Verified>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+
+/// Decimals of `token`, verified against the contract and cached.
+///
+/// Looks in the process cache, then in `token_metadata`, and only on a miss
+/// calls `decimals()` and `eth_getCode` on chain, storing the result for every
+/// later sweep. Whatever the source, the decimals must equal the registry's:
+/// a mismatch fails with `ConfigError` rather than crediting amounts off by
+/// orders of magnitude.
+///
+/// Stored metadata is refreshed on first use in the process and every
+/// `REFRESH_AFTER`: if the contract's code no longer hashes to the stored
+/// `code_hash`, the token is disabled and a `suspicious_activities` alert raised.
+pub async fn verified_decimals<P: Provider>(
+    provider: &P,
+    db: &DbConnection,
+    token: &TokenEntry,
+) -> Result<u8, AppError> {
+
+    let key = (token.chain.clone(), token.address);
+
+    let cached = VERIFIED
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(&key)
+        .copied()
+        .filter(|(_, checked_at)| checked_at.elapsed() < REFRESH_AFTER);
+
+    if let Some((decimals, _)) = cached {
+        return check_registry_decimals(token, decimals);
+    }
+
+    let stored = TokenMetadata::find_by_id((token.chain.clone(), token.address.to_string()))
+        .one(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    let decimals = match stored {
+        Some(metadata) => {
+            check_code_hash(provider, db, token, &metadata).await?;
+            u8::try_from(metadata.decimals)
+                .map_err(|_| AppError::InternalError(format!("Stored decimals {} of {} are invalid", metadata.decimals, token.symbol)))?
+        }
+        None => fetch_and_store(provider, db, token).await?,
+    };
+
+    check_registry_decimals(token, decimals)?;
+
+    VERIFIED
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(key, (decimals, Instant::now()));
+
+    Ok(decimals)
+}
+
+
+
+/// Compares the token contract's current code with the `code_hash` stored when
+/// it was first verified.
+///
+/// Changed code means the stored decimals (and everything else the registry
+/// assumes about the token) can no longer be trusted: the token is disabled in
+/// `tokens`, which takes it out of the registry on the next reload, and an
+/// alert is raised for an operator.
+async fn check_code_hash<P: Provider>(
+    provider: &P,
+    db: &DbConnection,
+    token: &TokenEntry,
+    metadata: &token_metadata::Model,
+) -> Result<(), AppError> {
+
+    let code = provider.get_code_at(token.address).await
+        .map_err(|e| AppError::InternalError(format!("Code check failed: {}", e)))?;
+
+    let code_hash = keccak256(&code).to_string();
+
+    if code_hash == metadata.code_hash {
+        return Ok(());
+    }
+
+    let txn = db.0.begin().await.map_err(AppError::DbError)?;
+
+    Tokens::update_many()
+        .col_expr(tokens::Column::Enabled, Expr::value(false))
+        .filter(tokens::Column::Chain.eq(token.chain.as_str()))
+        // The registry accepts any address casing in `tokens`
+        .filter(tokens::Column::Address.is_in([token.address.to_string(), token.address.to_string().to_lowercase()]))
+        .exec(&txn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    record_suspicious_activity(
+            &txn,
+            "system",
+            "TOKEN_CODE_CHANGED",
+            "HIGH",
+            json!({
+                "chain": token.chain,
+                "token": token.address.to_string(),
+                "symbol": token.symbol,
+                "stored_code_hash": metadata.code_hash,
+                "code_hash": code_hash,
+            }),
+        )
+        .await?;
+
+    txn.commit().await.map_err(AppError::DbError)?;
+
+    error!("Code of {} ({}) on {} changed since it was verified; token disabled", token.symbol, token.address, token.chain);
+
+    Err(AppError::InternalError(format!(
+        "{} ({}) on {} has changed code; refusing to sweep it",
+        token.symbol, token.address, token.chain
+    )))
+}
+
+
+
+async fn fetch_and_store<P: Provider>(provider: &P, db: &DbConnection, token: &TokenEntry) -> Result<u8, AppError> {
+
+    let decimals = get_token_decimals(provider, token.address).await?;
+
+    let code = provider.get_code_at(token.address).await
+        .map_err(|e| AppError::InternalError(format!("Code check failed: {}", e)))?;
+
+    let metadata = token_metadata::ActiveModel {
+        chain: Set(token.chain.clone()),
+        address: Set(token.address.to_string()),
+        decimals: Set(decimals as i16),
+        code_hash: Set(keccak256(&code).to_string()),
+        verified_at: Set(chrono::Utc::now().into()),
+    };
+
+    TokenMetadata::insert(metadata)
+        .on_conflict(
+            OnConflict::columns([token_metadata::Column::Chain, token_metadata::Column::Address])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&db.0)
+        .await
+        .map_err(AppError::DbError)?;
+
+    info!("Verified {} on {}: {} decimals", token.symbol, token.chain, decimals);
+
+    Ok(decimals)
+}
+
+
+
+fn check_registry_decimals(token: &TokenEntry, decimals: u8) -> Result<u8, AppError> {
+    if decimals != token.decimals {
+        return Err(AppError::ConfigError(format!(
+            "{} ({}) on {} has {} decimals on chain but {} in the registry; refusing to sweep it",
+            token.symbol, token.address, token.chain, decimals, token.decimals
+        )));
+    }
+
+    Ok(decimals)
+}
+
+
+
+/// Verifies the metadata of every registry token at startup.
+///
+/// A decimals mismatch aborts startup. Chains whose RPC is unreachable are only
+/// logged; their tokens are verified on first use instead.
+pub async fn warm_token_metadata(db: &DbConnection) -> Result<(), AppError> {
+
+    let registry = registry();
+
+    for chain in registry.chains() {
+        let provider = match create_read_provider(&chain.name).await {
+            Ok(provider) => provider,
+            Err(e) => {
+                warn!("Skipping token metadata warm-up on {}: {}", chain.name, e);
+                continue;
+            }
+        };
+
+        for token in registry.tokens(&chain.name) {
+            match verified_decimals(&provider, db, token).await {
+                Ok(_) => {}
+                Err(e @ AppError::ConfigError(_)) => return Err(e),
+                Err(e) => warn!("Cannot verify {} on {} yet: {}", token.symbol, chain.name, e),
+            }
+        }
+    }
+
+    Ok(())
+}
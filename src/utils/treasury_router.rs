@@ -0,0 +1,116 @@
+use std::{cmp::Reverse, str::FromStr};
+
+use alloy::{
+    primitives::{Address, U256}, providers::Provider
+};
+use rust_decimal::Decimal;
+use sea_orm::{ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter};
+use tracing::info;
+
+use crate::{
+    chain_config::registry::ChainEntry,
+    entities::{admin_limits, prelude::AdminLimits},
+    error::error::AppError,
+    utils::token_decimals::{ERC20, u256_to_decimal},
+};
+
+
+/// Treasury limits of one asset on one chain, from `admin_limits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreasuryLimits {
+    /// The hot wallet takes sweeps until it holds this much of the asset.
+    pub vault_limit: Decimal,
+    /// Below this the hot wallet cannot cover withdrawals and needs a top-up from cold storage.
+    pub hot_floor: Option<Decimal>,
+}
+
+
+/// Where a sweep of one asset goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Destination {
+    pub address: Address,
+    pub cold: bool,
+}
+
+
+/// Picks the treasury wallet for a sweep of `token` (the zero address for the native coin).
+///
+/// Sweeps go to the hot wallet until its on-chain balance of the asset reaches
+/// the asset's `vault_limit`, then to the chain's cold wallet. A chain without
+/// a cold wallet, or an asset without a limit, always sweeps hot.
+///
+/// The balance is read per sweep, so concurrent sweeps on one chain may each
+/// see the hot wallet just under its limit and carry it past by a few sweeps.
+pub async fn sweep_destination<P: Provider, C: ConnectionTrait>(
+    provider: &P,
+    conn: &C,
+    chain: &ChainEntry,
+    hot: Address,
+    token: Address,
+    decimals: u8,
+) -> Result<Destination, AppError> {
+
+    let hot_destination = Destination { address: hot, cold: false };
+
+    let Some(cold) = chain.cold_wallet_address else {
+        return Ok(hot_destination);
+    };
+
+    let Some(limits) = treasury_limits(conn, &chain.name, token).await? else {
+        return Ok(hot_destination);
+    };
+
+    let balance = u256_to_decimal(asset_balance(provider, token, hot).await?, decimals)?;
+
+    if balance < limits.vault_limit {
+        return Ok(hot_destination);
+    }
+
+    info!("Hot wallet holds {} of {} on {} (vault limit {}), sweeping to cold wallet {}", balance, token, chain.name, limits.vault_limit, cold);
+
+    Ok(Destination { address: cold, cold: true })
+}
+
+
+
+/// The `admin_limits` row governing `token` on `chain`.
+///
+/// Rows naming both the chain and the token come first, then chain-only,
+/// token-only and unscoped rows. Equally specific rows resolve to the lowest
+/// `vault_limit`, so a duplicate can only send more to cold storage.
+pub async fn treasury_limits<C: ConnectionTrait>(conn: &C, chain: &str, token: Address) -> Result<Option<TreasuryLimits>, AppError> {
+
+    let rows = AdminLimits::find()
+        .filter(
+            Condition::any()
+                .add(admin_limits::Column::Chain.is_null())
+                .add(admin_limits::Column::Chain.eq(chain)),
+        )
+        .all(conn)
+        .await
+        .map_err(AppError::DbError)?;
+
+    let best = rows
+        .into_iter()
+        .filter(|row| match row.token.as_deref() {
+            None => true,
+            Some(scoped) => Address::from_str(scoped.trim()).is_ok_and(|scoped| scoped == token),
+        })
+        .min_by_key(|row| (Reverse(2 * row.chain.is_some() as u8 + row.token.is_some() as u8), row.vault_limit));
+
+    Ok(best.map(|row| TreasuryLimits { vault_limit: row.vault_limit, hot_floor: row.hot_floor }))
+}
+
+
+
+/// Balance of `owner` in `token`, or in the native coin for the zero address.
+pub async fn asset_balance<P: Provider>(provider: &P, token: Address, owner: Address) -> Result<U256, AppError> {
+
+    if token.is_zero() {
+        return provider.get_balance(owner).await
+            .map_err(|e| AppError::InternalError(format!("Cannot fetch native balance: {e}")));
+    }
+
+    ERC20::new(token, provider).balanceOf(owner).call().await
+        .map_err(|e| AppError::InternalError(format!("Cannot fetch {} balance of {}: {e}", token, owner)))
+}
@@ -0,0 +1,116 @@
+use alloy::{
+    primitives::{Address, TxKind},
+    rpc::types::TransactionRequest,
+};
+
+use crate::{
+    chain_config::registry::{ChainEntry, TokenEntry, registry},
+    error::error::AppError,
+    utils::{sweep_outbox::SweepIntent, token_decimals::{decode_transfer, u256_to_decimal}},
+};
+
+
+/// Checks a sweep against the chain's treasury allow-list before it is signed.
+///
+/// The transaction itself is decoded rather than trusting the intent: it must
+/// be sent from the intent's wallet on the intent's chain and be either
+/// - a native transfer of exactly `intent.amount` to a treasury address, or
+/// - a call to the intent's token, registered on the chain, carrying exactly
+///   `transfer(treasury, intent.amount)` and no native value.
+///
+/// Returns what is wrong with the transaction on a mismatch.
+pub fn check_sweep(
+    chain: &ChainEntry,
+    tokens: &[TokenEntry],
+    intent: &SweepIntent<'_>,
+    tx: &TransactionRequest,
+) -> Result<(), String> {
+
+    if intent.chain != chain.name {
+        return Err(format!("intent for chain {} checked against {}", intent.chain, chain.name));
+    }
+
+    if tx.chain_id.is_some_and(|chain_id| chain_id != chain.chain_id) {
+        return Err(format!("chain id {:?} instead of {}", tx.chain_id, chain.chain_id));
+    }
+
+    if tx.from != Some(intent.wallet_address) {
+        return Err(format!("sender {:?} instead of wallet {}", tx.from, intent.wallet_address));
+    }
+
+    let Some(TxKind::Call(to)) = tx.to else {
+        return Err("no call target (contract creation)".into());
+    };
+
+    let value = tx.value.unwrap_or_default();
+    let input = tx.input.input().map(|input| input.as_ref()).unwrap_or_default();
+
+    if tx.authorization_list.as_ref().is_some_and(|list| !list.is_empty()) {
+        return Err("carries EIP-7702 authorizations".into());
+    }
+
+    if intent.token.is_zero() {
+        if !input.is_empty() {
+            return Err("native sweep carries calldata".into());
+        }
+        if !chain.is_treasury(to) {
+            return Err(format!("native sweep to {} which is not a treasury address", to));
+        }
+        if u256_to_decimal(value, 18).ok() != Some(intent.amount) {
+            return Err(format!("native sweep moves {} wei instead of {}", value, intent.amount));
+        }
+        return Ok(());
+    }
+
+    let Some(token) = tokens.iter().find(|token| token.address == to) else {
+        return Err(format!("call to {} which is not a registered token", to));
+    };
+
+    if to != intent.token {
+        return Err(format!("call to token {} instead of {}", to, intent.token));
+    }
+
+    if !value.is_zero() {
+        return Err(format!("token sweep carries {} wei of native value", value));
+    }
+
+    let (recipient, amount) = decode_transfer(input).ok_or("calldata is not an ERC-20 transfer")?;
+
+    if !chain.is_treasury(recipient) {
+        return Err(format!("token sweep to {} which is not a treasury address", recipient));
+    }
+
+    if u256_to_decimal(amount, token.decimals).ok() != Some(intent.amount) {
+        return Err(format!("token sweep moves {} units instead of {}", amount, intent.amount));
+    }
+
+    Ok(())
+}
+
+
+
+
+/// Fails unless `master`, and each chain's cold wallet, is on the treasury
+/// allow-list of every enabled chain, so a misconfigured chain stops startup
+/// instead of refusing every sweep.
+pub fn check_treasuries_allowed(master: Address) -> Result<(), AppError> {
+
+    let registry = registry();
+    let mut problems: Vec<String> = Vec::new();
+
+    for chain in registry.chains() {
+        if !chain.is_treasury(master) {
+            problems.push(format!("MASTER_WALLET_ADDRESS {} is not in treasury_addresses of {}", master, chain.name));
+        }
+        if let Some(cold) = chain.cold_wallet_address.filter(|cold| !chain.is_treasury(*cold)) {
+            problems.push(format!("cold wallet {} is not in treasury_addresses of {}", cold, chain.name));
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    problems.sort_unstable();
+    Err(AppError::ConfigError(problems.join("; ")))
+}
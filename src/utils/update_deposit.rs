@@ -2,7 +2,7 @@ use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QuerySele
 use uuid::Uuid;
 use rust_decimal::Decimal;
 
-use crate::{entities::{deposit_receipt, user_balance}, error::error::AppError};
+use crate::{entities::{deposit_receipt, pending_deposit, user_balance}, error::error::AppError};
 
 pub async fn upsert_user_balance_and_receipt(
     txn: &sea_orm::DatabaseTransaction,
@@ -66,3 +66,94 @@ pub async fn upsert_user_balance_and_receipt(
 
     Ok(())
 }
+
+
+
+/// Records a mined sweep as `PENDING` without crediting the user.
+///
+/// The confirmation tracker (`jobs::confirmations`) credits it through
+/// `upsert_user_balance_and_receipt` once the block is deep enough.
+#[allow(clippy::too_many_arguments)]
+pub async fn record_pending_deposit(
+    txn: &sea_orm::DatabaseTransaction,
+    user_id: Uuid,
+    wallet_address: &str,
+    token_address: &str,
+    chain_name: &str,
+    amount: Decimal,
+    tx_hash: &str,
+    block_number: u64,
+    block_hash: &str,
+) -> Result<(), AppError> {
+
+    pending_deposit::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        user_id: Set(user_id),
+        wallet_address: Set(wallet_address.to_string()),
+        token: Set(token_address.to_string()),
+        chain: Set(chain_name.to_string()),
+        amount: Set(amount),
+        tx_hash: Set(tx_hash.to_string()),
+        block_number: Set(block_number as i64),
+        block_hash: Set(block_hash.to_string()),
+        status: Set("PENDING".to_string()),
+        credited_at: Set(None),
+        updated_at: Set(chrono::Utc::now().into()),
+        created_at: Set(chrono::Utc::now().into()),
+    }
+    .insert(txn)
+    .await
+    .map_err(AppError::DbError)?;
+
+    Ok(())
+}
+
+
+
+/// Reverses a credited deposit whose sweep was reorged out of the chain.
+///
+/// Debits `user_balance` (which may go negative if the user already spent it)
+/// and appends a negative `deposit_receipt` so the receipt ledger stays append-only.
+pub async fn reverse_user_balance_and_receipt(
+    txn: &sea_orm::DatabaseTransaction,
+    user_id: Uuid,
+    wallet_address: &str,
+    token_address: &str,
+    chain_name: &str,
+    amount: Decimal,
+    tx_hash: &str,
+) -> Result<(), AppError> {
+
+    let existing_balance = user_balance::Entity::find()
+        .filter(user_balance::Column::Userid.eq(user_id))
+        .filter(user_balance::Column::Token.eq(token_address))
+        .filter(user_balance::Column::Chain.eq(chain_name))
+        .lock_exclusive()
+        .one(txn)
+        .await
+        .map_err(AppError::DbError)?
+        .ok_or_else(|| AppError::InternalError(format!("No balance to reverse for user {} tx {}", user_id, tx_hash)))?;
+
+    let balance = existing_balance.balance;
+    let mut active: user_balance::ActiveModel = existing_balance.into();
+    active.balance = Set(balance - amount);
+    active.updated_at = Set(chrono::Utc::now().into());
+    active.update(txn).await.map_err(AppError::DbError)?;
+
+    deposit_receipt::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        userid: Set(user_id.to_string()),
+        user_address: Set(wallet_address.to_string()),
+        token: Set(token_address.to_string()),
+        chain: Set(chain_name.to_string()),
+        amount: Set(-amount),
+        txn_hash: Set(tx_hash.to_string()),
+        created_at: Set(chrono::Utc::now().into()),
+        updated_at: Set(chrono::Utc::now().into()),
+    }
+    .insert(txn)
+    .await
+    .map_err(AppError::DbError)?;
+
+    Ok(())
+}
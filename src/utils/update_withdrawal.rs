@@ -0,0 +1,108 @@
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter, QuerySelect};
+use uuid::Uuid;
+use rust_decimal::Decimal;
+
+use crate::{entities::{user_balance, withdraw_receipt, withdraw_request}, error::error::AppError};
+
+/// Withdrawal states whose transfer is signed and may still reach the chain.
+pub const SIGNED_WITHDRAWAL_STATES: [&str; 2] = ["SIGNED", "BROADCAST"];
+
+
+/// Debits `amount` from the user's balance of `token` on `chain` for a withdrawal.
+///
+/// Returns `false` without changing anything when the user has no balance row
+/// or less than `amount` in it; withdrawals never take a balance negative.
+pub async fn debit_user_balance(
+    txn: &sea_orm::DatabaseTransaction,
+    user_id: Uuid,
+    token_address: &str,
+    chain_name: &str,
+    amount: Decimal,
+) -> Result<bool, AppError> {
+
+    let Some(existing_balance) = lock_user_balance(txn, user_id, token_address, chain_name).await? else {
+        return Ok(false);
+    };
+
+    let balance = existing_balance.balance;
+    if balance < amount {
+        return Ok(false);
+    }
+
+    let mut active: user_balance::ActiveModel = existing_balance.into();
+    active.balance = Set(balance - amount);
+    active.updated_at = Set(chrono::Utc::now().into());
+    active.update(txn).await.map_err(AppError::DbError)?;
+
+    Ok(true)
+}
+
+
+
+/// Gives a failed withdrawal's debit back to the user's balance.
+pub async fn refund_user_balance(
+    txn: &sea_orm::DatabaseTransaction,
+    user_id: Uuid,
+    token_address: &str,
+    chain_name: &str,
+    amount: Decimal,
+) -> Result<(), AppError> {
+
+    let existing_balance = lock_user_balance(txn, user_id, token_address, chain_name)
+        .await?
+        .ok_or_else(|| AppError::InternalError(format!("No balance to refund for user {} on {}", user_id, chain_name)))?;
+
+    let balance = existing_balance.balance;
+    let mut active: user_balance::ActiveModel = existing_balance.into();
+    active.balance = Set(balance + amount);
+    active.updated_at = Set(chrono::Utc::now().into());
+    active.update(txn).await.map_err(AppError::DbError)?;
+
+    Ok(())
+}
+
+
+
+/// Appends the `withdraw_receipt` of a completed withdrawal.
+pub async fn record_withdraw_receipt(
+    txn: &sea_orm::DatabaseTransaction,
+    request: &withdraw_request::Model,
+    tx_hash: &str,
+) -> Result<(), AppError> {
+
+    withdraw_receipt::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        userid: Set(request.userid.to_string()),
+        user_address: Set(request.user_address.clone()),
+        token: Set(request.token.clone()),
+        chain: Set(request.chain.clone()),
+        amount: Set(request.amount),
+        txn_hash: Set(tx_hash.to_string()),
+        created_at: Set(chrono::Utc::now().into()),
+        updated_at: Set(chrono::Utc::now().into()),
+    }
+    .insert(txn)
+    .await
+    .map_err(AppError::DbError)?;
+
+    Ok(())
+}
+
+
+
+async fn lock_user_balance(
+    txn: &sea_orm::DatabaseTransaction,
+    user_id: Uuid,
+    token_address: &str,
+    chain_name: &str,
+) -> Result<Option<user_balance::Model>, AppError> {
+
+    user_balance::Entity::find()
+        .filter(user_balance::Column::Userid.eq(user_id))
+        .filter(user_balance::Column::Token.eq(token_address))
+        .filter(user_balance::Column::Chain.eq(chain_name))
+        .lock_exclusive()
+        .one(txn)
+        .await
+        .map_err(AppError::DbError)
+}
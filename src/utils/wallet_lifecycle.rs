@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, ConnectionTrait};
+use uuid::Uuid;
+
+use crate::{
+    entities::{sea_orm_active_enums::WalletStatus, user_wallet, wallet_status_audit}, error::error::AppError
+};
+
+impl WalletStatus {
+    /// Whether a wallet may move from `self` to `next`.
+    ///
+    /// The happy path is `Assigned -> AwaitingDeposit -> Sweepable -> Sweeping
+    /// -> Confirming -> Free`. `GasPending` parks wallets that hold tokens but no
+    /// gas, `Failed` parks wallets whose sweep kept erroring, and any state may
+    /// be `Quarantined` for manual review. Parked wallets go back to `Sweepable`
+    /// once their `next_attempt_at` passes or a new deposit arrives. The deposit indexer may move an
+    /// `Assigned` wallet straight to `Sweepable` when its first deposit arrives.
+    pub fn can_transition_to(self, next: WalletStatus) -> bool {
+        use WalletStatus::*;
+
+        if next == Quarantined {
+            return self != Quarantined;
+        }
+
+        matches!(
+            (self, next),
+            (Assigned, AwaitingDeposit | Sweepable | Free)
+                | (AwaitingDeposit, Sweepable | Free)
+                | (Sweepable, Sweeping | Free)
+                | (Sweeping, Sweepable | GasPending | Confirming | Free | Failed)
+                | (GasPending, Sweepable | Failed)
+                | (Confirming, Free | Sweepable)
+                | (Free, Assigned | Sweepable)
+                | (Quarantined, Free | Sweepable)
+                | (Failed, Sweepable)
+        )
+    }
+}
+
+
+
+/// Moves `wallet` to `to`, rejecting illegal transitions and auditing legal ones.
+///
+/// Both the status update and the `wallet_status_audit` row are written on
+/// `conn`, so pass the caller's transaction to keep them atomic with the work
+/// that caused the change. Transitioning to the current status is a no-op.
+pub async fn transition_wallet<C: ConnectionTrait>(
+    conn: &C,
+    wallet: user_wallet::Model,
+    to: WalletStatus,
+    reason: &str,
+) -> Result<user_wallet::Model, AppError> {
+
+    let from = wallet.status;
+
+    if from == to {
+        return Ok(wallet);
+    }
+
+    if !from.can_transition_to(to) {
+        return Err(AppError::BadRequest(format!(
+            "Illegal wallet transition {:?} -> {:?} for {}", from, to, wallet.wallet_address
+        )));
+    }
+
+    wallet_status_audit::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        wallet_id: Set(wallet.id),
+        from_status: Set(from),
+        to_status: Set(to),
+        reason: Set(reason.to_string()),
+        created_at: Set(chrono::Utc::now().into()),
+    }
+    .insert(conn)
+    .await
+    .map_err(AppError::DbError)?;
+
+    let mut active: user_wallet::ActiveModel = wallet.into();
+    active.status = Set(to);
+
+    // A lease only means something while the wallet is being swept
+    if to != WalletStatus::Sweeping {
+        active.claimed_by = Set(None);
+        active.lease_expires_at = Set(None);
+    }
+
+    active.update(conn).await.map_err(AppError::DbError)
+}
+
+
+
+/// Claims a `SWEEPABLE` wallet for `worker` and starts its lease.
+pub async fn claim_wallet<C: ConnectionTrait>(
+    conn: &C,
+    wallet: user_wallet::Model,
+    worker: &str,
+    lease: Duration,
+) -> Result<user_wallet::Model, AppError> {
+    let claimed = transition_wallet(conn, wallet, WalletStatus::Sweeping, &format!("claimed by worker {}", worker)).await?;
+    renew_lease(conn, claimed, worker, lease).await
+}
+
+
+
+/// Extends the lease of a wallet `worker` is still sweeping.
+pub async fn renew_lease<C: ConnectionTrait>(
+    conn: &C,
+    wallet: user_wallet::Model,
+    worker: &str,
+    lease: Duration,
+) -> Result<user_wallet::Model, AppError> {
+    let expires_at = chrono::Utc::now() + chrono::Duration::from_std(lease).unwrap_or_default();
+
+    let mut active: user_wallet::ActiveModel = wallet.into();
+    active.claimed_by = Set(Some(worker.to_string()));
+    active.lease_expires_at = Set(Some(expires_at.into()));
+    active.update(conn).await.map_err(AppError::DbError)
+}
+
+
+
+/// Holds `wallet` out of the sweep queue, and out of the reaper's re-arm pass, for `delay`.
+pub async fn defer_wallet<C: ConnectionTrait>(
+    conn: &C,
+    wallet: user_wallet::Model,
+    delay: Duration,
+) -> Result<user_wallet::Model, AppError> {
+    let retry_at = chrono::Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
+
+    let mut active: user_wallet::ActiveModel = wallet.into();
+    active.next_attempt_at = Set(Some(retry_at.into()));
+    active.update(conn).await.map_err(AppError::DbError)
+}
+
+
+
+/// Identifies a worker across processes and hosts, e.g. `sweeper-7f9c:4121:base_sepolia:2`.
+pub fn worker_identity(chain: &str, worker_id: u64) -> String {
+    let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "local".to_string());
+    format!("{}:{}:{}:{}", host, std::process::id(), chain, worker_id)
+}
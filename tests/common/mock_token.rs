@@ -0,0 +1,183 @@
+//! Bytecode of the mock USDC/USDT contracts deployed on Anvil.
+//!
+//! The repo only ships the ERC-20 ABI (`src/utils/abi/ERC20.json`), not a
+//! compiled contract, and the test environment has no Solidity compiler. The
+//! mock is therefore assembled here: it answers the parts of that ABI the
+//! sweeper uses (`balanceOf`, `transfer`, `decimals`) plus a `mint`, and emits
+//! the standard `Transfer` event. Balances live in the storage slot equal to
+//! the holder's address.
+
+use std::collections::HashMap;
+
+use alloy::{primitives::Bytes, sol, sol_types::{SolCall, SolEvent}};
+
+sol!(
+    #[sol(rpc)]
+    ERC20,
+    "src/utils/abi/ERC20.json"
+);
+
+sol! {
+    #[sol(rpc)]
+    interface Mintable {
+        function mint(address to, uint256 amount) external;
+    }
+}
+
+const STOP: u8 = 0x00;
+const ADD: u8 = 0x01;
+const SUB: u8 = 0x03;
+const LT: u8 = 0x10;
+const EQ: u8 = 0x14;
+const SHR: u8 = 0x1c;
+const CALLER: u8 = 0x33;
+const CALLDATALOAD: u8 = 0x35;
+const CODECOPY: u8 = 0x39;
+const MSTORE: u8 = 0x52;
+const SLOAD: u8 = 0x54;
+const SSTORE: u8 = 0x55;
+const JUMPI: u8 = 0x57;
+const JUMPDEST: u8 = 0x5b;
+const PUSH1: u8 = 0x60;
+const DUP1: u8 = 0x80;
+const DUP3: u8 = 0x82;
+const SWAP1: u8 = 0x90;
+const LOG3: u8 = 0xa3;
+const RETURN: u8 = 0xf3;
+const REVERT: u8 = 0xfd;
+
+
+/// Creation code of a mock token with `decimals`.
+///
+/// With `returns_bool` false, `transfer` returns no data at all, like USDT on
+/// Ethereum (`tokens.transfer_returns_bool = FALSE`).
+pub fn creation_code(decimals: u8, returns_bool: bool) -> Bytes {
+    let runtime = runtime_code(decimals, returns_bool);
+    let length = u16::try_from(runtime.len()).expect("runtime fits in PUSH2");
+
+    // Copy the runtime that follows this 13-byte prefix into memory and return it
+    let mut asm = Asm::default();
+    asm.push(&length.to_be_bytes())
+        .op(DUP1)
+        .push(&13u16.to_be_bytes())
+        .push(&[0])
+        .op(CODECOPY)
+        .push(&[0])
+        .op(RETURN);
+
+    let mut code = asm.finish();
+    assert_eq!(code.len(), 13);
+    code.extend(runtime);
+
+    code.into()
+}
+
+
+fn runtime_code(decimals: u8, returns_bool: bool) -> Vec<u8> {
+    let transfer_topic = ERC20::Transfer::SIGNATURE_HASH;
+    let mut asm = Asm::default();
+
+    // Dispatch on the 4-byte selector
+    asm.push(&[0]).op(CALLDATALOAD).push(&[0xe0]).op(SHR);
+    for (selector, label) in [
+        (ERC20::balanceOfCall::SELECTOR, "balance_of"),
+        (ERC20::transferCall::SELECTOR, "transfer"),
+        (ERC20::decimalsCall::SELECTOR, "decimals"),
+        (Mintable::mintCall::SELECTOR, "mint"),
+    ] {
+        asm.op(DUP1).push(&selector).op(EQ).jump_if(label);
+    }
+    asm.label("revert").push(&[0]).op(DUP1).op(REVERT);
+
+    // balanceOf(owner): SLOAD(owner)
+    asm.label("balance_of")
+        .push(&[4]).op(CALLDATALOAD).op(SLOAD)
+        .push(&[0]).op(MSTORE)
+        .push(&[32]).push(&[0]).op(RETURN);
+
+    asm.label("decimals")
+        .push(&[decimals]).push(&[0]).op(MSTORE)
+        .push(&[32]).push(&[0]).op(RETURN);
+
+    // transfer(to, amount): revert unless the caller holds `amount`
+    asm.label("transfer")
+        .op(CALLER).op(SLOAD)
+        .push(&[0x24]).op(CALLDATALOAD)
+        .op(DUP1).op(DUP3).op(LT).jump_if("revert")
+        .op(SWAP1).op(SUB).op(CALLER).op(SSTORE);
+    credit_recipient(&mut asm);
+    asm.push(&[4]).op(CALLDATALOAD).op(CALLER);
+    emit_transfer(&mut asm, &transfer_topic.0);
+    if returns_bool {
+        asm.push(&[1]).push(&[0]).op(MSTORE).push(&[32]).push(&[0]).op(RETURN);
+    } else {
+        asm.op(STOP);
+    }
+
+    // mint(to, amount): open to anyone, it is a test token
+    asm.label("mint");
+    credit_recipient(&mut asm);
+    asm.push(&[4]).op(CALLDATALOAD).push(&[0]);
+    emit_transfer(&mut asm, &transfer_topic.0);
+    asm.op(STOP);
+
+    asm.finish()
+}
+
+
+/// Adds calldata `amount` to the balance of calldata `to`, leaving `amount` in memory at 0.
+fn credit_recipient(asm: &mut Asm) {
+    asm.push(&[0x24]).op(CALLDATALOAD)
+        .push(&[4]).op(CALLDATALOAD)
+        .op(DUP1).op(SLOAD).op(DUP3).op(ADD)
+        .op(SWAP1).op(SSTORE)
+        .push(&[0]).op(MSTORE);
+}
+
+
+/// Emits `Transfer(from, to, amount)` with `to` and `from` on the stack and `amount` in memory at 0.
+fn emit_transfer(asm: &mut Asm, topic: &[u8; 32]) {
+    asm.push(topic).push(&[32]).push(&[0]).op(LOG3);
+}
+
+
+/// Minimal assembler: opcodes, pushes and `PUSH2` jumps to labels resolved at the end.
+#[derive(Default)]
+struct Asm {
+    code: Vec<u8>,
+    labels: HashMap<&'static str, u16>,
+    jumps: Vec<(usize, &'static str)>,
+}
+
+impl Asm {
+    fn op(&mut self, op: u8) -> &mut Self {
+        self.code.push(op);
+        self
+    }
+
+    fn push(&mut self, bytes: &[u8]) -> &mut Self {
+        assert!((1..=32).contains(&bytes.len()));
+        self.code.push(PUSH1 + bytes.len() as u8 - 1);
+        self.code.extend_from_slice(bytes);
+        self
+    }
+
+    fn jump_if(&mut self, label: &'static str) -> &mut Self {
+        self.jumps.push((self.code.len() + 1, label));
+        self.push(&[0, 0]).op(JUMPI)
+    }
+
+    fn label(&mut self, label: &'static str) -> &mut Self {
+        let offset = u16::try_from(self.code.len()).expect("code fits in PUSH2");
+        self.labels.insert(label, offset);
+        self.op(JUMPDEST)
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        for (at, label) in &self.jumps {
+            let target = self.labels[label].to_be_bytes();
+            self.code[*at..*at + 2].copy_from_slice(&target);
+        }
+        self.code
+    }
+}
@@ -0,0 +1,308 @@
+//! Shared harness of the integration tests: a local Anvil node with mock
+//! USDC/USDT deployed, and a throwaway Postgres schema with the sweeper tables.
+//!
+//! Tests need `anvil` (Foundry) on `PATH` and `TEST_DATABASE_URL` pointing at a
+//! Postgres database the tests may create schemas in. When either is missing
+//! `TestEnv::start` returns `None` and the test passes without running, unless
+//! `REQUIRE_INTEGRATION` is set: then it panics, so CI cannot go green without
+//! having run them:
+//!
+//! ```sh
+//! REQUIRE_INTEGRATION=1 TEST_DATABASE_URL=postgres://... cargo test
+//! ```
+
+#![allow(dead_code)]
+
+pub mod mock_token;
+pub mod registry;
+
+use std::{
+    env, fs, net::TcpListener, path::Path, process::{Child, Command, Stdio}, time::Duration
+};
+
+use alloy::{
+    network::{EthereumWallet, TransactionBuilder}, primitives::{Address, FixedBytes, U256}, providers::{DynProvider, Provider, ProviderBuilder}, rpc::types::TransactionRequest
+};
+use alloy_signer_local::PrivateKeySigner;
+use avitus_casino_sweeper::{
+    chain_config::registry::reload_registry, entities::{prelude::UserWallet, sea_orm_active_enums::WalletStatus, user_wallet}, state_models::models::DbConnection
+};
+use hmac::{Hmac, Mac};
+use rust_decimal::Decimal;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, ConnectOptions, ConnectionTrait, Database, DatabaseConnection};
+use sha2::Sha256;
+use tokio::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+use mock_token::{ERC20, Mintable, creation_code};
+
+/// Registry name of the Anvil chain.
+pub const CHAIN: &str = "anvil";
+
+/// Anvil's default chain id.
+pub const CHAIN_ID: u64 = 31337;
+
+/// First of Anvil's prefunded dev accounts; deploys the tokens and funds deposit wallets.
+const FUNDER_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+/// `WALLET_GENERATION_SECRET` of the tests.
+const WALLET_SECRET: &str = "5eed5eed5eed5eed5eed5eed5eed5eed5eed5eed5eed5eed5eed5eed5eed5eed";
+
+/// The sweeper keeps its configuration, registry and RPC pools in process-wide
+/// state, so tests in one binary take turns instead of running in parallel.
+static TEST_LOCK: Mutex<()> = Mutex::const_new(());
+
+
+pub struct TestEnv {
+    _turn: MutexGuard<'static, ()>,
+    anvil: Child,
+    admin: DatabaseConnection,
+    schema: String,
+    pub db: DbConnection,
+    /// Funder account's provider.
+    pub provider: DynProvider,
+    /// Sweep destination (`MASTER_WALLET_ADDRESS`), a fresh address with no history.
+    pub master: Address,
+    /// Key of `master`, the hot wallet paying out withdrawals.
+    pub master_key: PrivateKeySigner,
+    pub usdc: Address,
+    pub usdt: Address,
+}
+
+/// Skips an integration test whose environment is missing, or fails it when
+/// `REQUIRE_INTEGRATION` is set.
+fn skip<T>(reason: &str) -> Option<T> {
+    if env::var_os("REQUIRE_INTEGRATION").is_some() {
+        panic!("REQUIRE_INTEGRATION is set but {reason}");
+    }
+
+    eprintln!("{reason}, skipping integration test");
+    None
+}
+
+
+impl TestEnv {
+    /// Boots Anvil, deploys the mock tokens and creates a fresh schema registered as chain `anvil`.
+    pub async fn start() -> Option<Self> {
+        let turn = TEST_LOCK.lock().await;
+
+        let Ok(database_url) = env::var("TEST_DATABASE_URL") else {
+            return skip("TEST_DATABASE_URL not set");
+        };
+
+        let port = match TcpListener::bind("127.0.0.1:0").and_then(|listener| listener.local_addr()) {
+            Ok(address) => address.port(),
+            Err(e) => return skip(&format!("no free port for anvil ({e})")),
+        };
+        let anvil = match Command::new("anvil")
+            .args(["--port", &port.to_string(), "--chain-id", &CHAIN_ID.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(anvil) => anvil,
+            Err(e) => return skip(&format!("cannot start anvil ({e})")),
+        };
+
+        let rpc_url = format!("http://127.0.0.1:{port}");
+        let master_key = PrivateKeySigner::random();
+        let master = master_key.address();
+
+        // SAFETY: tests take turns under TEST_LOCK and set these before any sweeper code runs
+        unsafe {
+            env::set_var("DATABASE_URL", &database_url);
+            env::set_var("MASTER_WALLET_ADDRESS", master.to_string());
+            env::set_var("WALLET_GENERATION_SECRET", WALLET_SECRET);
+            env::remove_var("GAS_FUNDER_PRIVATE_KEY");
+        }
+
+        let funder: PrivateKeySigner = FUNDER_KEY.parse().expect("valid funder key");
+        let provider = ProviderBuilder::new()
+            .wallet(EthereumWallet::from(funder))
+            .connect_http(rpc_url.parse().expect("valid anvil url"))
+            .erased();
+
+        wait_for_node(&provider).await;
+
+        let admin = Database::connect(&database_url).await.expect("connect to TEST_DATABASE_URL");
+        let schema = format!("sweeper_test_{}", Uuid::new_v4().simple());
+        admin.execute_unprepared(&format!("CREATE SCHEMA {schema}")).await.expect("create schema");
+
+        let mut options = ConnectOptions::new(database_url);
+        options.set_schema_search_path(schema.clone()).sqlx_logging(false);
+        let db = DbConnection(Database::connect(options).await.expect("connect to test schema"));
+
+        let mut env = Self {
+            _turn: turn,
+            anvil,
+            admin,
+            schema,
+            db,
+            provider,
+            master,
+            master_key,
+            usdc: Address::ZERO,
+            usdt: Address::ZERO,
+        };
+
+        env.migrate().await;
+        env.usdc = env.deploy_token(6, true).await;
+        env.usdt = env.deploy_token(6, false).await;
+        env.register_chain(&rpc_url).await;
+
+        reload_registry(&env.db).await.expect("load registry");
+
+        Some(env)
+    }
+
+    /// Creates the shared tables, then applies `migrations/*.sql` in order.
+    async fn migrate(&self) {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        self.execute(&fs::read_to_string(root.join("tests/fixtures/base_schema.sql")).expect("read base schema")).await;
+
+        let mut migrations: Vec<_> = fs::read_dir(root.join("migrations"))
+            .expect("read migrations")
+            .map(|entry| entry.expect("migration entry").path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
+            .collect();
+        migrations.sort();
+
+        for migration in migrations {
+            self.execute(&fs::read_to_string(&migration).expect("read migration")).await;
+        }
+    }
+
+    /// Disables the seeded networks and registers Anvil with the two mock tokens.
+    async fn register_chain(&self, rpc_url: &str) {
+        self.execute(&format!(
+            "UPDATE chains SET enabled = FALSE;
+             INSERT INTO chains (name, chain_id, rpc_urls, confirmations, native_symbol, min_native_sweep, treasury_addresses)
+             VALUES ('{CHAIN}', {CHAIN_ID}, '[\"{rpc_url}\"]', 1, 'ETH', 0.01, '[\"{master}\"]');
+             INSERT INTO tokens (chain, symbol, address, decimals, min_sweep_amount, transfer_returns_bool) VALUES
+                 ('{CHAIN}', 'USDC', '{usdc}', 6, 1, TRUE),
+                 ('{CHAIN}', 'USDT', '{usdt}', 6, 1, FALSE);",
+            usdc = self.usdc,
+            usdt = self.usdt,
+            master = self.master,
+        ))
+        .await;
+    }
+
+    pub async fn execute(&self, sql: &str) {
+        self.db.0.execute_unprepared(sql).await.unwrap_or_else(|e| panic!("SQL failed: {e}\n{sql}"));
+    }
+
+    async fn deploy_token(&self, decimals: u8, returns_bool: bool) -> Address {
+        let tx = TransactionRequest::default().with_deploy_code(creation_code(decimals, returns_bool));
+        let receipt = self.provider.send_transaction(tx).await.expect("deploy token")
+            .get_receipt().await.expect("deploy receipt");
+
+        receipt.contract_address.expect("deployed contract address")
+    }
+
+    /// Deposit address of `user_id`, derived like the sweeper's v1 `HmacDeriver`:
+    /// the HMAC-SHA256 of the user id under the wallet secret is the private key.
+    pub fn deposit_address(&self, user_id: Uuid) -> Address {
+        let secret = hex::decode(WALLET_SECRET).expect("hex secret");
+        let mut mac = Hmac::<Sha256>::new_from_slice(&secret).expect("HMAC takes any key size");
+        mac.update(user_id.as_bytes());
+        let seed = FixedBytes::<32>::from_slice(&mac.finalize().into_bytes());
+
+        PrivateKeySigner::from_bytes(&seed).expect("valid seed").address()
+    }
+
+    pub async fn create_user(&self) -> Uuid {
+        let user_id = Uuid::new_v4();
+        self.execute(&format!("INSERT INTO app_user (id, username) VALUES ('{user_id}', 'user_{}')", user_id.simple())).await;
+
+        user_id
+    }
+
+    /// Creates a user and its deposit wallet, queued for sweeping on Anvil.
+    pub async fn create_wallet(&self) -> (Uuid, user_wallet::Model) {
+        let user_id = self.create_user().await;
+
+        let wallet = user_wallet::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            user_id: Set(user_id),
+            wallet_address: Set(self.deposit_address(user_id).to_string()),
+            status: Set(WalletStatus::Sweepable),
+            active_token: Set(self.usdc.to_string()),
+            active_chain: Set(CHAIN.to_string()),
+            active_balance: Set(Decimal::ZERO),
+            active_gas: Set(Decimal::ZERO),
+            created_at: Set(chrono::Utc::now().into()),
+            claimed_by: Set(None),
+            lease_expires_at: Set(None),
+            next_attempt_at: Set(None),
+            key_version: Set(1),
+            key_index: Set(None),
+        }
+        .insert(&self.db.0)
+        .await
+        .expect("insert wallet");
+
+        (user_id, wallet)
+    }
+
+    /// Puts a wallet back in the sweep queue, as the indexer does for a new deposit.
+    pub async fn queue_wallet(&self, wallet_id: Uuid) {
+        self.execute(&format!(
+            "UPDATE user_wallet SET status = 'SWEEPABLE', active_chain = '{CHAIN}', claimed_by = NULL, lease_expires_at = NULL WHERE id = '{wallet_id}'"
+        ))
+        .await;
+    }
+
+    pub async fn wallet(&self, wallet_id: Uuid) -> user_wallet::Model {
+        use sea_orm::EntityTrait;
+        UserWallet::find_by_id(wallet_id).one(&self.db.0).await.expect("load wallet").expect("wallet exists")
+    }
+
+    pub async fn mint(&self, token: Address, to: Address, amount: U256) {
+        Mintable::new(token, &self.provider).mint(to, amount)
+            .send().await.expect("mint")
+            .get_receipt().await.expect("mint receipt");
+    }
+
+    pub async fn send_native(&self, to: Address, amount: U256) {
+        let tx = TransactionRequest::default().with_to(to).with_value(amount);
+        self.provider.send_transaction(tx).await.expect("send native")
+            .get_receipt().await.expect("native receipt");
+    }
+
+    pub async fn token_balance(&self, token: Address, owner: Address) -> U256 {
+        ERC20::new(token, &self.provider).balanceOf(owner).call().await.expect("balanceOf")
+    }
+
+    pub async fn native_balance(&self, owner: Address) -> U256 {
+        self.provider.get_balance(owner).await.expect("get balance")
+    }
+
+    /// Drops the test schema. Skipped when a test panics, leaving it for inspection.
+    pub async fn teardown(self) {
+        self.db.0.clone().close().await.ok();
+        self.admin
+            .execute_unprepared(&format!("DROP SCHEMA {} CASCADE", self.schema))
+            .await
+            .expect("drop schema");
+    }
+}
+
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        self.anvil.kill().ok();
+        self.anvil.wait().ok();
+    }
+}
+
+
+/// Waits until Anvil answers RPC calls.
+async fn wait_for_node(provider: &DynProvider) {
+    for _ in 0..50 {
+        if provider.get_chain_id().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("anvil did not start");
+}
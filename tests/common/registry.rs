@@ -0,0 +1,50 @@
+//! Registry entries of an offline `testnet` chain with one USDC token, for
+//! tests that check policies without a node or database.
+
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use avitus_casino_sweeper::{
+    chain_config::registry::{ChainEntry, TokenEntry},
+    utils::dust_policy::DustPolicy,
+};
+use rust_decimal::Decimal;
+
+/// Registry name of the offline chain.
+pub const TESTNET: &str = "testnet";
+
+
+/// The `testnet` chain, paying out only to `treasury`.
+pub fn chain_entry(treasury: Address) -> ChainEntry {
+    ChainEntry {
+        name: TESTNET.into(),
+        chain_id: 31337,
+        rpc_urls: Vec::new(),
+        confirmations: 1,
+        finality_depth: 64,
+        native_symbol: "ETH".into(),
+        multicall3: None,
+        min_native_sweep: Decimal::ZERO,
+        max_fee_per_gas: None,
+        max_fee_ratio: None,
+        rbf_after: Duration::from_secs(600),
+        rbf_bump_percent: 20,
+        treasury_addresses: vec![treasury],
+        cold_wallet_address: None,
+    }
+}
+
+
+/// A 6-decimal USDC at `address` on `testnet`.
+pub fn token_entries(address: Address) -> Vec<TokenEntry> {
+    vec![TokenEntry {
+        chain: TESTNET.into(),
+        symbol: "USDC".into(),
+        address,
+        decimals: 6,
+        min_sweep_amount: Decimal::ONE,
+        transfer_returns_bool: true,
+        native_price: None,
+        dust_policy: DustPolicy::Accumulate,
+    }]
+}
@@ -0,0 +1,87 @@
+-- Tables the sweeper shares with the main application. They predate migrations/ and are
+-- created here with just the columns the sweeper reads and writes; migrations/*.sql are
+-- applied on top of them in order.
+
+CREATE TABLE app_user (
+    id         UUID PRIMARY KEY,
+    username   TEXT        NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE user_wallet (
+    id             UUID PRIMARY KEY,
+    user_id        UUID            NOT NULL REFERENCES app_user (id) ON UPDATE RESTRICT ON DELETE RESTRICT,
+    wallet_address TEXT            NOT NULL UNIQUE,
+    status         TEXT            NOT NULL,
+    active_token   VARCHAR         NOT NULL DEFAULT '',
+    active_chain   VARCHAR         NOT NULL DEFAULT '',
+    active_balance NUMERIC(78, 18) NOT NULL DEFAULT 0,
+    active_gas     NUMERIC(78, 18) NOT NULL DEFAULT 0,
+    created_at     TIMESTAMPTZ     NOT NULL DEFAULT now()
+);
+
+CREATE TABLE user_balance (
+    id         UUID PRIMARY KEY,
+    userid     UUID            NOT NULL REFERENCES app_user (id) ON UPDATE RESTRICT ON DELETE RESTRICT,
+    token      VARCHAR         NOT NULL,
+    chain      VARCHAR         NOT NULL,
+    balance    NUMERIC(78, 18) NOT NULL,
+    updated_at TIMESTAMPTZ     NOT NULL DEFAULT now(),
+    created_at TIMESTAMPTZ     NOT NULL DEFAULT now()
+);
+
+CREATE TABLE deposit_receipt (
+    id           UUID PRIMARY KEY,
+    userid       TEXT            NOT NULL,
+    user_address VARCHAR         NOT NULL,
+    token        VARCHAR         NOT NULL,
+    chain        VARCHAR         NOT NULL,
+    amount       NUMERIC(78, 18) NOT NULL,
+    txn_hash     TEXT            NOT NULL,
+    updated_at   TIMESTAMPTZ     NOT NULL DEFAULT now(),
+    created_at   TIMESTAMPTZ     NOT NULL DEFAULT now()
+);
+
+CREATE TABLE gas_donation (
+    id             UUID PRIMARY KEY,
+    user_id        UUID            NOT NULL REFERENCES app_user (id) ON UPDATE RESTRICT ON DELETE RESTRICT,
+    wallet_address TEXT            NOT NULL UNIQUE,
+    chain          VARCHAR         NOT NULL,
+    gas            NUMERIC(78, 18) NOT NULL,
+    created_at     TIMESTAMPTZ     NOT NULL DEFAULT now()
+);
+
+CREATE TABLE suspicious_activities (
+    id            SERIAL PRIMARY KEY,
+    user_id       VARCHAR     NOT NULL,
+    activity_type VARCHAR     NOT NULL,
+    severity      VARCHAR     NOT NULL,
+    details       JSONB       NOT NULL,
+    resolved      BOOLEAN     NOT NULL DEFAULT FALSE,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+    resolved_at   TIMESTAMPTZ
+);
+
+CREATE TABLE withdraw_request (
+    id           UUID PRIMARY KEY,
+    userid       UUID            NOT NULL REFERENCES app_user (id) ON UPDATE RESTRICT ON DELETE RESTRICT,
+    user_address VARCHAR         NOT NULL,
+    token        VARCHAR         NOT NULL,
+    chain        VARCHAR         NOT NULL,
+    amount       NUMERIC(78, 18) NOT NULL,
+    status       TEXT            NOT NULL,
+    updated_at   TIMESTAMPTZ     NOT NULL DEFAULT now(),
+    created_at   TIMESTAMPTZ     NOT NULL DEFAULT now()
+);
+
+CREATE TABLE withdraw_receipt (
+    id           UUID PRIMARY KEY,
+    userid       TEXT            NOT NULL,
+    user_address VARCHAR         NOT NULL,
+    token        VARCHAR         NOT NULL,
+    chain        VARCHAR         NOT NULL,
+    amount       NUMERIC(78, 18) NOT NULL,
+    txn_hash     TEXT            NOT NULL,
+    updated_at   TIMESTAMPTZ     NOT NULL DEFAULT now(),
+    created_at   TIMESTAMPTZ     NOT NULL DEFAULT now()
+);
@@ -0,0 +1,57 @@
+use alloy::primitives::address;
+use avitus_casino_sweeper::chain_config::key_deriver::{
+    BIP44_KEY_VERSION, Bip44Deriver, ExtendedKey, HARDENED, HMAC_KEY_VERSION, HmacDeriver, KeyDeriver, WalletKey,
+};
+use uuid::Uuid;
+
+/// BIP-32 test vector 1.
+const VECTOR_SEED: &str = "000102030405060708090a0b0c0d0e0f";
+
+/// BIP-39 seed of Anvil's `test test ... junk` mnemonic.
+const ANVIL_SEED: &str = "9dfc3c64c2f8bede1533b6a79f8570e5943e0b8fd1cf77107adf7b72cef42185d564a3aee24cab43f80e3c4538087d70fc824eabbad596a23c97b6ee8322ccc0";
+
+
+#[test]
+fn derives_the_bip32_test_vector() {
+    let master = ExtendedKey::master(&hex::decode(VECTOR_SEED).unwrap()).unwrap();
+
+    let expected = [
+        (vec![], "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"),
+        (vec![HARDENED], "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea"),
+        (vec![HARDENED, 1], "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368"),
+        (vec![HARDENED, 1, 2 | HARDENED], "cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca"),
+        (vec![HARDENED, 1, 2 | HARDENED, 2], "0f479245fb19a38a1954c5c7c0ebab2f9bdfd96a17563ef28a6a4b1a2a764ef4"),
+        (vec![HARDENED, 1, 2 | HARDENED, 2, 1_000_000_000], "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8"),
+    ];
+
+    for (path, key) in expected {
+        assert_eq!(hex::encode(master.derive_path(&path).unwrap().secret_bytes()), key, "path {:?}", path);
+    }
+}
+
+
+#[test]
+fn bip44_deriver_matches_standard_wallets() {
+    let deriver = Bip44Deriver::from_seed(&hex::decode(ANVIL_SEED).unwrap()).unwrap();
+    let key = |index| WalletKey { user_id: Uuid::new_v4(), version: BIP44_KEY_VERSION, index: Some(index) };
+
+    assert_eq!(deriver.derive(&key(0)).unwrap().address(), address!("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"));
+    assert_eq!(deriver.derive(&key(1)).unwrap().address(), address!("0x70997970C51812dc3A010C7d01b50e0d17dc79C8"));
+
+    let unindexed = WalletKey { index: None, ..key(0) };
+    assert!(deriver.derive(&unindexed).is_err());
+    assert!(deriver.derive(&key(HARDENED as i64)).is_err());
+}
+
+
+#[test]
+fn hmac_deriver_keys_by_user_id() {
+    let deriver = HmacDeriver::new(vec![7; 32]);
+    let user_id = Uuid::new_v4();
+    let key = WalletKey { user_id, version: HMAC_KEY_VERSION, index: None };
+
+    let address = deriver.derive(&key).unwrap().address();
+
+    assert_eq!(deriver.derive(&WalletKey { index: Some(5), ..key }).unwrap().address(), address);
+    assert_ne!(deriver.derive(&WalletKey { user_id: Uuid::new_v4(), ..key }).unwrap().address(), address);
+}
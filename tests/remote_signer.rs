@@ -0,0 +1,125 @@
+use std::net::TcpListener;
+
+use alloy::{
+    consensus::{TxEip1559, TxLegacy, TypedTransaction},
+    primitives::{Address, Bytes, TxKind, U256, address},
+    sol,
+    sol_types::SolCall,
+};
+use avitus_casino_sweeper::chain_config::{
+    key_deriver::{HMAC_KEY_VERSION, HmacDeriver, KeyDeriver, KeyDerivers, WalletKey},
+    remote_signer::{RemoteSigner, SignerService, run_signer_service},
+    signing_policy::SigningPolicy,
+};
+use uuid::Uuid;
+
+sol! {
+    interface IToken {
+        function transfer(address to, uint256 amount) external returns (bool);
+        function approve(address spender, uint256 amount) external returns (bool);
+    }
+}
+
+const SECRET: &str = "0f1e2d3c4b5a69788796a5b4c3d2e1f00f1e2d3c4b5a69788796a5b4c3d2e1f0";
+const MASTER: Address = address!("0x1000000000000000000000000000000000000001");
+const COLD: Address = address!("0x4000000000000000000000000000000000000004");
+const TOKEN: Address = address!("0x2000000000000000000000000000000000000002");
+const TOKEN_AUTH: &str = "signer-test-token";
+
+
+/// Starts a signer service on a free loopback port and returns its URL.
+fn start_signer() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind signer");
+    let url = format!("http://{}", listener.local_addr().expect("signer address"));
+
+    let service = SignerService {
+        derivers: KeyDerivers::from_secrets(Some(SECRET), None).expect("derivers"),
+        policy: SigningPolicy::new(MASTER, [COLD]),
+        auth_token: Some(TOKEN_AUTH.to_string()),
+    };
+    actix_web::rt::spawn(run_signer_service(listener, service));
+
+    url
+}
+
+
+fn wallet_key() -> WalletKey {
+    WalletKey { user_id: Uuid::new_v4(), version: HMAC_KEY_VERSION, index: None }
+}
+
+
+fn eip1559(to: Address, value: U256, input: Vec<u8>) -> TypedTransaction {
+    TypedTransaction::Eip1559(TxEip1559 {
+        chain_id: 31337,
+        nonce: 3,
+        gas_limit: 80_000,
+        max_fee_per_gas: 2_000_000_000,
+        max_priority_fee_per_gas: 1_000_000_000,
+        to: TxKind::Call(to),
+        value,
+        access_list: Default::default(),
+        input: Bytes::from(input),
+    })
+}
+
+
+fn transfer_to(recipient: Address) -> Vec<u8> {
+    IToken::transferCall { to: recipient, amount: U256::from(5_000_000u64) }.abi_encode()
+}
+
+
+#[actix_web::test]
+async fn signs_sweeps_to_treasury_wallets_only() {
+    let signer = RemoteSigner::new(&start_signer(), Some(TOKEN_AUTH.to_string()));
+    let key = wallet_key();
+
+    let expected = HmacDeriver::new(hex::decode(SECRET).unwrap()).derive(&key).unwrap().address();
+    assert_eq!(signer.address(&key).await.expect("address"), expected);
+
+    let token_sweep = eip1559(TOKEN, U256::ZERO, transfer_to(MASTER));
+    let envelope = signer.sign(&key, token_sweep).await.expect("token sweep is signed");
+    let recovered = envelope.signature().recover_address_from_prehash(&envelope.signature_hash()).unwrap();
+    assert_eq!(recovered, expected);
+
+    let native_sweep = eip1559(MASTER, U256::from(10u64).pow(U256::from(17u64)), Vec::new());
+    signer.sign(&key, native_sweep).await.expect("native sweep is signed");
+
+    // Sweeps past the vault limit go to the cold wallet
+    signer.sign(&key, eip1559(TOKEN, U256::ZERO, transfer_to(COLD))).await.expect("cold token sweep is signed");
+    signer.sign(&key, eip1559(COLD, U256::from(1u64), Vec::new())).await.expect("cold native sweep is signed");
+
+    let refused = [
+        eip1559(TOKEN, U256::ZERO, transfer_to(Address::repeat_byte(0x66))),
+        eip1559(TOKEN, U256::ZERO, IToken::approveCall { spender: MASTER, amount: U256::MAX }.abi_encode()),
+        eip1559(Address::repeat_byte(0x66), U256::from(1u64), Vec::new()),
+        eip1559(TOKEN, U256::from(1u64), transfer_to(MASTER)),
+        eip1559(TOKEN, U256::ZERO, [transfer_to(MASTER), vec![0; 32]].concat()),
+        TypedTransaction::Legacy(TxLegacy { chain_id: None, to: TxKind::Call(MASTER), ..Default::default() }),
+    ];
+
+    for tx in refused {
+        let result = signer.sign(&key, tx.clone()).await;
+        assert!(result.is_err(), "signed a transaction outside the policy: {:?}", tx);
+    }
+}
+
+
+#[actix_web::test]
+async fn rejects_clients_without_the_token() {
+    let signer = RemoteSigner::new(&start_signer(), None);
+
+    assert!(signer.address(&wallet_key()).await.is_err());
+}
+
+
+#[test]
+fn policy_refuses_contract_creation() {
+    let policy = SigningPolicy::new(MASTER, []);
+    let mut create = eip1559(MASTER, U256::ZERO, Vec::new());
+    if let TypedTransaction::Eip1559(tx) = &mut create {
+        tx.to = TxKind::Create;
+    }
+
+    assert!(policy.check(&create).is_err());
+    assert!(policy.check(&eip1559(MASTER, U256::ZERO, Vec::new())).is_ok());
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use alloy::network::{Ethereum, NetworkWallet};
+use alloy_signer_local::PrivateKeySigner;
+use avitus_casino_sweeper::chain_config::signer_registry::SignerRegistry;
+
+
+fn holds(registry: &SignerRegistry, signer: &PrivateKeySigner) -> bool {
+    NetworkWallet::<Ethereum>::has_signer_for(registry, &signer.address())
+}
+
+
+#[test]
+fn evicts_the_least_recently_used_signer_past_capacity() {
+    let registry = SignerRegistry::bounded(2, Duration::from_secs(3600));
+    let (first, second, third) = (PrivateKeySigner::random(), PrivateKeySigner::random(), PrivateKeySigner::random());
+
+    registry.register(first.clone());
+    registry.register(second.clone());
+    // Registering again marks the first signer as used, so the second is now the oldest
+    registry.register(first.clone());
+    registry.register(third.clone());
+
+    assert!(holds(&registry, &first));
+    assert!(!holds(&registry, &second));
+    assert!(holds(&registry, &third));
+}
+
+
+#[test]
+fn drops_signers_left_unused_past_the_idle_ttl() {
+    let registry = SignerRegistry::bounded(16, Duration::from_millis(50));
+    let (idle, fresh) = (PrivateKeySigner::random(), PrivateKeySigner::random());
+
+    registry.register(idle.clone());
+    std::thread::sleep(Duration::from_millis(100));
+    registry.register(fresh.clone());
+
+    assert!(!holds(&registry, &idle));
+    assert!(holds(&registry, &fresh));
+}
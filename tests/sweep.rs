@@ -0,0 +1,192 @@
+mod common;
+
+use alloy::primitives::{Address, U256};
+use avitus_casino_sweeper::{
+    chain_config::registry::registry,
+    entities::{deposit_receipt, pending_deposit, prelude::{DepositReceipt, PendingDeposit, UserBalance}, sea_orm_active_enums::WalletStatus, user_balance},
+    jobs::{confirmations::track_chain, reaper::rearm_parked_wallets, sweeper::sweep_wallet},
+    utils::token_decimals::u256_to_decimal,
+};
+use rust_decimal::Decimal;
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
+use uuid::Uuid;
+
+use common::{CHAIN, TestEnv};
+
+const USDC_DEPOSIT: u64 = 250_000_000; // 250 USDC
+const USDT_DEPOSIT: u64 = 100_500_000; // 100.5 USDT
+const GAS_DEPOSIT: u64 = 1_000_000_000_000_000_000; // 1 ETH
+
+
+/// One sweep cycle plus one confirmation pass, the way the workers run them.
+async fn sweep_and_confirm(env: &TestEnv) {
+    sweep_wallet(CHAIN, 0, &env.db).await.expect("sweep cycle");
+
+    let registry = registry();
+    let chain = registry.chain(CHAIN).expect("anvil in registry");
+    track_chain(chain, &env.db).await.expect("confirmation pass");
+}
+
+
+async fn credited(env: &TestEnv, user_id: Uuid, token: Address) -> Decimal {
+    UserBalance::find()
+        .filter(user_balance::Column::Userid.eq(user_id))
+        .filter(user_balance::Column::Token.eq(token.to_string()))
+        .filter(user_balance::Column::Chain.eq(CHAIN))
+        .one(&env.db.0)
+        .await
+        .expect("load balance")
+        .map_or(Decimal::ZERO, |balance| balance.balance)
+}
+
+
+async fn receipts(env: &TestEnv, user_id: Uuid) -> u64 {
+    DepositReceipt::find()
+        .filter(deposit_receipt::Column::Userid.eq(user_id.to_string()))
+        .count(&env.db.0)
+        .await
+        .expect("count receipts")
+}
+
+
+#[tokio::test]
+async fn sweeps_tokens_and_native_and_credits_them_once() {
+    let Some(env) = TestEnv::start().await else { return };
+
+    let (user_id, wallet) = env.create_wallet().await;
+    let deposit_address: Address = wallet.wallet_address.parse().unwrap();
+
+    env.mint(env.usdc, deposit_address, U256::from(USDC_DEPOSIT)).await;
+    env.mint(env.usdt, deposit_address, U256::from(USDT_DEPOSIT)).await;
+    env.send_native(deposit_address, U256::from(GAS_DEPOSIT)).await;
+
+    sweep_and_confirm(&env).await;
+
+    // Every asset left the deposit wallet for the master wallet
+    assert_eq!(env.token_balance(env.usdc, deposit_address).await, U256::ZERO);
+    assert_eq!(env.token_balance(env.usdt, deposit_address).await, U256::ZERO);
+    assert_eq!(env.token_balance(env.usdc, env.master).await, U256::from(USDC_DEPOSIT));
+    assert_eq!(env.token_balance(env.usdt, env.master).await, U256::from(USDT_DEPOSIT));
+
+    let native_swept = env.native_balance(env.master).await;
+    assert!(native_swept > U256::ZERO && native_swept < U256::from(GAS_DEPOSIT));
+
+    // ...and was credited at its exact on-chain amount
+    assert_eq!(credited(&env, user_id, env.usdc).await, Decimal::new(250, 0));
+    assert_eq!(credited(&env, user_id, env.usdt).await, Decimal::new(1005, 1));
+    assert_eq!(credited(&env, user_id, Address::ZERO).await, u256_to_decimal(native_swept, 18).unwrap());
+    assert_eq!(receipts(&env, user_id).await, 3);
+
+    let pending = PendingDeposit::find()
+        .filter(pending_deposit::Column::UserId.eq(user_id))
+        .filter(pending_deposit::Column::Status.eq("PENDING"))
+        .count(&env.db.0)
+        .await
+        .unwrap();
+    assert_eq!(pending, 0);
+    assert_eq!(env.wallet(wallet.id).await.status, WalletStatus::Free);
+
+    // A duplicate queue entry and repeated confirmation passes credit nothing twice
+    env.queue_wallet(wallet.id).await;
+    sweep_and_confirm(&env).await;
+    sweep_and_confirm(&env).await;
+
+    assert_eq!(credited(&env, user_id, env.usdc).await, Decimal::new(250, 0));
+    assert_eq!(credited(&env, user_id, env.usdt).await, Decimal::new(1005, 1));
+    assert_eq!(credited(&env, user_id, Address::ZERO).await, u256_to_decimal(native_swept, 18).unwrap());
+    assert_eq!(receipts(&env, user_id).await, 3);
+    assert_eq!(env.native_balance(env.master).await, native_swept);
+
+    env.teardown().await;
+}
+
+
+#[tokio::test]
+async fn leaves_wallets_below_the_sweep_threshold_in_place() {
+    let Some(env) = TestEnv::start().await else { return };
+
+    let (user_id, wallet) = env.create_wallet().await;
+    let deposit_address: Address = wallet.wallet_address.parse().unwrap();
+
+    // Below the 1 USDC minimum; the default dust policy leaves it accumulating
+    env.mint(env.usdc, deposit_address, U256::from(500_000u64)).await;
+    env.send_native(deposit_address, U256::from(GAS_DEPOSIT)).await;
+
+    sweep_and_confirm(&env).await;
+
+    assert_eq!(env.token_balance(env.usdc, deposit_address).await, U256::from(500_000u64));
+    assert_eq!(credited(&env, user_id, env.usdc).await, Decimal::ZERO);
+
+    env.teardown().await;
+}
+
+
+#[tokio::test]
+async fn requeues_parked_wallets_once_their_retry_time_passes() {
+    let Some(env) = TestEnv::start().await else { return };
+
+    let (user_id, wallet) = env.create_wallet().await;
+    let deposit_address: Address = wallet.wallet_address.parse().unwrap();
+
+    // Tokens but no gas, and the gas station is disabled in tests
+    env.mint(env.usdc, deposit_address, U256::from(USDC_DEPOSIT)).await;
+    sweep_and_confirm(&env).await;
+
+    let parked = env.wallet(wallet.id).await;
+    assert_eq!(parked.status, WalletStatus::GasPending);
+    assert!(parked.next_attempt_at.is_some_and(|retry_at| retry_at > chrono::Utc::now()));
+
+    // Not due yet
+    assert_eq!(rearm_parked_wallets(&env.db).await.expect("re-arm pass"), 0);
+
+    // The gas arrives and the retry time passes
+    env.send_native(deposit_address, U256::from(GAS_DEPOSIT)).await;
+    env.execute(&format!("UPDATE user_wallet SET next_attempt_at = now() - interval '1 minute' WHERE id = '{}'", wallet.id)).await;
+
+    assert_eq!(rearm_parked_wallets(&env.db).await.expect("re-arm pass"), 1);
+    assert_eq!(env.wallet(wallet.id).await.status, WalletStatus::Sweepable);
+
+    sweep_and_confirm(&env).await;
+
+    assert_eq!(env.token_balance(env.usdc, deposit_address).await, U256::ZERO);
+    assert_eq!(credited(&env, user_id, env.usdc).await, Decimal::new(250, 0));
+
+    // A failed wallet without a retry time is re-queued on the next pass
+    let (_, failed) = env.create_wallet().await;
+    env.execute(&format!("UPDATE user_wallet SET status = 'FAILED', next_attempt_at = NULL WHERE id = '{}'", failed.id)).await;
+
+    assert_eq!(rearm_parked_wallets(&env.db).await.expect("re-arm pass"), 1);
+    assert_eq!(env.wallet(failed.id).await.status, WalletStatus::Sweepable);
+
+    env.teardown().await;
+}
+
+
+#[tokio::test]
+async fn keeps_wallets_with_a_sweep_in_flight_confirming() {
+    let Some(env) = TestEnv::start().await else { return };
+
+    let (user_id, wallet) = env.create_wallet().await;
+    let deposit_address: Address = wallet.wallet_address.parse().unwrap();
+
+    env.mint(env.usdc, deposit_address, U256::from(USDC_DEPOSIT)).await;
+
+    // An earlier USDC sweep that has not been mined yet
+    env.execute(&format!(
+        "INSERT INTO sweep_attempt (id, user_id, wallet_id, wallet_address, chain, token, amount, nonce, raw_tx, tx_hash, state)
+         VALUES ('{}', '{user_id}', '{}', '{deposit_address}', '{CHAIN}', '{}', 250, 0, '00', '0x{}', 'BROADCAST')",
+        Uuid::new_v4(),
+        wallet.id,
+        env.usdc,
+        "11".repeat(32),
+    ))
+    .await;
+
+    sweep_wallet(CHAIN, 0, &env.db).await.expect("sweep cycle");
+
+    // Nothing new was signed, and the wallet waits on the attempt instead of going `FREE`
+    assert_eq!(env.token_balance(env.usdc, deposit_address).await, U256::from(USDC_DEPOSIT));
+    assert_eq!(env.wallet(wallet.id).await.status, WalletStatus::Confirming);
+
+    env.teardown().await;
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use alloy::{
+    network::TransactionBuilder,
+    primitives::{Address, U256, address},
+    rpc::types::TransactionRequest,
+    sol,
+    sol_types::SolCall,
+};
+use avitus_casino_sweeper::{
+    chain_config::registry::{ChainEntry, TokenEntry},
+    config::config::parse_master_address,
+    utils::{sweep_outbox::SweepIntent, tx_policy::check_sweep},
+};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use common::registry::{TESTNET, chain_entry, token_entries};
+
+sol! {
+    interface IToken {
+        function transfer(address to, uint256 amount) external returns (bool);
+    }
+}
+
+const TREASURY: Address = address!("0x1000000000000000000000000000000000000001");
+const TOKEN: Address = address!("0x2000000000000000000000000000000000000002");
+const WALLET: Address = address!("0x3000000000000000000000000000000000000003");
+const STRANGER: Address = address!("0x6666666666666666666666666666666666666666");
+
+
+fn chain() -> ChainEntry {
+    chain_entry(TREASURY)
+}
+
+
+fn tokens() -> Vec<TokenEntry> {
+    token_entries(TOKEN)
+}
+
+
+fn intent(token: Address, amount: Decimal) -> SweepIntent<'static> {
+    SweepIntent { user_id: Uuid::new_v4(), wallet_id: Uuid::new_v4(), wallet_address: WALLET, chain: TESTNET, token, amount }
+}
+
+
+fn token_transfer(to: Address, units: u64) -> TransactionRequest {
+    TransactionRequest::default()
+        .with_from(WALLET)
+        .with_to(TOKEN)
+        .with_input(IToken::transferCall { to, amount: U256::from(units) }.abi_encode())
+        .with_chain_id(31337)
+}
+
+
+#[test]
+fn accepts_sweeps_to_the_treasury() {
+    let (chain, tokens) = (chain(), tokens());
+
+    let token_sweep = token_transfer(TREASURY, 2_500_000);
+    assert_eq!(check_sweep(&chain, &tokens, &intent(TOKEN, Decimal::new(25, 1)), &token_sweep), Ok(()));
+
+    let native_sweep = TransactionRequest::default()
+        .with_from(WALLET)
+        .with_to(TREASURY)
+        .with_value(U256::from(500_000_000_000_000_000u64))
+        .with_chain_id(31337);
+    assert_eq!(check_sweep(&chain, &tokens, &intent(Address::ZERO, Decimal::new(5, 1)), &native_sweep), Ok(()));
+}
+
+
+#[test]
+fn refuses_anything_else() {
+    let (chain, tokens) = (chain(), tokens());
+    let token_intent = intent(TOKEN, Decimal::new(25, 1));
+
+    let refused = [
+        token_transfer(STRANGER, 2_500_000),
+        token_transfer(TREASURY, 2_500_001),
+        token_transfer(TREASURY, 2_500_000).with_value(U256::from(1u64)),
+        token_transfer(TREASURY, 2_500_000).with_chain_id(1),
+        token_transfer(TREASURY, 2_500_000).with_from(STRANGER),
+        token_transfer(TREASURY, 2_500_000).with_to(STRANGER),
+    ];
+
+    for tx in refused {
+        assert!(check_sweep(&chain, &tokens, &token_intent, &tx).is_err(), "accepted {:?}", tx);
+    }
+
+    let native_to_stranger = TransactionRequest::default()
+        .with_from(WALLET)
+        .with_to(STRANGER)
+        .with_value(U256::from(500_000_000_000_000_000u64));
+    assert!(check_sweep(&chain, &tokens, &intent(Address::ZERO, Decimal::new(5, 1)), &native_to_stranger).is_err());
+}
+
+
+#[test]
+fn validates_the_master_address() {
+    assert_eq!(parse_master_address(" 0x1000000000000000000000000000000000000001 ").unwrap(), TREASURY);
+    assert!(parse_master_address("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").is_ok());
+    assert!(parse_master_address("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92267").is_err());
+    assert!(parse_master_address("0xF39fd6e51aad88F6F4ce6aB8827279cffFb92266").is_err());
+    assert!(parse_master_address("0x0000000000000000000000000000000000000000").is_err());
+    assert!(parse_master_address("not an address").is_err());
+}
@@ -0,0 +1,180 @@
+mod common;
+
+use alloy::primitives::{Address, U256, address};
+use alloy_signer_local::PrivateKeySigner;
+use avitus_casino_sweeper::{
+    chain_config::registry::{ChainEntry, TokenEntry},
+    entities::{prelude::{UserBalance, WithdrawReceipt, WithdrawRequest}, user_balance, withdraw_receipt, withdraw_request},
+    jobs::withdrawals::{process_withdrawals, validate_withdrawal},
+    utils::token_decimals::decimal_to_u256,
+};
+use rust_decimal::Decimal;
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
+use uuid::Uuid;
+
+use common::{CHAIN, TestEnv, registry::{TESTNET, chain_entry, token_entries}};
+
+const HOT: Address = address!("0x1000000000000000000000000000000000000001");
+const TOKEN: Address = address!("0x2000000000000000000000000000000000000002");
+const USER: Address = address!("0x3000000000000000000000000000000000000003");
+
+
+fn chain() -> ChainEntry {
+    chain_entry(HOT)
+}
+
+
+fn tokens() -> Vec<TokenEntry> {
+    token_entries(TOKEN)
+}
+
+
+fn request(recipient: &str, token: &str, amount: Decimal) -> withdraw_request::Model {
+    withdraw_request::Model {
+        id: Uuid::new_v4(),
+        userid: Uuid::new_v4(),
+        user_address: recipient.into(),
+        token: token.into(),
+        chain: TESTNET.into(),
+        amount,
+        status: "PENDING".into(),
+        sender: None,
+        nonce: None,
+        raw_tx: None,
+        tx_hash: None,
+        failure_reason: None,
+        dropped_at_block: None,
+        replaced_tx_hashes: None,
+        updated_at: chrono::Utc::now().into(),
+        created_at: chrono::Utc::now().into(),
+    }
+}
+
+
+#[test]
+fn validates_token_and_native_withdrawals() {
+    let transfer = validate_withdrawal(&chain(), &tokens(), HOT, &request(&USER.to_string(), &TOKEN.to_string(), Decimal::new(125, 1)))
+        .expect("token withdrawal is valid");
+    assert_eq!((transfer.recipient, transfer.token, transfer.raw_amount), (USER, TOKEN, U256::from(12_500_000u64)));
+
+    // Lower-case addresses from the application are accepted
+    let native = request(&USER.to_string().to_lowercase(), &Address::ZERO.to_string(), Decimal::new(1, 3));
+    let transfer = validate_withdrawal(&chain(), &tokens(), HOT, &native).expect("native withdrawal is valid");
+    assert_eq!((transfer.token, transfer.raw_amount), (Address::ZERO, U256::from(1_000_000_000_000_000u64)));
+}
+
+
+#[test]
+fn rejects_invalid_withdrawals() {
+    let token = TOKEN.to_string();
+    let invalid = [
+        request("not an address", &token, Decimal::ONE),
+        request(&Address::ZERO.to_string(), &token, Decimal::ONE),
+        request(&HOT.to_string(), &token, Decimal::ONE),
+        request(&USER.to_string(), "0x6666666666666666666666666666666666666666", Decimal::ONE),
+        request(&USER.to_string(), &token, Decimal::ZERO),
+        request(&USER.to_string(), &token, Decimal::NEGATIVE_ONE),
+        // Finer than USDC's 6 decimals
+        request(&USER.to_string(), &token, Decimal::new(1, 7)),
+        withdraw_request::Model { chain: "othernet".into(), ..request(&USER.to_string(), &token, Decimal::ONE) },
+    ];
+
+    for request in invalid {
+        assert!(validate_withdrawal(&chain(), &tokens(), HOT, &request).is_err(), "{:?} should be rejected", request);
+    }
+}
+
+
+#[test]
+fn converts_decimal_amounts_to_raw_units() {
+    assert_eq!(decimal_to_u256(Decimal::new(100_500, 3), 6).unwrap(), U256::from(100_500_000u64));
+    assert_eq!(decimal_to_u256(Decimal::new(1_000, 3), 0).unwrap(), U256::from(1u64));
+    assert_eq!(decimal_to_u256(Decimal::ZERO, 18).unwrap(), U256::ZERO);
+    assert!(decimal_to_u256(Decimal::new(15, 1), 0).is_err());
+    assert!(decimal_to_u256(Decimal::NEGATIVE_ONE, 18).is_err());
+}
+
+
+async fn balance(env: &TestEnv, user_id: Uuid, token: Address) -> Decimal {
+    UserBalance::find()
+        .filter(user_balance::Column::Userid.eq(user_id))
+        .filter(user_balance::Column::Token.eq(token.to_string()))
+        .filter(user_balance::Column::Chain.eq(CHAIN))
+        .one(&env.db.0)
+        .await
+        .expect("load balance")
+        .map_or(Decimal::ZERO, |balance| balance.balance)
+}
+
+
+async fn queue_withdrawal(env: &TestEnv, user_id: Uuid, recipient: Address, token: Address, amount: Decimal) -> Uuid {
+    let id = Uuid::new_v4();
+    env.execute(&format!(
+        "INSERT INTO withdraw_request (id, userid, user_address, token, chain, amount, status)
+         VALUES ('{id}', '{user_id}', '{recipient}', '{token}', '{CHAIN}', {amount}, 'PENDING')"
+    ))
+    .await;
+
+    id
+}
+
+
+async fn withdrawal(env: &TestEnv, id: Uuid) -> withdraw_request::Model {
+    WithdrawRequest::find_by_id(id).one(&env.db.0).await.expect("load withdrawal").expect("withdrawal exists")
+}
+
+
+#[tokio::test]
+async fn pays_out_withdrawals_and_rejects_overdrafts() {
+    let Some(env) = TestEnv::start().await else { return };
+
+    let user_id = env.create_user().await;
+    env.execute(&format!(
+        "INSERT INTO user_balance (id, userid, token, chain, balance) VALUES ('{}', '{user_id}', '{}', '{CHAIN}', 100)",
+        Uuid::new_v4(),
+        env.usdc,
+    ))
+    .await;
+
+    // The hot wallet holds the tokens and the gas to move them
+    env.mint(env.usdc, env.master, U256::from(500_000_000u64)).await;
+    env.send_native(env.master, U256::from(1_000_000_000_000_000_000u64)).await;
+
+    let recipient = PrivateKeySigner::random().address();
+    // More than the hot wallet holds: it waits without holding up the requests behind it
+    let uncovered = queue_withdrawal(&env, user_id, recipient, env.usdc, Decimal::new(1_000, 0)).await;
+    let paid = queue_withdrawal(&env, user_id, recipient, env.usdc, Decimal::new(40, 0)).await;
+    let overdraft = queue_withdrawal(&env, user_id, recipient, env.usdc, Decimal::new(70, 0)).await;
+
+    // First cycle debits and broadcasts, the second sees it mined and completes it
+    process_withdrawals(CHAIN, &env.db, &env.master_key).await.expect("withdrawal cycle");
+    process_withdrawals(CHAIN, &env.db, &env.master_key).await.expect("withdrawal cycle");
+
+    assert_eq!(env.token_balance(env.usdc, recipient).await, U256::from(40_000_000u64));
+    assert_eq!(balance(&env, user_id, env.usdc).await, Decimal::new(60, 0));
+
+    let paid = withdrawal(&env, paid).await;
+    assert_eq!(paid.status, "COMPLETED");
+    assert_eq!(paid.sender, Some(env.master.to_string()));
+
+    let overdraft = withdrawal(&env, overdraft).await;
+    assert_eq!(overdraft.status, "REJECTED");
+    assert_eq!(overdraft.failure_reason.as_deref(), Some("insufficient balance"));
+
+    assert_eq!(withdrawal(&env, uncovered).await.status, "PENDING");
+
+    let receipts = WithdrawReceipt::find()
+        .filter(withdraw_receipt::Column::Userid.eq(user_id.to_string()))
+        .filter(withdraw_receipt::Column::TxnHash.eq(paid.tx_hash.clone().expect("tx hash stored")))
+        .count(&env.db.0)
+        .await
+        .expect("count receipts");
+    assert_eq!(receipts, 1);
+
+    // Further cycles pay nothing twice
+    process_withdrawals(CHAIN, &env.db, &env.master_key).await.expect("withdrawal cycle");
+    assert_eq!(env.token_balance(env.usdc, recipient).await, U256::from(40_000_000u64));
+    assert_eq!(balance(&env, user_id, env.usdc).await, Decimal::new(60, 0));
+
+    env.teardown().await;
+}